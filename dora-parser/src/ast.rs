@@ -223,6 +223,7 @@ pub struct Global {
     pub span: Span,
     pub name: Name,
     pub reassignable: bool,
+    pub thread_local: bool,
     pub data_type: Type,
     pub initializer: Option<Function>,
 }
@@ -474,6 +475,7 @@ pub struct Trait {
     pub name: Name,
     pub pos: Position,
     pub span: Span,
+    pub type_params: Option<Vec<TypeParam>>,
     pub methods: Vec<Function>,
 }
 
@@ -587,6 +589,7 @@ pub struct Function {
     pub is_test: bool,
     pub use_cannon: bool,
     pub internal: bool,
+    pub external: Option<ExternSymbol>,
     pub is_constructor: bool,
 
     pub params: Vec<Param>,
@@ -602,6 +605,14 @@ impl Function {
     }
 }
 
+// symbol in a shared library given with @extern("library", "symbol"),
+// defaults to the function's name when the symbol is omitted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExternSymbol {
+    pub library: String,
+    pub symbol: Option<String>,
+}
+
 #[derive(Clone, Debug)]
 pub struct Modifiers(Vec<ModifierElement>);
 
@@ -614,14 +625,22 @@ impl Modifiers {
         self.0.iter().find(|el| el.value == modifier).is_some()
     }
 
-    pub fn add(&mut self, modifier: Modifier, pos: Position, span: Span) {
+    pub fn add(&mut self, modifier: Modifier, args: Vec<String>, pos: Position, span: Span) {
         self.0.push(ModifierElement {
             value: modifier,
+            args,
             pos,
             span,
         });
     }
 
+    pub fn args(&self, modifier: Modifier) -> Option<&[String]> {
+        self.0
+            .iter()
+            .find(|el| el.value == modifier)
+            .map(|el| &el.args[..])
+    }
+
     pub fn iter(&self) -> Iter<ModifierElement> {
         self.0.iter()
     }
@@ -630,6 +649,7 @@ impl Modifiers {
 #[derive(Clone, Debug)]
 pub struct ModifierElement {
     pub value: Modifier,
+    pub args: Vec<String>,
     pub pos: Position,
     pub span: Span,
 }
@@ -647,6 +667,8 @@ pub enum Modifier {
     Test,
     Cannon,
     OptimizeImmediately,
+    ThreadLocal,
+    Extern,
 }
 
 impl Modifier {
@@ -663,6 +685,8 @@ impl Modifier {
             Modifier::Test => "test",
             Modifier::Cannon => "cannon",
             Modifier::OptimizeImmediately => "optimize_immediately",
+            Modifier::ThreadLocal => "threadlocal",
+            Modifier::Extern => "extern",
         }
     }
 }
@@ -687,6 +711,7 @@ pub enum Stmt {
     StmtContinue(StmtContinueType),
     StmtReturn(StmtReturnType),
     StmtFor(StmtForType),
+    StmtSync(StmtSyncType),
 }
 
 impl Stmt {
@@ -747,6 +772,23 @@ impl Stmt {
         })
     }
 
+    pub fn create_sync(
+        id: NodeId,
+        pos: Position,
+        span: Span,
+        mutex: Box<Expr>,
+        block: Box<Stmt>,
+    ) -> Stmt {
+        Stmt::StmtSync(StmtSyncType {
+            id,
+            pos,
+            span,
+
+            mutex,
+            block,
+        })
+    }
+
     pub fn create_expr(id: NodeId, pos: Position, span: Span, expr: Box<Expr>) -> Stmt {
         Stmt::StmtExpr(StmtExprType {
             id,
@@ -784,6 +826,7 @@ impl Stmt {
             Stmt::StmtBreak(ref stmt) => stmt.id,
             Stmt::StmtContinue(ref stmt) => stmt.id,
             Stmt::StmtReturn(ref stmt) => stmt.id,
+            Stmt::StmtSync(ref stmt) => stmt.id,
         }
     }
 
@@ -796,6 +839,7 @@ impl Stmt {
             Stmt::StmtBreak(ref stmt) => stmt.pos,
             Stmt::StmtContinue(ref stmt) => stmt.pos,
             Stmt::StmtReturn(ref stmt) => stmt.pos,
+            Stmt::StmtSync(ref stmt) => stmt.pos,
         }
     }
 
@@ -808,6 +852,7 @@ impl Stmt {
             Stmt::StmtBreak(ref stmt) => stmt.span,
             Stmt::StmtContinue(ref stmt) => stmt.span,
             Stmt::StmtReturn(ref stmt) => stmt.span,
+            Stmt::StmtSync(ref stmt) => stmt.span,
         }
     }
 
@@ -853,6 +898,20 @@ impl Stmt {
         }
     }
 
+    pub fn to_sync(&self) -> Option<&StmtSyncType> {
+        match *self {
+            Stmt::StmtSync(ref val) => Some(val),
+            _ => None,
+        }
+    }
+
+    pub fn is_sync(&self) -> bool {
+        match *self {
+            Stmt::StmtSync(_) => true,
+            _ => false,
+        }
+    }
+
     pub fn to_expr(&self) -> Option<&StmtExprType> {
         match *self {
             Stmt::StmtExpr(ref val) => Some(val),
@@ -944,6 +1003,16 @@ pub struct StmtWhileType {
     pub block: Box<Stmt>,
 }
 
+#[derive(Clone, Debug)]
+pub struct StmtSyncType {
+    pub id: NodeId,
+    pub pos: Position,
+    pub span: Span,
+
+    pub mutex: Box<Expr>,
+    pub block: Box<Stmt>,
+}
+
 #[derive(Clone, Debug)]
 pub struct StmtExprType {
     pub id: NodeId,
@@ -1243,13 +1312,20 @@ impl Expr {
         })
     }
 
-    pub fn create_template(id: NodeId, pos: Position, span: Span, parts: Vec<Box<Expr>>) -> Expr {
+    pub fn create_template(
+        id: NodeId,
+        pos: Position,
+        span: Span,
+        parts: Vec<Box<Expr>>,
+        specs: Vec<Option<FormatSpec>>,
+    ) -> Expr {
         Expr::ExprTemplate(ExprTemplateType {
             id,
             pos,
             span,
 
             parts,
+            specs,
         })
     }
 
@@ -1897,6 +1973,75 @@ pub struct ExprTemplateType {
     pub span: Span,
 
     pub parts: Vec<Box<Expr>>,
+
+    // format specifier for each part, always None for the string literals
+    pub specs: Vec<Option<FormatSpec>>,
+}
+
+// Format specifier of a template part like `${value:>8.3}`:
+// `[[fill]align][+][#][0][width][.precision][kind]`
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormatSpec {
+    pub pos: Position,
+    pub fill: char,
+    pub align: FormatAlign,
+    pub sign: bool,
+    pub alternate: bool,
+    pub zero: bool,
+    pub width: Option<u32>,
+    pub precision: Option<u32>,
+    pub kind: FormatKind,
+}
+
+impl FormatSpec {
+    pub fn is_radix(&self) -> bool {
+        match self.kind {
+            FormatKind::Binary | FormatKind::Octal | FormatKind::Hex | FormatKind::UpperHex => {
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_exponential(&self) -> bool {
+        match self.kind {
+            FormatKind::Exp | FormatKind::UpperExp => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FormatAlign {
+    Default,
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FormatKind {
+    Default,
+    Binary,
+    Octal,
+    Hex,
+    UpperHex,
+    Exp,
+    UpperExp,
+}
+
+impl FormatKind {
+    pub fn to_char(self) -> char {
+        match self {
+            FormatKind::Default => ' ',
+            FormatKind::Binary => 'b',
+            FormatKind::Octal => 'o',
+            FormatKind::Hex => 'x',
+            FormatKind::UpperHex => 'X',
+            FormatKind::Exp => 'e',
+            FormatKind::UpperExp => 'E',
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -374,6 +374,7 @@ impl<'a> AstDumper<'a> {
             StmtVar(ref stmt) => self.dump_stmt_var(stmt),
             StmtWhile(ref stmt) => self.dump_stmt_while(stmt),
             StmtFor(ref stmt) => self.dump_stmt_for(stmt),
+            StmtSync(ref stmt) => self.dump_stmt_sync(stmt),
         }
     }
 
@@ -439,6 +440,22 @@ impl<'a> AstDumper<'a> {
         });
     }
 
+    fn dump_stmt_sync(&mut self, stmt: &StmtSyncType) {
+        dump!(self, "synchronized @ {} {}", stmt.pos, stmt.id);
+
+        self.indent(|d| {
+            dump!(d, "mutex");
+            d.indent(|d| {
+                d.dump_expr(&stmt.mutex);
+            });
+
+            dump!(d, "body");
+            d.indent(|d| {
+                d.dump_stmt(&stmt.block);
+            });
+        });
+    }
+
     fn dump_stmt_expr(&mut self, stmt: &StmtExprType) {
         dump!(self, "expr stmt @ {} {}", stmt.pos, stmt.id);
         self.indent(|d| {
@@ -593,8 +610,12 @@ impl<'a> AstDumper<'a> {
     fn dump_expr_template(&mut self, tmpl: &ExprTemplateType) {
         dump!(self, "template @ {} {}", tmpl.pos, tmpl.id);
         self.indent(|d| {
-            for part in &tmpl.parts {
-                d.dump_expr(part)
+            for (part, spec) in tmpl.parts.iter().zip(&tmpl.specs) {
+                d.dump_expr(part);
+
+                if let Some(ref spec) = spec {
+                    dump!(d, "format spec {:?} @ {}", spec, spec.pos);
+                }
             }
         });
     }
@@ -250,6 +250,11 @@ pub fn walk_stmt<'v, V: Visitor<'v>>(v: &mut V, s: &'v Stmt) {
             v.visit_stmt(&value.block);
         }
 
+        StmtSync(ref value) => {
+            v.visit_expr(&value.mutex);
+            v.visit_stmt(&value.block);
+        }
+
         StmtExpr(ref value) => {
             v.visit_expr(&value.expr);
         }
@@ -165,6 +165,7 @@ impl<'a> BuilderFct<'a> {
             is_static: false,
             is_abstract: false,
             internal: false,
+            external: None,
             is_constructor: self.is_constructor,
             is_test: false,
             use_cannon: self.use_cannon,
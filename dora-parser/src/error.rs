@@ -20,7 +20,10 @@ pub enum ParseError {
     ExpectedFactor(String),
     NumberOverflow,
     UnclosedStringTemplate,
+    InvalidFormatSpec(String),
     ExpectedIdentifier(String),
+    ExpectedString(String),
+    InvalidAnnotationArguments(String),
 }
 
 impl ParseError {
@@ -53,9 +56,16 @@ impl ParseError {
             ParseError::MisplacedElse => "misplace else.".into(),
             ParseError::ExpectedFactor(ref got) => format!("factor expected but got {}.", got),
             ParseError::UnclosedStringTemplate => "unclosed string template.".into(),
+            ParseError::InvalidFormatSpec(ref spec) => {
+                format!("invalid format specifier `{}`.", spec)
+            }
             ParseError::ExpectedIdentifier(ref tok) => {
                 format!("identifier expected but got {}.", tok)
             }
+            ParseError::ExpectedString(ref tok) => format!("string expected but got {}.", tok),
+            ParseError::InvalidAnnotationArguments(ref modifier) => {
+                format!("invalid arguments for annotation `{}`.", modifier)
+            }
         }
     }
 }
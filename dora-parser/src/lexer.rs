@@ -226,6 +226,29 @@ impl Lexer {
         self.read_string(false)
     }
 
+    // Reads the raw format specifier after the `:` of a template part up to
+    // and including the closing `}`, e.g. `>8.3}` for `${x:>8.3}`.
+    pub fn read_format_spec(&mut self) -> Result<(String, Position), ParseErrorAndPos> {
+        let pos = self.reader.pos();
+        let mut value = String::new();
+
+        while let Some(ch) = self.curr() {
+            if ch == '}' {
+                self.read_char();
+                return Ok((value, pos));
+            }
+
+            if is_quote(Some(ch)) || is_newline(Some(ch)) {
+                break;
+            }
+
+            value.push(ch);
+            self.read_char();
+        }
+
+        Err(ParseErrorAndPos::new(pos, ParseError::UnclosedStringTemplate))
+    }
+
     fn read_operator(&mut self) -> Result<Token, ParseErrorAndPos> {
         let pos = self.reader.pos();
         let idx = self.reader.idx();
@@ -582,6 +605,7 @@ fn keywords_in_map() -> HashMap<&'static str, TokenKind> {
     keywords.insert("in", TokenKind::In);
     keywords.insert("break", TokenKind::Break);
     keywords.insert("continue", TokenKind::Continue);
+    keywords.insert("synchronized", TokenKind::Synchronized);
 
     // qualifiers
     keywords.insert("self", TokenKind::This);
@@ -1035,6 +1059,9 @@ mod tests {
         assert_tok(&mut reader, TokenKind::Return, 1, 16);
         assert_tok(&mut reader, TokenKind::Nil, 1, 23);
 
+        let mut reader = Lexer::from_str("synchronized");
+        assert_tok(&mut reader, TokenKind::Synchronized, 1, 1);
+
         let mut reader = Lexer::from_str("type struct enum alias trait const");
         assert_tok(&mut reader, TokenKind::Type, 1, 1);
         assert_tok(&mut reader, TokenKind::Struct, 1, 6);
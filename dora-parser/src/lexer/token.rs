@@ -40,6 +40,7 @@ pub enum TokenKind {
     In,
     Break,
     Continue,
+    Synchronized,
 
     // qualifiers
     This,
@@ -150,6 +151,7 @@ impl TokenKind {
             TokenKind::In => "in",
             TokenKind::Break => "break",
             TokenKind::Continue => "continue",
+            TokenKind::Synchronized => "synchronized",
 
             // qualifiers
             TokenKind::This => "self",
@@ -105,6 +105,7 @@ impl<'a> Parser<'a> {
                         Modifier::OptimizeImmediately,
                         Modifier::Test,
                         Modifier::Cannon,
+                        Modifier::Extern,
                     ],
                 )?;
                 let fct = self.parse_function(&modifiers)?;
@@ -156,8 +157,8 @@ impl<'a> Parser<'a> {
             }
 
             TokenKind::Let | TokenKind::Var => {
-                self.ban_modifiers(&modifiers)?;
-                self.parse_global(elements)?;
+                self.restrict_modifiers(&modifiers, &[Modifier::ThreadLocal])?;
+                self.parse_global(&modifiers, elements)?;
             }
 
             TokenKind::Const => {
@@ -289,7 +290,11 @@ impl<'a> Parser<'a> {
         })
     }
 
-    fn parse_global(&mut self, elements: &mut Vec<Elem>) -> Result<(), ParseErrorAndPos> {
+    fn parse_global(
+        &mut self,
+        modifiers: &Modifiers,
+        elements: &mut Vec<Elem>,
+    ) -> Result<(), ParseErrorAndPos> {
         let start = self.token.span.start();
         let pos = self.token.position;
         let reassignable = self.token.is(TokenKind::Var);
@@ -317,6 +322,7 @@ impl<'a> Parser<'a> {
             span,
             data_type,
             reassignable,
+            thread_local: modifiers.contains(Modifier::ThreadLocal),
             initializer: None,
         };
 
@@ -334,6 +340,7 @@ impl<'a> Parser<'a> {
         let start = self.token.span.start();
         let pos = self.expect_token(TokenKind::Trait)?.position;
         let ident = self.expect_identifier()?;
+        let type_params = self.parse_type_params()?;
 
         self.expect_token(TokenKind::LBrace)?;
 
@@ -355,6 +362,7 @@ impl<'a> Parser<'a> {
             name: ident,
             pos,
             span,
+            type_params,
             methods,
         })
     }
@@ -771,6 +779,8 @@ impl<'a> Parser<'a> {
                 "test" => Modifier::Test,
                 "cannon" => Modifier::Cannon,
                 "optimize_immediately" => Modifier::OptimizeImmediately,
+                "threadlocal" => Modifier::ThreadLocal,
+                "extern" => Modifier::Extern,
                 annotation => {
                     return Err(ParseErrorAndPos::new(
                         self.token.position,
@@ -786,12 +796,47 @@ impl<'a> Parser<'a> {
                 ));
             }
 
-            modifiers.add(modifier, self.token.position, self.token.span);
+            let pos = self.token.position;
+            let span = self.token.span;
+
+            let args = if modifier == Modifier::Extern {
+                // @extern("library") or @extern("library", "symbol")
+                let args = self.parse_annotation_args()?;
+
+                if args.is_empty() || args.len() > 2 {
+                    return Err(ParseErrorAndPos::new(
+                        pos,
+                        ParseError::InvalidAnnotationArguments(modifier.name().into()),
+                    ));
+                }
+
+                args
+            } else {
+                Vec::new()
+            };
+
+            modifiers.add(modifier, args, pos, span);
         }
 
         Ok(modifiers)
     }
 
+    fn parse_annotation_args(&mut self) -> Result<Vec<String>, ParseErrorAndPos> {
+        self.expect_token(TokenKind::LParen)?;
+
+        self.parse_list(TokenKind::Comma, TokenKind::RParen, |p| {
+            let tok = p.advance_token()?;
+
+            match tok.kind {
+                TokenKind::StringTail(value) => Ok(value),
+                _ => Err(ParseErrorAndPos::new(
+                    tok.position,
+                    ParseError::ExpectedString(tok.name()),
+                )),
+            }
+        })
+    }
+
     fn ban_modifiers(&mut self, modifiers: &Modifiers) -> Result<(), ParseErrorAndPos> {
         self.restrict_modifiers(modifiers, &[])
     }
@@ -876,6 +921,10 @@ impl<'a> Parser<'a> {
             is_pub: modifiers.contains(Modifier::Pub),
             is_static: modifiers.contains(Modifier::Static),
             internal: modifiers.contains(Modifier::Internal),
+            external: modifiers.args(Modifier::Extern).map(|args| ExternSymbol {
+                library: args[0].clone(),
+                symbol: args.get(1).cloned(),
+            }),
             is_abstract: modifiers.contains(Modifier::Abstract),
             is_constructor: false,
             is_test: modifiers.contains(Modifier::Test),
@@ -1221,6 +1270,7 @@ impl<'a> Parser<'a> {
         match self.token.kind {
             TokenKind::Let | TokenKind::Var => Ok(StmtOrExpr::Stmt(self.parse_var()?)),
             TokenKind::While => Ok(StmtOrExpr::Stmt(self.parse_while()?)),
+            TokenKind::Synchronized => Ok(StmtOrExpr::Stmt(self.parse_synchronized()?)),
             TokenKind::Break => Ok(StmtOrExpr::Stmt(self.parse_break()?)),
             TokenKind::Continue => Ok(StmtOrExpr::Stmt(self.parse_continue()?)),
             TokenKind::Return => Ok(StmtOrExpr::Stmt(self.parse_return()?)),
@@ -1316,6 +1366,22 @@ impl<'a> Parser<'a> {
         )))
     }
 
+    fn parse_synchronized(&mut self) -> StmtResult {
+        let start = self.token.span.start();
+        let pos = self.expect_token(TokenKind::Synchronized)?.position;
+        let expr = self.parse_expression_no_struct_lit()?;
+        let block = self.parse_block_stmt()?;
+        let span = self.span_from(start);
+
+        Ok(Box::new(Stmt::create_sync(
+            self.generate_id(),
+            pos,
+            span,
+            expr,
+            block,
+        )))
+    }
+
     fn parse_break(&mut self) -> StmtResult {
         let start = self.token.span.start();
         let pos = self.expect_token(TokenKind::Break)?.position;
@@ -1742,18 +1808,25 @@ impl<'a> Parser<'a> {
             TokenKind::StringExpr(value) => {
                 let start = self.token.span.start();
                 let mut parts: Vec<Box<Expr>> = Vec::new();
+                let mut specs: Vec<Option<FormatSpec>> = Vec::new();
                 parts.push(Box::new(Expr::create_lit_str(
                     self.generate_id(),
                     string.position,
                     span,
                     value,
                 )));
+                specs.push(None);
 
                 loop {
                     let expr = self.parse_expression()?;
                     parts.push(expr);
 
-                    if !self.token.is(TokenKind::RBrace) {
+                    if self.token.is(TokenKind::Colon) {
+                        let (spec, pos) = self.lexer.read_format_spec()?;
+                        specs.push(Some(parse_format_spec(&spec, pos)?));
+                    } else if self.token.is(TokenKind::RBrace) {
+                        specs.push(None);
+                    } else {
                         return Err(ParseErrorAndPos::new(
                             self.token.position,
                             ParseError::UnclosedStringTemplate,
@@ -1778,6 +1851,7 @@ impl<'a> Parser<'a> {
                         span,
                         value,
                     )));
+                    specs.push(None);
 
                     self.advance_token()?;
 
@@ -1793,6 +1867,7 @@ impl<'a> Parser<'a> {
                     string.position,
                     span,
                     parts,
+                    specs,
                 )))
             }
 
@@ -2032,6 +2107,101 @@ impl NodeIdGenerator {
     }
 }
 
+// Parses the format specifier of a template part:
+// `[[fill]align][+][#][0][width][.precision][kind]`
+fn parse_format_spec(value: &str, pos: Position) -> Result<FormatSpec, ParseErrorAndPos> {
+    let chars: Vec<char> = value.chars().collect();
+    let mut idx = 0;
+
+    let invalid = || ParseErrorAndPos::new(pos, ParseError::InvalidFormatSpec(value.into()));
+
+    let to_align = |ch: Option<&char>| match ch {
+        Some('<') => Some(FormatAlign::Left),
+        Some('>') => Some(FormatAlign::Right),
+        Some('^') => Some(FormatAlign::Center),
+        _ => None,
+    };
+
+    let mut fill = ' ';
+    let mut align = FormatAlign::Default;
+
+    if let Some(value) = to_align(chars.get(1)) {
+        fill = chars[0];
+        align = value;
+        idx = 2;
+    } else if let Some(value) = to_align(chars.get(0)) {
+        align = value;
+        idx = 1;
+    }
+
+    let mut flag = |ch: char| {
+        if chars.get(idx) == Some(&ch) {
+            idx += 1;
+            true
+        } else {
+            false
+        }
+    };
+
+    let sign = flag('+');
+    let alternate = flag('#');
+    let zero = flag('0');
+
+    let read_number = |idx: &mut usize| -> Result<Option<u32>, ParseErrorAndPos> {
+        let start = *idx;
+
+        while *idx < chars.len() && chars[*idx].is_ascii_digit() {
+            *idx += 1;
+        }
+
+        if start == *idx {
+            return Ok(None);
+        }
+
+        let digits: String = chars[start..*idx].iter().collect();
+        digits.parse::<u32>().map(Some).map_err(|_| invalid())
+    };
+
+    let width = read_number(&mut idx)?;
+    let mut precision = None;
+
+    if chars.get(idx) == Some(&'.') {
+        idx += 1;
+        precision = Some(read_number(&mut idx)?.ok_or_else(invalid)?);
+    }
+
+    let kind = match chars.get(idx) {
+        None => FormatKind::Default,
+        Some('b') => FormatKind::Binary,
+        Some('o') => FormatKind::Octal,
+        Some('x') => FormatKind::Hex,
+        Some('X') => FormatKind::UpperHex,
+        Some('e') => FormatKind::Exp,
+        Some('E') => FormatKind::UpperExp,
+        Some(_) => return Err(invalid()),
+    };
+
+    if kind != FormatKind::Default {
+        idx += 1;
+    }
+
+    if idx != chars.len() {
+        return Err(invalid());
+    }
+
+    Ok(FormatSpec {
+        pos,
+        fill,
+        align,
+        sign,
+        alternate,
+        zero,
+        width,
+        precision,
+        kind,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use crate::ast::*;
@@ -2787,6 +2957,15 @@ mod tests {
         assert!(whilestmt.block.is_expr());
     }
 
+    #[test]
+    fn parse_synchronized() {
+        let stmt = parse_stmt("synchronized mtx { 2; }");
+        let syncstmt = stmt.to_sync().unwrap();
+
+        assert!(syncstmt.mutex.is_ident());
+        assert!(syncstmt.block.is_expr());
+    }
+
     #[test]
     fn parse_empty_block() {
         let (expr, _) = parse_expr("{}");
@@ -3237,6 +3416,49 @@ mod tests {
         assert!(fct.internal);
     }
 
+    #[test]
+    fn parse_extern() {
+        let (prog, _) = parse("@extern(\"libm.so.6\", \"cos\") fun cos(x: Float64) -> Float64;");
+        let fct = prog.fct0();
+        assert!(fct.block.is_none());
+        assert_eq!(
+            Some(ExternSymbol {
+                library: "libm.so.6".into(),
+                symbol: Some("cos".into()),
+            }),
+            fct.external
+        );
+
+        let (prog, _) = parse("@extern(\"libc.so.6\") fun getpid() -> Int32;");
+        let fct = prog.fct0();
+        assert_eq!(None, fct.external.as_ref().unwrap().symbol);
+
+        parse_err(
+            "@extern fun foo();",
+            ParseError::ExpectedToken("(".into(), "fun".into()),
+            1,
+            9,
+        );
+        parse_err(
+            "@extern() fun foo();",
+            ParseError::InvalidAnnotationArguments("extern".into()),
+            1,
+            8,
+        );
+        parse_err(
+            "@extern(foo) fun foo();",
+            ParseError::ExpectedString("foo".into()),
+            1,
+            9,
+        );
+        parse_err(
+            "@extern(\"a\") class Foo",
+            ParseError::MisplacedAnnotation("extern".into()),
+            1,
+            8,
+        );
+    }
+
     #[test]
     fn parse_function_without_body() {
         let (prog, _) = parse("fun foo();");
@@ -3338,6 +3560,56 @@ mod tests {
         assert!(expr.is_lit_str());
     }
 
+    #[test]
+    fn parse_template_with_format_spec() {
+        let (expr, _) = parse_expr("\"a${1:*^+#08.3x}b${2}c${3:}\"");
+        let tmpl = expr.to_template().unwrap();
+        assert_eq!(tmpl.parts.len(), 7);
+        assert_eq!(tmpl.specs.len(), 7);
+
+        let spec = tmpl.specs[1].as_ref().unwrap();
+        assert_eq!(Position::new(1, 7), spec.pos);
+        assert_eq!('*', spec.fill);
+        assert_eq!(FormatAlign::Center, spec.align);
+        assert!(spec.sign && spec.alternate && spec.zero);
+        assert_eq!(Some(8), spec.width);
+        assert_eq!(Some(3), spec.precision);
+        assert_eq!(FormatKind::Hex, spec.kind);
+
+        assert!(tmpl.specs[0].is_none());
+        assert!(tmpl.specs[3].is_none());
+
+        let spec = tmpl.specs[5].as_ref().unwrap();
+        assert_eq!(' ', spec.fill);
+        assert_eq!(FormatAlign::Default, spec.align);
+        assert_eq!(None, spec.width);
+        assert_eq!(FormatKind::Default, spec.kind);
+
+        let (expr, _) = parse_expr("\"${x:<5}${y:>.2e}\"");
+        let tmpl = expr.to_template().unwrap();
+        let spec = tmpl.specs[1].as_ref().unwrap();
+        assert_eq!(FormatAlign::Left, spec.align);
+        assert_eq!(Some(5), spec.width);
+        let spec = tmpl.specs[3].as_ref().unwrap();
+        assert_eq!(FormatAlign::Right, spec.align);
+        assert_eq!(Some(2), spec.precision);
+        assert_eq!(FormatKind::Exp, spec.kind);
+
+        err_expr(
+            "\"${1:5q}\"",
+            ParseError::InvalidFormatSpec("5q".into()),
+            1,
+            6,
+        );
+        err_expr(
+            "\"${1:.}\"",
+            ParseError::InvalidFormatSpec(".".into()),
+            1,
+            6,
+        );
+        err_expr("\"${1:5", ParseError::UnclosedStringTemplate, 1, 6);
+    }
+
     #[test]
     fn parse_class_type_params() {
         let (prog, interner) = parse("class Foo[T]");
@@ -3395,6 +3667,16 @@ mod tests {
         assert_eq!(true, xtrait.methods[0].is_static);
     }
 
+    #[test]
+    fn parse_generic_trait() {
+        let (prog, interner) = parse("trait Foo[A, B] { fun get() -> A; }");
+        let xtrait = prog.trait0();
+
+        assert_eq!("Foo", *interner.str(xtrait.name));
+        assert_eq!(2, xtrait.type_params.as_ref().unwrap().len());
+        assert_eq!(1, xtrait.methods.len());
+    }
+
     #[test]
     fn parse_empty_impl() {
         let (prog, interner) = parse("impl Foo for A {}");
@@ -3452,6 +3734,24 @@ mod tests {
 
         assert_eq!("b", *interner.str(global.name));
         assert_eq!(false, global.reassignable);
+        assert_eq!(false, global.thread_local);
+    }
+
+    #[test]
+    fn parse_global_thread_local() {
+        let (prog, interner) = parse("@threadlocal var c: int = 0;");
+        let global = prog.global0();
+
+        assert_eq!("c", *interner.str(global.name));
+        assert_eq!(true, global.reassignable);
+        assert_eq!(true, global.thread_local);
+
+        parse_err(
+            "@threadlocal fun f() {}",
+            ParseError::MisplacedAnnotation("threadlocal".into()),
+            1,
+            14,
+        );
     }
 
     #[test]
@@ -176,6 +176,65 @@ impl Assembler {
         self.emit_u32(imm.uint32());
     }
 
+    pub fn lock_cmpxchgb_ar(&mut self, dest: Address, src: Register) {
+        self.emit_u8(0xF0);
+        self.emit_rex32_byte_address(src, dest);
+        self.emit_u8(0x0F);
+        self.emit_u8(0xB0);
+        self.emit_address(src.low_bits(), dest);
+    }
+
+    pub fn lock_cmpxchgl_ar(&mut self, dest: Address, src: Register) {
+        self.emit_u8(0xF0);
+        self.emit_rex32_modrm_address(src, dest);
+        self.emit_u8(0x0F);
+        self.emit_u8(0xB1);
+        self.emit_address(src.low_bits(), dest);
+    }
+
+    pub fn lock_cmpxchgq_ar(&mut self, dest: Address, src: Register) {
+        self.emit_u8(0xF0);
+        self.emit_rex64_modrm_address(src, dest);
+        self.emit_u8(0x0F);
+        self.emit_u8(0xB1);
+        self.emit_address(src.low_bits(), dest);
+    }
+
+    pub fn lock_xaddl_ar(&mut self, dest: Address, src: Register) {
+        self.emit_u8(0xF0);
+        self.emit_rex32_modrm_address(src, dest);
+        self.emit_u8(0x0F);
+        self.emit_u8(0xC1);
+        self.emit_address(src.low_bits(), dest);
+    }
+
+    pub fn lock_xaddq_ar(&mut self, dest: Address, src: Register) {
+        self.emit_u8(0xF0);
+        self.emit_rex64_modrm_address(src, dest);
+        self.emit_u8(0x0F);
+        self.emit_u8(0xC1);
+        self.emit_address(src.low_bits(), dest);
+    }
+
+    // xchg with a memory operand is always locked
+    pub fn xchgb_ar(&mut self, dest: Address, src: Register) {
+        self.emit_rex32_byte_address(src, dest);
+        self.emit_u8(0x86);
+        self.emit_address(src.low_bits(), dest);
+    }
+
+    pub fn xchgl_ar(&mut self, dest: Address, src: Register) {
+        self.emit_rex32_modrm_address(src, dest);
+        self.emit_u8(0x87);
+        self.emit_address(src.low_bits(), dest);
+    }
+
+    pub fn xchgq_ar(&mut self, dest: Address, src: Register) {
+        self.emit_rex64_modrm_address(src, dest);
+        self.emit_u8(0x87);
+        self.emit_address(src.low_bits(), dest);
+    }
+
     pub fn movl_rr(&mut self, dest: Register, src: Register) {
         self.emit_rex32_optional(src, dest);
         self.emit_u8(0x89);
@@ -482,6 +541,26 @@ impl Assembler {
         self.emit_modrm_sse_registers(dest, src);
     }
 
+    pub fn roundss_ri(&mut self, dest: XmmRegister, src: XmmRegister, mode: u8) {
+        self.emit_u8(0x66);
+        self.emit_rex_sse_modrm_optional(dest, src);
+        self.emit_u8(0x0f);
+        self.emit_u8(0x3a);
+        self.emit_u8(0x0a);
+        self.emit_modrm_sse_registers(dest, src);
+        self.emit_u8(mode);
+    }
+
+    pub fn roundsd_ri(&mut self, dest: XmmRegister, src: XmmRegister, mode: u8) {
+        self.emit_u8(0x66);
+        self.emit_rex_sse_modrm_optional(dest, src);
+        self.emit_u8(0x0f);
+        self.emit_u8(0x3a);
+        self.emit_u8(0x0b);
+        self.emit_modrm_sse_registers(dest, src);
+        self.emit_u8(mode);
+    }
+
     pub fn pxor_rr(&mut self, dest: XmmRegister, src: XmmRegister) {
         self.emit_u8(0x66);
         self.emit_rex_sse_modrm_optional(dest, src);
@@ -1836,6 +1915,32 @@ mod tests {
         assert_emit!(0x8b, 0x05, 0, 0, 0, 0; movl_ra(RAX, Address::rip(0)));
     }
 
+    #[test]
+    fn test_lock_cmpxchg_ar() {
+        assert_emit!(0xf0, 0x0f, 0xb0, 0x4f, 1; lock_cmpxchgb_ar(Address::offset(RDI, 1), RCX));
+        assert_emit!(0xf0, 0x40, 0x0f, 0xb0, 0x77, 1; lock_cmpxchgb_ar(Address::offset(RDI, 1), RSI));
+        assert_emit!(0xf0, 0x0f, 0xb1, 0x4f, 1; lock_cmpxchgl_ar(Address::offset(RDI, 1), RCX));
+        assert_emit!(0xf0, 0x45, 0x0f, 0xb1, 0x1a; lock_cmpxchgl_ar(Address::offset(R10, 0), R11));
+        assert_emit!(0xf0, 0x48, 0x0f, 0xb1, 0x4f, 1; lock_cmpxchgq_ar(Address::offset(RDI, 1), RCX));
+        assert_emit!(0xf0, 0x4d, 0x0f, 0xb1, 0x5a, 8; lock_cmpxchgq_ar(Address::offset(R10, 8), R11));
+    }
+
+    #[test]
+    fn test_lock_xadd_ar() {
+        assert_emit!(0xf0, 0x0f, 0xc1, 0x47, 1; lock_xaddl_ar(Address::offset(RDI, 1), RAX));
+        assert_emit!(0xf0, 0x48, 0x0f, 0xc1, 0x47, 1; lock_xaddq_ar(Address::offset(RDI, 1), RAX));
+        assert_emit!(0xf0, 0x49, 0x0f, 0xc1, 0x42, 8; lock_xaddq_ar(Address::offset(R10, 8), RAX));
+    }
+
+    #[test]
+    fn test_xchg_ar() {
+        assert_emit!(0x86, 0x47, 1; xchgb_ar(Address::offset(RDI, 1), RAX));
+        assert_emit!(0x40, 0x86, 0x77, 1; xchgb_ar(Address::offset(RDI, 1), RSI));
+        assert_emit!(0x87, 0x47, 1; xchgl_ar(Address::offset(RDI, 1), RAX));
+        assert_emit!(0x48, 0x87, 0x47, 1; xchgq_ar(Address::offset(RDI, 1), RAX));
+        assert_emit!(0x4d, 0x87, 0x5a, 8; xchgq_ar(Address::offset(R10, 8), R11));
+    }
+
     #[test]
     fn test_cmpq_ar() {
         assert_emit!(0x48, 0x39, 0x43, 1; cmpq_ar(Address::offset(RBX, 1), RAX));
@@ -2172,6 +2277,20 @@ mod tests {
         assert_emit!(0xf2, 0x41, 0x0f, 0x51, 0xe0; sqrtsd_rr(XMM4, XMM8));
     }
 
+    #[test]
+    fn test_roundss_ri() {
+        assert_emit!(0x66, 0x0f, 0x3a, 0x0a, 0xc8, 0x01; roundss_ri(XMM1, XMM0, 1));
+        assert_emit!(0x66, 0x44, 0x0f, 0x3a, 0x0a, 0xfb, 0x02; roundss_ri(XMM15, XMM3, 2));
+        assert_emit!(0x66, 0x41, 0x0f, 0x3a, 0x0a, 0xe0, 0x03; roundss_ri(XMM4, XMM8, 3));
+    }
+
+    #[test]
+    fn test_roundsd_ri() {
+        assert_emit!(0x66, 0x0f, 0x3a, 0x0b, 0xc8, 0x01; roundsd_ri(XMM1, XMM0, 1));
+        assert_emit!(0x66, 0x44, 0x0f, 0x3a, 0x0b, 0xfb, 0x02; roundsd_ri(XMM15, XMM3, 2));
+        assert_emit!(0x66, 0x41, 0x0f, 0x3a, 0x0b, 0xe0, 0x00; roundsd_ri(XMM4, XMM8, 0));
+    }
+
     #[test]
     fn test_tzcnt() {
         assert_emit!(0xF3, 0x48, 0x0F, 0xBC, 0xF8; tzcntq_rr(RDI, RAX));
@@ -15,14 +15,30 @@ use crate::vm::{
     Intrinsic, TraitId, TupleId, VarId, VM,
 };
 
+// Alignment and flags of template format specifiers, keep in sync with Format.dora.
+const FORMAT_ALIGN_DEFAULT: i32 = 0;
+const FORMAT_ALIGN_LEFT: i32 = 1;
+const FORMAT_ALIGN_RIGHT: i32 = 2;
+const FORMAT_ALIGN_CENTER: i32 = 3;
+
+const FORMAT_FLAG_SIGN: i32 = 1;
+const FORMAT_FLAG_ALTERNATE: i32 = 2;
+const FORMAT_FLAG_ZERO: i32 = 4;
+
 pub struct LoopLabels {
     cond: Label,
     end: Label,
+    // number of entered synchronized blocks at the start of the loop
+    monitors: usize,
 }
 
 impl LoopLabels {
-    fn new(cond: Label, end: Label) -> LoopLabels {
-        LoopLabels { cond, end }
+    fn new(cond: Label, end: Label, monitors: usize) -> LoopLabels {
+        LoopLabels {
+            cond,
+            end,
+            monitors,
+        }
     }
 }
 
@@ -58,6 +74,7 @@ pub fn generate<'ast>(
 
         gen: BytecodeBuilder::new(&vm.args),
         loops: Vec::new(),
+        monitors: Vec::new(),
         var_registers: HashMap::new(),
     };
     ast_bytecode_generator.generate()
@@ -74,6 +91,8 @@ struct AstBytecodeGen<'a, 'ast: 'a> {
 
     gen: BytecodeBuilder,
     loops: Vec<LoopLabels>,
+    // registers holding the Mutex of each entered synchronized block
+    monitors: Vec<Register>,
     var_registers: HashMap<VarId, Register>,
 }
 
@@ -142,6 +161,7 @@ impl<'a, 'ast> AstBytecodeGen<'a, 'ast> {
             StmtVar(ref stmt) => self.visit_stmt_var(stmt),
             StmtWhile(ref stmt) => self.visit_stmt_while(stmt),
             StmtFor(ref stmt) => self.visit_stmt_for(stmt),
+            StmtSync(ref stmt) => self.visit_stmt_sync(stmt),
         }
     }
 
@@ -188,7 +208,8 @@ impl<'a, 'ast> AstBytecodeGen<'a, 'ast> {
         // load current array element
         self.emit_load_array(var_ty, var_reg, array_reg, index_reg, stmt.expr.pos());
 
-        self.loops.push(LoopLabels::new(lbl_cond, lbl_end));
+        self.loops
+            .push(LoopLabels::new(lbl_cond, lbl_end, self.monitors.len()));
         self.visit_stmt(&stmt.block);
         self.loops.pop().unwrap();
 
@@ -211,15 +232,29 @@ impl<'a, 'ast> AstBytecodeGen<'a, 'ast> {
 
         // Emit: <obj> = <expr> (for <var> in <expr> { ... })
         let object_reg = self.visit_expr(&stmt.expr, DataDest::Alloc);
-        self.gen.emit_push_register(object_reg);
-
-        // Emit: <iterator> = <obj>.makeIterator();
         let iterator_reg = self.alloc_var(BytecodeType::Ptr);
-        self.gen.emit_invoke_direct(
-            iterator_reg,
-            FctDef::fct_id(self.vm, for_type_info.make_iterator),
-            stmt.expr.pos(),
-        );
+
+        if let Some((make_iterator, iterable_type)) = for_type_info.make_iterator {
+            // Emit: <iterator> = <obj>.makeIterator();
+            let iterable_type = self.specialize_type(iterable_type);
+            self.gen.emit_push_register(object_reg);
+            self.gen.emit_invoke_direct(
+                iterator_reg,
+                FctDef::fct_id_types(
+                    self.vm,
+                    make_iterator,
+                    iterable_type.type_params(self.vm),
+                    TypeList::empty(),
+                ),
+                stmt.expr.pos(),
+            );
+        } else {
+            // Emit: <iterator> = <obj>
+            self.gen.emit_mov_ptr(iterator_reg, object_reg);
+        }
+
+        let iterator_type = self.specialize_type(for_type_info.iterator_type);
+        let iterator_type_params = iterator_type.type_params(self.vm);
 
         self.gen.emit_push_register(iterator_reg);
 
@@ -230,7 +265,12 @@ impl<'a, 'ast> AstBytecodeGen<'a, 'ast> {
         let cond_reg = self.alloc_temp(BytecodeType::Bool);
         self.gen.emit_invoke_direct(
             cond_reg,
-            FctDef::fct_id(self.vm, for_type_info.has_next),
+            FctDef::fct_id_types(
+                self.vm,
+                for_type_info.has_next,
+                iterator_type_params.clone(),
+                TypeList::empty(),
+            ),
             stmt.expr.pos(),
         );
         self.gen.emit_jump_if_false(cond_reg, lbl_end);
@@ -249,11 +289,17 @@ impl<'a, 'ast> AstBytecodeGen<'a, 'ast> {
         self.emit_invoke_direct(
             var_ty,
             var_reg,
-            FctDef::fct_id(self.vm, for_type_info.next),
+            FctDef::fct_id_types(
+                self.vm,
+                for_type_info.next,
+                iterator_type_params,
+                TypeList::empty(),
+            ),
             stmt.expr.pos(),
         );
 
-        self.loops.push(LoopLabels::new(lbl_cond, lbl_end));
+        self.loops
+            .push(LoopLabels::new(lbl_cond, lbl_end, self.monitors.len()));
         self.visit_stmt(&stmt.block);
         self.loops.pop().unwrap();
 
@@ -291,13 +337,53 @@ impl<'a, 'ast> AstBytecodeGen<'a, 'ast> {
         let cond_reg = self.visit_expr(&stmt.cond, DataDest::Alloc);
         self.gen.emit_jump_if_false(cond_reg, end_lbl);
         self.free_if_temp(cond_reg);
-        self.loops.push(LoopLabels::new(cond_lbl, end_lbl));
+        self.loops
+            .push(LoopLabels::new(cond_lbl, end_lbl, self.monitors.len()));
         self.visit_stmt(&stmt.block);
         self.loops.pop().unwrap();
         self.gen.emit_jump_loop(cond_lbl);
         self.gen.bind_label(end_lbl);
     }
 
+    fn visit_stmt_sync(&mut self, stmt: &StmtSyncType) {
+        self.push_scope();
+
+        let mutex_reg = self.alloc_var(BytecodeType::Ptr);
+        self.visit_expr(&stmt.mutex, DataDest::Reg(mutex_reg));
+        self.gen.emit_nil_check(mutex_reg, stmt.pos);
+
+        // Emit: <mutex>.lock()
+        let fct_id = self.vm.vips.fct.mutex_lock;
+        self.gen.emit_push_register(mutex_reg);
+        self.gen
+            .emit_invoke_direct_void(FctDef::fct_id(self.vm, fct_id), stmt.pos);
+
+        self.monitors.push(mutex_reg);
+        self.visit_stmt(&stmt.block);
+        self.monitors.pop().unwrap();
+
+        self.emit_unlock_monitor(mutex_reg, stmt.pos);
+
+        self.pop_scope();
+    }
+
+    // return, break and continue leave synchronized blocks
+    // without reaching their end, unlock the mutexes before
+    fn emit_unlock_monitors(&mut self, monitors: usize, pos: Position) {
+        for idx in (monitors..self.monitors.len()).rev() {
+            let mutex_reg = self.monitors[idx];
+            self.emit_unlock_monitor(mutex_reg, pos);
+        }
+    }
+
+    fn emit_unlock_monitor(&mut self, mutex_reg: Register, pos: Position) {
+        // Emit: <mutex>.unlock()
+        let fct_id = self.vm.vips.fct.mutex_unlock;
+        self.gen.emit_push_register(mutex_reg);
+        self.gen
+            .emit_invoke_direct_void(FctDef::fct_id(self.vm, fct_id), pos);
+    }
+
     fn visit_stmt_expr(&mut self, stmt: &StmtExprType) {
         let reg = self.visit_expr(&stmt.expr, DataDest::Effect);
         self.free_if_temp(reg);
@@ -306,9 +392,11 @@ impl<'a, 'ast> AstBytecodeGen<'a, 'ast> {
     fn visit_stmt_return(&mut self, ret: &StmtReturnType) {
         if let Some(ref expr) = ret.expr {
             let result_reg = self.visit_expr(expr, DataDest::Alloc);
+            self.emit_unlock_monitors(0, ret.pos);
             self.emit_ret_value(result_reg);
             self.free_if_temp(result_reg);
         } else {
+            self.emit_unlock_monitors(0, ret.pos);
             self.gen.emit_ret_void();
         }
     }
@@ -324,12 +412,16 @@ impl<'a, 'ast> AstBytecodeGen<'a, 'ast> {
         self.gen.emit_ret(result_reg);
     }
 
-    fn visit_stmt_break(&mut self, _stmt: &StmtBreakType) {
+    fn visit_stmt_break(&mut self, stmt: &StmtBreakType) {
+        let monitors = self.loops.last().unwrap().monitors;
+        self.emit_unlock_monitors(monitors, stmt.pos);
         let end = self.loops.last().unwrap().end;
         self.gen.emit_jump(end);
     }
 
-    fn visit_stmt_continue(&mut self, _stmt: &StmtContinueType) {
+    fn visit_stmt_continue(&mut self, stmt: &StmtContinueType) {
+        let monitors = self.loops.last().unwrap().monitors;
+        self.emit_unlock_monitors(monitors, stmt.pos);
         let cond = self.loops.last().unwrap().cond;
         self.gen.emit_jump_loop(cond);
     }
@@ -376,44 +468,17 @@ impl<'a, 'ast> AstBytecodeGen<'a, 'ast> {
 
         let part_register = self.alloc_temp(BytecodeType::Ptr);
 
-        for part in &expr.parts {
+        for (part, spec) in expr.parts.iter().zip(&expr.specs) {
+            if let Some(ref spec) = spec {
+                self.emit_template_format(part, spec, buffer_register, part_register);
+                continue;
+            }
+
             if let Some(ref lit_str) = part.to_lit_str() {
                 let value = lit_str.value.clone();
                 self.gen.emit_const_string(part_register, value);
             } else {
-                let ty = self.ty(part.id());
-
-                if ty.cls_id(self.vm) == Some(self.vm.vips.string_class) {
-                    self.visit_expr(part, DataDest::Reg(part_register));
-                } else {
-                    let expr_register = self.visit_expr(part, DataDest::Alloc);
-                    self.gen.emit_push_register(expr_register);
-
-                    // build toString() call
-                    let cls_id = ty.cls_id(self.vm).expect("no cls_id found for type");
-                    let cls = self.vm.classes.idx(cls_id);
-                    let cls = cls.read();
-                    let name = self.vm.interner.intern("toString");
-                    let to_string_id = cls
-                        .find_trait_method(self.vm, self.vm.vips.stringable_trait, name, false)
-                        .expect("toString() method not found");
-
-                    if ty.reference_type() {
-                        self.gen.emit_invoke_direct(
-                            part_register,
-                            FctDef::fct_id(self.vm, to_string_id),
-                            part.pos(),
-                        );
-                    } else {
-                        self.gen.emit_invoke_static(
-                            part_register,
-                            FctDef::fct_id(self.vm, to_string_id),
-                            part.pos(),
-                        );
-                    }
-
-                    self.free_if_temp(expr_register);
-                }
+                self.emit_template_to_string(part, part_register);
             }
 
             // build StringBuffer::append() call
@@ -435,6 +500,142 @@ impl<'a, 'ast> AstBytecodeGen<'a, 'ast> {
         buffer_register
     }
 
+    fn emit_template_to_string(&mut self, part: &Expr, part_register: Register) {
+        let ty = self.ty(part.id());
+
+        if ty.cls_id(self.vm) == Some(self.vm.vips.string_class) {
+            self.visit_expr(part, DataDest::Reg(part_register));
+        } else {
+            let expr_register = self.visit_expr(part, DataDest::Alloc);
+            self.gen.emit_push_register(expr_register);
+
+            // build toString() call
+            let cls_id = ty.cls_id(self.vm).expect("no cls_id found for type");
+            let cls = self.vm.classes.idx(cls_id);
+            let cls = cls.read();
+            let name = self.vm.interner.intern("toString");
+            let to_string_id = cls
+                .find_trait_method(self.vm, self.vm.vips.stringable_trait, name, false)
+                .expect("toString() method not found");
+
+            if ty.reference_type() {
+                self.gen.emit_invoke_direct(
+                    part_register,
+                    FctDef::fct_id(self.vm, to_string_id),
+                    part.pos(),
+                );
+            } else {
+                self.gen.emit_invoke_static(
+                    part_register,
+                    FctDef::fct_id(self.vm, to_string_id),
+                    part.pos(),
+                );
+            }
+
+            self.free_if_temp(expr_register);
+        }
+    }
+
+    // Appends a part with format specifier like `${value:>8.3}` through the
+    // Format::append* function for its type, numbers are passed unconverted
+    // while everything else is formatted from its toString() result.
+    fn emit_template_format(
+        &mut self,
+        part: &Expr,
+        spec: &FormatSpec,
+        buffer_register: Register,
+        part_register: Register,
+    ) {
+        let ty = self.ty(part.id());
+        let fct = &self.vm.vips.fct;
+
+        let (fct_id, value_register) = match ty {
+            BuiltinType::UInt8 => (fct.format_uint8, None),
+            BuiltinType::Int32 => (fct.format_int32, None),
+            BuiltinType::Int64 => (fct.format_int64, None),
+            BuiltinType::Float32 => (fct.format_float32, None),
+            BuiltinType::Float64 => (fct.format_float64, None),
+            _ => (fct.format_string, Some(part_register)),
+        };
+
+        let value_register = match value_register {
+            Some(part_register) => {
+                self.emit_template_to_string(part, part_register);
+                part_register
+            }
+
+            None => self.visit_expr(part, DataDest::Alloc),
+        };
+
+        let align = match spec.align {
+            FormatAlign::Default => FORMAT_ALIGN_DEFAULT,
+            FormatAlign::Left => FORMAT_ALIGN_LEFT,
+            FormatAlign::Right => FORMAT_ALIGN_RIGHT,
+            FormatAlign::Center => FORMAT_ALIGN_CENTER,
+        };
+
+        let mut flags = 0;
+
+        if spec.sign {
+            flags |= FORMAT_FLAG_SIGN;
+        }
+
+        if spec.alternate {
+            flags |= FORMAT_FLAG_ALTERNATE;
+        }
+
+        if spec.zero {
+            flags |= FORMAT_FLAG_ZERO;
+        }
+
+        let fill_register = self.alloc_temp(BytecodeType::Char);
+        self.gen.emit_const_char(fill_register, spec.fill);
+
+        let args = [
+            align,
+            flags,
+            spec.width.map(|width| width as i32).unwrap_or(0),
+            spec.precision
+                .map(|precision| precision as i32)
+                .unwrap_or(-1),
+        ];
+
+        let arg_registers: Vec<Register> = args
+            .iter()
+            .map(|&value| {
+                let register = self.alloc_temp(BytecodeType::Int32);
+                self.gen.emit_const_int32(register, value);
+                register
+            })
+            .collect();
+
+        let kind_register = self.alloc_temp(BytecodeType::Char);
+        self.gen.emit_const_char(kind_register, spec.kind.to_char());
+
+        self.gen.emit_push_register(buffer_register);
+        self.gen.emit_push_register(value_register);
+        self.gen.emit_push_register(fill_register);
+
+        for &register in &arg_registers {
+            self.gen.emit_push_register(register);
+        }
+
+        self.gen.emit_push_register(kind_register);
+        self.gen
+            .emit_invoke_static_void(FctDef::fct_id(self.vm, fct_id), part.pos());
+
+        self.free_temp(fill_register);
+        self.free_temp(kind_register);
+
+        for register in arg_registers {
+            self.free_temp(register);
+        }
+
+        if value_register != part_register {
+            self.free_if_temp(value_register);
+        }
+    }
+
     fn visit_expr_path(&mut self, expr: &ExprPathType, dest: DataDest) -> Register {
         let ident_type = self.src.map_idents.get(expr.id).unwrap();
 
@@ -1400,7 +1601,9 @@ impl<'a, 'ast> AstBytecodeGen<'a, 'ast> {
         self.gen.emit_push_register(lhs);
         self.gen.emit_push_register(rhs);
 
-        if lhs_type == BytecodeType::Ptr {
+        if callee.is_virtual() {
+            self.emit_invoke_virtual(function_return_type, result, callee_def_id, expr.pos);
+        } else if lhs_type == BytecodeType::Ptr {
             self.emit_invoke_direct(function_return_type, result, callee_def_id, expr.pos);
         } else {
             self.emit_invoke_static(function_return_type, result, callee_def_id, expr.pos);
@@ -1477,7 +1680,8 @@ impl<'a, 'ast> AstBytecodeGen<'a, 'ast> {
 
                 Intrinsic::ArrayWithValues => {
                     let ty = self.ty(expr.id);
-                    self.emit_array_with_variadic_arguments(expr, &[ty], 0, dest)
+                    let element_ty = ty.type_params(self.vm)[0];
+                    self.emit_array_with_variadic_arguments(expr, &[element_ty], 0, dest)
                 }
 
                 Intrinsic::DefaultValue => {
@@ -1801,7 +2005,11 @@ impl<'a, 'ast> AstBytecodeGen<'a, 'ast> {
                 self.gen.emit_const_int32(dest, 0);
                 self.gen.bind_label(lbl_end);
             }
-            Intrinsic::Float32Sqrt => {
+            Intrinsic::Float32Sqrt
+            | Intrinsic::Float32RoundToZero
+            | Intrinsic::Float32RoundUp
+            | Intrinsic::Float32RoundDown
+            | Intrinsic::Float32RoundHalfEven => {
                 self.gen.emit_push_register(src);
                 self.gen.emit_invoke_static(
                     dest,
@@ -1809,7 +2017,11 @@ impl<'a, 'ast> AstBytecodeGen<'a, 'ast> {
                     opnd.pos(),
                 );
             }
-            Intrinsic::Float64Sqrt => {
+            Intrinsic::Float64Sqrt
+            | Intrinsic::Float64RoundToZero
+            | Intrinsic::Float64RoundUp
+            | Intrinsic::Float64RoundDown
+            | Intrinsic::Float64RoundHalfEven => {
                 self.gen.emit_push_register(src);
                 self.gen.emit_invoke_static(
                     dest,
@@ -2547,6 +2759,9 @@ impl<'a, 'ast> AstBytecodeGen<'a, 'ast> {
         let fct = fct.read();
 
         match fct.kind {
+            // atomics are emitted as regular calls, the compiler inlines them
+            // and needs the class type params for the field's type
+            FctKind::Builtin(intr) if intr.is_atomic() => None,
             FctKind::Builtin(intr) => Some(IntrinsicInfo::with_fct(intr, fid)),
             _ => None,
         }
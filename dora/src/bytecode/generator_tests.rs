@@ -1953,6 +1953,30 @@ fn gen_virtual_method_call_int_with_0_args() {
     );
 }
 
+#[test]
+fn gen_virtual_method_call_for_operator() {
+    gen(
+        "
+            fun f(a: Bar, b: Bar) -> Bool { a == b }
+            @open @abstract class Bar {
+                @open @abstract fun equals(other: Bar) -> Bool;
+            }
+            ",
+        |vm, code| {
+            let fct_id = vm
+                .cls_method_def_by_name("Bar", "equals", false)
+                .expect("equals not found");
+            let expected = vec![
+                PushRegister(r(0)),
+                PushRegister(r(1)),
+                InvokeVirtual(r(2), fct_id),
+                Ret(r(2)),
+            ];
+            assert_eq!(expected, code);
+        },
+    );
+}
+
 #[test]
 fn gen_virtual_method_call_int_with_1_arg() {
     gen(
@@ -9,19 +9,20 @@ use crate::compiler::codegen::{ensure_native_stub, should_emit_debug, Allocation
 use crate::compiler::fct::{Code, GcPoint, JitDescriptor};
 use crate::compiler::native_stub::{NativeFct, NativeFctDescriptor};
 use crate::cpu::{
-    Mem, Reg, FREG_PARAMS, FREG_RESULT, FREG_TMP1, REG_PARAMS, REG_RESULT, REG_SP, REG_TMP1,
-    REG_TMP2, STACK_FRAME_ALIGNMENT,
+    Mem, Reg, FREG_PARAMS, FREG_RESULT, FREG_TMP1, REG_PARAMS, REG_RESULT, REG_SP, REG_THREAD,
+    REG_TMP1, REG_TMP2, STACK_FRAME_ALIGNMENT,
 };
 use crate::gc::Address;
 use crate::masm::*;
 use crate::mem::{self, align_i32};
 use crate::object::{offset_of_array_data, Header, Str};
-use crate::semck::specialize::specialize_type;
+use crate::semck::specialize::{specialize_class_id_params, specialize_type};
 use crate::size::InstanceSize;
+use crate::threads::ThreadLocalData;
 use crate::ty::{BuiltinType, MachineMode, TypeList};
 use crate::vm::{
-    ClassDefId, Fct, FctDef, FctDefId, FctId, FctKind, FctSrc, FieldId, GlobalId, Intrinsic, Trap,
-    TupleId, VM,
+    ClassDefId, Fct, FctDef, FctDefId, FctId, FctKind, FctSrc, FieldId, GlobalData, GlobalId,
+    Intrinsic, Trap, TupleId, VM,
 };
 use crate::vtable::{VTable, DISPLAY_SIZE};
 
@@ -1202,8 +1203,13 @@ where
             );
         } else {
             let dest_reg = result_reg(bytecode_type);
-            self.asm
-                .load_mem(field.ty.mode(), dest_reg, Mem::Base(obj_reg, field.offset));
+            let field_mem = Mem::Base(obj_reg, field.offset);
+
+            if field.ty.reference_type() && self.vm.compressed_refs() {
+                self.asm.load_compressed_ref(dest_reg.reg(), field_mem);
+            } else {
+                self.asm.load_mem(field.ty.mode(), dest_reg, field_mem);
+            }
 
             self.emit_store_register(dest_reg.into(), dest);
         }
@@ -1264,8 +1270,13 @@ where
             let value = result_reg(bytecode_type);
 
             self.emit_load_register(src, value.into());
-            self.asm
-                .store_mem(field.ty.mode(), Mem::Base(obj_reg, field.offset), value);
+            let field_mem = Mem::Base(obj_reg, field.offset);
+
+            if field.ty.reference_type() && self.vm.compressed_refs() {
+                self.asm.store_compressed_ref(field_mem, value.reg());
+            } else {
+                self.asm.store_mem(field.ty.mode(), field_mem, value);
+            }
 
             field.ty.reference_type()
         };
@@ -1292,9 +1303,7 @@ where
             self.asm.ensure_global(&*glob, fid, ptr, glob.pos, gcpoint);
         }
 
-        let disp = self.asm.add_addr(glob.address_value.to_ptr());
-        let pos = self.asm.pos() as i32;
-        self.asm.load_constpool(REG_TMP1, disp + pos);
+        self.emit_global_address(REG_TMP1, &*glob, false);
 
         let bytecode_type = self.bytecode.register_type(dest);
 
@@ -1321,12 +1330,9 @@ where
 
         assert_eq!(self.bytecode.register_type(src), glob.ty.into());
 
-        let disp = self.asm.add_addr(glob.address_value.to_ptr());
-        let pos = self.asm.pos() as i32;
-
         let name = self.vm.interner.str(glob.name);
         self.asm.emit_comment(format!("store global {}", name));
-        self.asm.load_constpool(REG_TMP1, disp + pos);
+        self.emit_global_address(REG_TMP1, &*glob, false);
 
         let bytecode_type = self.bytecode.register_type(src);
 
@@ -1347,15 +1353,42 @@ where
         }
 
         if glob.needs_initialization() {
-            let disp = self.asm.add_addr(glob.address_init.to_ptr());
-            let pos = self.asm.pos() as i32;
-            self.asm.load_constpool(REG_RESULT, disp + pos);
+            self.emit_global_address(REG_RESULT, &*glob, true);
             self.asm.load_int_const(MachineMode::Int8, REG_TMP1, 1);
             self.asm
                 .store_mem(MachineMode::Int8, Mem::Base(REG_RESULT, 0), REG_TMP1.into());
         }
     }
 
+    // loads the address of either the global's value or its initialization
+    // flag, @threadlocal globals live in the current thread's table
+    fn emit_global_address(&mut self, dest: Reg, glob: &GlobalData, init_flag: bool) {
+        if glob.thread_local {
+            let offset = if init_flag {
+                glob.thread_local_init
+            } else {
+                glob.thread_local_value
+            };
+
+            self.asm.load_mem(
+                MachineMode::Ptr,
+                dest.into(),
+                Mem::Base(REG_THREAD, ThreadLocalData::thread_locals_offset()),
+            );
+            self.asm.lea(dest, Mem::Base(dest, offset));
+        } else {
+            let address = if init_flag {
+                glob.address_init
+            } else {
+                glob.address_value
+            };
+
+            let disp = self.asm.add_addr(address.to_ptr());
+            let pos = self.asm.pos() as i32;
+            self.asm.load_constpool(dest, disp + pos);
+        }
+    }
+
     fn emit_const_nil(&mut self, dest: Register) {
         assert_eq!(self.bytecode.register_type(dest), BytecodeType::Ptr);
 
@@ -1613,7 +1646,7 @@ where
             }
             InstanceSize::ObjArray => {
                 self.asm
-                    .determine_array_size(REG_TMP1, REG_TMP1, mem::ptr_width(), true);
+                    .determine_array_size(REG_TMP1, REG_TMP1, self.vm.ref_width(), true);
                 AllocationSize::Dynamic(REG_TMP1)
             }
             InstanceSize::UnitArray => AllocationSize::Fixed(array_header_size),
@@ -1667,7 +1700,7 @@ where
                 self.emit_array_initialization(REG_RESULT, REG_TMP1, size);
             }
             InstanceSize::ObjArray => {
-                self.emit_array_initialization(REG_RESULT, REG_TMP1, mem::ptr_width());
+                self.emit_array_initialization(REG_RESULT, REG_TMP1, self.vm.ref_width());
             }
             InstanceSize::UnitArray => {}
             _ => unreachable!(),
@@ -1837,16 +1870,20 @@ where
 
             self.emit_load_register(src, value_reg.into());
 
-            self.asm.store_mem(
-                src_type.mode(),
-                Mem::Index(
-                    REG_RESULT,
-                    REG_TMP1,
-                    src_type.mode().size(),
-                    offset_of_array_data(),
-                ),
-                value_reg,
-            );
+            let compressed = src_type.is_ptr() && self.vm.compressed_refs();
+            let element_size = if compressed {
+                self.vm.ref_width()
+            } else {
+                src_type.mode().size()
+            };
+            let element_mem =
+                Mem::Index(REG_RESULT, REG_TMP1, element_size, offset_of_array_data());
+
+            if compressed {
+                self.asm.store_compressed_ref(element_mem, value_reg.reg());
+            } else {
+                self.asm.store_mem(src_type.mode(), element_mem, value_reg);
+            }
 
             let needs_write_barrier = src_type.is_ptr();
 
@@ -1855,12 +1892,7 @@ where
                 let scratch = self.asm.get_scratch();
                 self.asm.lea(
                     *scratch,
-                    Mem::Index(
-                        REG_RESULT,
-                        REG_TMP1,
-                        src_type.mode().size(),
-                        offset_of_array_data(),
-                    ),
+                    Mem::Index(REG_RESULT, REG_TMP1, element_size, offset_of_array_data()),
                 );
                 self.asm.emit_barrier(*scratch, card_table_offset);
             }
@@ -1899,8 +1931,22 @@ where
             );
         } else {
             let register = result_reg(dest_type);
-            self.asm
-                .load_array_elem(dest_type.mode(), register, REG_RESULT, REG_TMP1);
+
+            if dest_type.is_ptr() && self.vm.compressed_refs() {
+                self.asm.load_compressed_ref(
+                    register.reg(),
+                    Mem::Index(
+                        REG_RESULT,
+                        REG_TMP1,
+                        self.vm.ref_width(),
+                        offset_of_array_data(),
+                    ),
+                );
+            } else {
+                self.asm
+                    .load_array_elem(dest_type.mode(), register, REG_RESULT, REG_TMP1);
+            }
+
             self.emit_store_register(register, dest);
         }
     }
@@ -1981,6 +2027,23 @@ where
             None
         };
 
+        let fct_def = self.vm.fct_defs.idx(fct_def_id);
+        let fct_def = fct_def.read();
+
+        let fct_id = fct_def.fct_id;
+        let fct = self.vm.fcts.idx(fct_id);
+        let fct = fct.read();
+
+        if let FctKind::Builtin(intrinsic) = fct.kind {
+            let reg = self.emit_invoke_intrinsic(&*fct, &*fct_def, intrinsic);
+
+            if let Some(dest) = dest {
+                self.emit_store_register(reg, dest);
+            }
+
+            return;
+        }
+
         let arguments = self.argument_stack.drain(..).collect::<Vec<_>>();
         let self_register = arguments[0];
 
@@ -1993,13 +2056,6 @@ where
         self.asm
             .test_if_nil_bailout(position, REG_RESULT.into(), Trap::NIL);
 
-        let fct_def = self.vm.fct_defs.idx(fct_def_id);
-        let fct_def = fct_def.read();
-
-        let fct_id = fct_def.fct_id;
-        let fct = self.vm.fcts.idx(fct_id);
-        let fct = fct.read();
-
         let cls_type_params = fct_def.cls_type_params.clone();
         let fct_type_params = fct_def.fct_type_params.clone();
 
@@ -2112,12 +2168,16 @@ where
 
     fn emit_invoke_intrinsic(
         &mut self,
-        _fct: &Fct,
-        _fct_def: &FctDef,
+        fct: &Fct,
+        fct_def: &FctDef,
         intrinsic: Intrinsic,
     ) -> AnyReg {
         let arguments = self.argument_stack.drain(..).collect::<Vec<_>>();
 
+        if intrinsic.is_atomic() {
+            return self.emit_atomic(fct, fct_def, intrinsic, arguments);
+        }
+
         match intrinsic {
             Intrinsic::Float32Sqrt | Intrinsic::Float64Sqrt => {
                 debug_assert_eq!(arguments.len(), 1);
@@ -2134,6 +2194,39 @@ where
                 FREG_RESULT.into()
             }
 
+            Intrinsic::Float32RoundToZero
+            | Intrinsic::Float32RoundUp
+            | Intrinsic::Float32RoundDown
+            | Intrinsic::Float32RoundHalfEven
+            | Intrinsic::Float64RoundToZero
+            | Intrinsic::Float64RoundUp
+            | Intrinsic::Float64RoundDown
+            | Intrinsic::Float64RoundHalfEven => {
+                debug_assert_eq!(arguments.len(), 1);
+
+                let (mode, rounding) = match intrinsic {
+                    Intrinsic::Float32RoundToZero => (MachineMode::Float32, RoundingMode::Zero),
+                    Intrinsic::Float32RoundUp => (MachineMode::Float32, RoundingMode::Up),
+                    Intrinsic::Float32RoundDown => (MachineMode::Float32, RoundingMode::Down),
+                    Intrinsic::Float32RoundHalfEven => {
+                        (MachineMode::Float32, RoundingMode::NearestEven)
+                    }
+                    Intrinsic::Float64RoundToZero => (MachineMode::Float64, RoundingMode::Zero),
+                    Intrinsic::Float64RoundUp => (MachineMode::Float64, RoundingMode::Up),
+                    Intrinsic::Float64RoundDown => (MachineMode::Float64, RoundingMode::Down),
+                    Intrinsic::Float64RoundHalfEven => {
+                        (MachineMode::Float64, RoundingMode::NearestEven)
+                    }
+                    _ => unreachable!(),
+                };
+
+                self.emit_load_register(arguments[0], FREG_RESULT.into());
+                self.asm
+                    .float_round(mode, FREG_RESULT, FREG_RESULT, rounding);
+
+                FREG_RESULT.into()
+            }
+
             Intrinsic::Int32CountZeroBits
             | Intrinsic::Int32CountZeroBitsLeading
             | Intrinsic::Int32CountZeroBitsTrailing
@@ -2216,6 +2309,142 @@ where
         }
     }
 
+    // all atomic classes store their value in the first field
+    fn emit_atomic(
+        &mut self,
+        fct: &Fct,
+        fct_def: &FctDef,
+        intrinsic: Intrinsic,
+        arguments: Vec<Register>,
+    ) -> AnyReg {
+        let cls_def_id =
+            specialize_class_id_params(self.vm, fct.cls_id(), &fct_def.cls_type_params);
+        let cls = self.vm.class_defs.idx(cls_def_id);
+        let cls = cls.read();
+        let field = &cls.fields[0];
+
+        let is_ref = field.ty.reference_type();
+        let compressed = is_ref && self.vm.compressed_refs();
+
+        let mode = if compressed {
+            MachineMode::Int32
+        } else {
+            field.ty.mode()
+        };
+
+        if mode.is_float() || field.ty.is_tuple() {
+            panic!("atomic operation on {}", field.ty.name(self.vm));
+        }
+
+        self.asm
+            .emit_comment(format!("atomic {}", fct.full_name(self.vm)));
+
+        let obj_reg = REG_TMP1;
+        self.emit_load_register(arguments[0], obj_reg.into());
+
+        let pos = self.bytecode.offset_position(self.current_offset.to_u32());
+        self.asm.test_if_nil_bailout(pos, obj_reg, Trap::NIL);
+
+        let field_mem = Mem::Base(obj_reg, field.offset);
+        let value_reg = REG_TMP2;
+
+        let stores = match intrinsic {
+            Intrinsic::AtomicInt32Load
+            | Intrinsic::AtomicInt64Load
+            | Intrinsic::AtomicBoolLoad
+            | Intrinsic::AtomicRefLoad => {
+                debug_assert_eq!(arguments.len(), 1);
+                self.asm.atomic_load(mode, REG_RESULT, field_mem);
+                false
+            }
+
+            Intrinsic::AtomicInt32Store
+            | Intrinsic::AtomicInt64Store
+            | Intrinsic::AtomicBoolStore
+            | Intrinsic::AtomicRefStore => {
+                debug_assert_eq!(arguments.len(), 2);
+                self.emit_load_register(arguments[1], value_reg.into());
+
+                if compressed {
+                    self.asm.compress_ref(value_reg);
+                }
+
+                self.asm.atomic_store(mode, field_mem, value_reg);
+                true
+            }
+
+            Intrinsic::AtomicInt32Exchange
+            | Intrinsic::AtomicInt64Exchange
+            | Intrinsic::AtomicBoolExchange
+            | Intrinsic::AtomicRefExchange => {
+                debug_assert_eq!(arguments.len(), 2);
+                self.emit_load_register(arguments[1], value_reg.into());
+
+                if compressed {
+                    self.asm.compress_ref(value_reg);
+                }
+
+                self.asm
+                    .atomic_exchange(mode, REG_RESULT, field_mem, value_reg);
+                true
+            }
+
+            Intrinsic::AtomicInt32CompareExchange
+            | Intrinsic::AtomicInt64CompareExchange
+            | Intrinsic::AtomicBoolCompareExchange
+            | Intrinsic::AtomicRefCompareExchange => {
+                debug_assert_eq!(arguments.len(), 3);
+                self.emit_load_register(arguments[1], REG_RESULT.into());
+                self.emit_load_register(arguments[2], value_reg.into());
+
+                if compressed {
+                    self.asm.compress_ref(REG_RESULT);
+                    self.asm.compress_ref(value_reg);
+                }
+
+                self.asm.atomic_compare_exchange(mode, field_mem, value_reg);
+                true
+            }
+
+            Intrinsic::AtomicInt32FetchAdd | Intrinsic::AtomicInt64FetchAdd => {
+                debug_assert_eq!(arguments.len(), 2);
+                self.emit_load_register(arguments[1], value_reg.into());
+                self.asm
+                    .atomic_fetch_add(mode, REG_RESULT, field_mem, value_reg);
+                false
+            }
+
+            _ => unreachable!(),
+        };
+
+        if stores && is_ref && self.vm.gc.needs_write_barrier() {
+            let lbl_skip_barrier = self.asm.create_label();
+
+            // a failed compareExchange didn't store anything: the previous
+            // value (still compressed) differs from the expected one
+            if intrinsic == Intrinsic::AtomicRefCompareExchange {
+                self.emit_load_register(arguments[1], value_reg.into());
+
+                if compressed {
+                    self.asm.compress_ref(value_reg);
+                }
+
+                self.asm.cmp_reg(mode, REG_RESULT, value_reg);
+                self.asm.jump_if(CondCode::NotEqual, lbl_skip_barrier);
+            }
+
+            let card_table_offset = self.vm.gc.card_table_offset();
+            self.asm.emit_barrier(obj_reg, card_table_offset);
+            self.asm.bind_label(lbl_skip_barrier);
+        }
+
+        if compressed {
+            self.asm.decompress_ref(REG_RESULT);
+        }
+
+        REG_RESULT.into()
+    }
+
     fn emit_invoke_arguments(
         &mut self,
         result_register: Option<Register>,
@@ -2347,11 +2576,17 @@ where
                 }
 
                 FctKind::Native(ptr) => {
+                    let desc = if fct.is_extern() {
+                        NativeFctDescriptor::ExternStub(fid)
+                    } else {
+                        NativeFctDescriptor::NativeStub(fid)
+                    };
+
                     let internal_fct = NativeFct {
                         ptr,
                         args: fct.params_with_self(),
                         return_type: fct.return_type,
-                        desc: NativeFctDescriptor::NativeStub(fid),
+                        desc,
                     };
 
                     ensure_native_stub(self.vm, Some(fid), internal_fct)
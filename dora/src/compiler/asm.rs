@@ -10,7 +10,7 @@ use crate::cpu::{
 };
 use crate::gc::tlab::TLAB_OBJECT_SIZE;
 use crate::gc::Address;
-use crate::masm::{CondCode, Label, MacroAssembler, ScratchReg};
+use crate::masm::{CondCode, Label, MacroAssembler, RoundingMode, ScratchReg};
 use crate::stdlib;
 use crate::threads::ThreadLocalData;
 use crate::ty::{BuiltinType, MachineMode, TypeList};
@@ -142,6 +142,42 @@ where
         self.masm.load_mem(mode, dest, mem);
     }
 
+    pub fn load_compressed_ref(&mut self, dest: Reg, mem: Mem) {
+        self.masm.load_compressed_ref(dest, mem);
+    }
+
+    pub fn store_compressed_ref(&mut self, mem: Mem, src: Reg) {
+        self.masm.store_compressed_ref(mem, src);
+    }
+
+    pub fn compress_ref(&mut self, reg: Reg) {
+        self.masm.compress_ref(reg);
+    }
+
+    pub fn decompress_ref(&mut self, reg: Reg) {
+        self.masm.decompress_ref(reg);
+    }
+
+    pub fn atomic_load(&mut self, mode: MachineMode, dest: Reg, mem: Mem) {
+        self.masm.atomic_load(mode, dest, mem);
+    }
+
+    pub fn atomic_store(&mut self, mode: MachineMode, mem: Mem, src: Reg) {
+        self.masm.atomic_store(mode, mem, src);
+    }
+
+    pub fn atomic_exchange(&mut self, mode: MachineMode, dest: Reg, mem: Mem, src: Reg) {
+        self.masm.atomic_exchange(mode, dest, mem, src);
+    }
+
+    pub fn atomic_compare_exchange(&mut self, mode: MachineMode, mem: Mem, src: Reg) {
+        self.masm.atomic_compare_exchange(mode, mem, src);
+    }
+
+    pub fn atomic_fetch_add(&mut self, mode: MachineMode, dest: Reg, mem: Mem, src: Reg) {
+        self.masm.atomic_fetch_add(mode, dest, mem, src);
+    }
+
     pub fn test_and_jump_if(&mut self, cond: CondCode, reg: Reg, lbl: Label) {
         self.masm.test_and_jump_if(cond, reg, lbl);
     }
@@ -378,6 +414,16 @@ where
         self.masm.float_sqrt(mode, dest, src);
     }
 
+    pub fn float_round(
+        &mut self,
+        mode: MachineMode,
+        dest: FReg,
+        src: FReg,
+        rounding: RoundingMode,
+    ) {
+        self.masm.float_round(mode, dest, src, rounding);
+    }
+
     pub fn copy(&mut self, mode: MachineMode, dest: AnyReg, src: AnyReg) {
         self.masm.copy(mode, dest, src);
     }
@@ -691,13 +737,24 @@ where
         let lbl_global = self.masm.create_label();
         let lbl_return = self.masm.create_label();
 
-        let disp = self.masm.add_addr(glob.address_init.to_ptr());
-        let pos = self.masm.pos() as i32;
-        self.masm.load_constpool(REG_RESULT, disp + pos);
+        let init_offset = if glob.thread_local {
+            self.masm.load_mem(
+                MachineMode::Ptr,
+                REG_RESULT.into(),
+                Mem::Base(REG_THREAD, ThreadLocalData::thread_locals_offset()),
+            );
+            glob.thread_local_init
+        } else {
+            let disp = self.masm.add_addr(glob.address_init.to_ptr());
+            let pos = self.masm.pos() as i32;
+            self.masm.load_constpool(REG_RESULT, disp + pos);
+            0
+        };
+
         self.masm.load_mem(
             MachineMode::Int8,
             REG_RESULT.into(),
-            Mem::Base(REG_RESULT, 0),
+            Mem::Base(REG_RESULT, init_offset),
         );
         self.masm.cmp_reg_imm(MachineMode::Ptr, REG_RESULT, 0);
         self.masm.jump_if(CondCode::Zero, lbl_global);
@@ -1,3 +1,4 @@
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use crate::boots;
@@ -53,7 +54,8 @@ pub fn generate_fct<'ast>(
         }
     }
 
-    let bc = if fct.use_cannon {
+    // boots doesn't know about compressed references
+    let bc = if fct.use_cannon || vm.compressed_refs() {
         CompilerName::Cannon
     } else if fct.has_optimize_immediately {
         CompilerName::Boots
@@ -61,11 +63,16 @@ pub fn generate_fct<'ast>(
         vm.args.compiler()
     };
 
+    // code collector must not free code while a compilation is running
+    vm.active_compilations.fetch_add(1, Ordering::SeqCst);
+
     let code = match bc {
         CompilerName::Cannon => cannon::compile(vm, &fct, src, cls_type_params, fct_type_params),
         CompilerName::Boots => boots::compile(vm, &fct, src, cls_type_params, fct_type_params),
     };
 
+    vm.active_compilations.fetch_sub(1, Ordering::SeqCst);
+
     if vm.args.flag_enable_perf {
         os::perf::register_with_perf(&code, vm, fct.ast.name);
     }
@@ -91,10 +98,17 @@ pub fn generate_fct<'ast>(
 
     let jit_fct_id = {
         let mut jit_fcts = vm.jit_fcts.lock();
-        let jit_fct_id = jit_fcts.len().into();
-        jit_fcts.push(Arc::new(JitFct::Compiled(code)));
+        let jit_fct = Arc::new(JitFct::Compiled(code));
 
-        jit_fct_id
+        // reuse slot of function freed by the code collector
+        if let Some(jit_fct_id) = vm.free_jit_fcts.lock().pop() {
+            jit_fcts[jit_fct_id.idx()] = jit_fct;
+            jit_fct_id
+        } else {
+            let jit_fct_id = jit_fcts.len().into();
+            jit_fcts.push(jit_fct);
+            jit_fct_id
+        }
     };
 
     {
@@ -224,20 +224,16 @@ fn compile_request(ra: usize, receiver1: Address, receiver2: Address) -> Address
             patch_fct_call(vm, ra, fct_id, cls_tps, fct_tps, disp)
         }
 
-        LazyCompilationSite::VirtCompile(
-            receiver_is_first,
-            vtable_index,
-            ref cls_tps,
-            ref fct_tps,
-        ) => patch_vtable_call(
-            vm,
-            receiver_is_first,
-            receiver1,
-            receiver2,
-            vtable_index,
-            cls_tps,
-            fct_tps,
-        ),
+        LazyCompilationSite::VirtCompile(receiver_is_first, vtable_index, _, ref fct_tps) => {
+            patch_vtable_call(
+                vm,
+                receiver_is_first,
+                receiver1,
+                receiver2,
+                vtable_index,
+                fct_tps,
+            )
+        }
     }
 }
 
@@ -247,7 +243,6 @@ fn patch_vtable_call(
     receiver1: Address,
     receiver2: Address,
     vtable_index: u32,
-    cls_tps: &TypeList,
     fct_tps: &TypeList,
 ) -> Address {
     let receiver = if receiver_is_first {
@@ -263,7 +258,24 @@ fn patch_vtable_call(
     let cls = cls.read();
 
     let fct_id = cls.virtual_fcts[vtable_index as usize];
-    let fct_ptr = compiler::generate(vm, fct_id, cls_tps, fct_tps);
+
+    // the call site only knows the type params of the receiver's static type,
+    // the method needs the type params of the class that defines it
+    let fct_cls_id = vm.fcts.idx(fct_id).read().cls_id();
+    let mut cls_def_id = vtable.class().id;
+
+    let cls_tps = loop {
+        let cls_def = vm.class_defs.idx(cls_def_id);
+        let cls_def = cls_def.read();
+
+        if cls_def.cls_id == Some(fct_cls_id) {
+            break cls_def.type_params.clone();
+        }
+
+        cls_def_id = cls_def.parent_id.expect("method not defined in superclass");
+    };
+
+    let fct_ptr = compiler::generate(vm, fct_id, &cls_tps, fct_tps);
 
     let methodtable = vtable.table_mut();
     methodtable[vtable_index as usize] = fct_ptr.to_usize();
@@ -58,8 +58,14 @@ where
             .copy_reg(MachineMode::Ptr, REG_THREAD, CCALL_REG_PARAMS[0]);
         self.masm
             .copy_reg(MachineMode::Ptr, REG_TMP1, CCALL_REG_PARAMS[1]);
-        self.masm
-            .copy_reg(MachineMode::Ptr, REG_PARAMS[0], CCALL_REG_PARAMS[2]);
+
+        // forward remaining arguments, thread and function pointer took the
+        // first two registers. Float arguments already are in place.
+        for (idx, &reg) in CCALL_REG_PARAMS.iter().enumerate().skip(2) {
+            self.masm
+                .copy_reg(MachineMode::Int64, REG_PARAMS[idx - 2], reg);
+        }
+
         self.masm.call_reg(REG_TMP1);
 
         self.masm.load_mem(
@@ -59,6 +59,12 @@ impl CodeMap {
 
         self.tree.get(&span).map(|el| *el)
     }
+
+    pub fn remove(&mut self, start: Address) -> Option<CodeDescriptor> {
+        let span = CodeSpan::new(start, start.offset(1));
+
+        self.tree.remove(&span)
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -166,6 +172,25 @@ mod tests {
         assert_eq!(None, map.get(9.into()));
     }
 
+    #[test]
+    fn test_remove() {
+        let mut map = CodeMap::new();
+
+        map.insert(5.into(), 7.into(), CodeDescriptor::DoraFct(1.into()));
+        map.insert(7.into(), 9.into(), CodeDescriptor::DoraFct(2.into()));
+
+        assert_eq!(
+            Some(CodeDescriptor::DoraFct(1.into())),
+            map.remove(5.into())
+        );
+        assert_eq!(None, map.get(6.into()));
+        assert_eq!(None, map.remove(5.into()));
+        assert_eq!(Some(CodeDescriptor::DoraFct(2.into())), map.get(7.into()));
+
+        map.insert(5.into(), 6.into(), CodeDescriptor::DoraFct(3.into()));
+        assert_eq!(Some(CodeDescriptor::DoraFct(3.into())), map.get(5.into()));
+    }
+
     #[test]
     #[should_panic]
     fn test_insert_fails() {
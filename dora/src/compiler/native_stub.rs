@@ -1,21 +1,27 @@
+use libc::c_char;
 use std::collections::hash_map::HashMap;
+use std::ffi::CString;
 use std::mem::size_of;
+use std::ptr;
+use std::slice;
 
 use crate::compiler::codegen::AnyReg;
 use crate::compiler::CodeDescriptor;
 use crate::compiler::{Code, GcPoint, JitDescriptor, JitFct, JitFctId};
 use crate::cpu::{
-    FReg, Mem, Reg, CCALL_FREG_PARAMS, CCALL_REG_PARAMS, FREG_PARAMS, FREG_TMP1, PARAM_OFFSET,
-    REG_FP, REG_PARAMS, REG_RESULT, REG_SP, REG_THREAD, REG_TMP1,
+    FReg, Mem, Reg, CCALL_FREG_PARAMS, CCALL_REG_PARAMS, FREG_PARAMS, FREG_RESULT, FREG_TMP1,
+    PARAM_OFFSET, REG_FP, REG_PARAMS, REG_RESULT, REG_SP, REG_THREAD, REG_TMP1,
 };
 use crate::gc::Address;
 use crate::masm::MacroAssembler;
 use crate::mem;
+use crate::object::{Ref, Str, UInt8Array};
+use crate::semck::externck;
 use crate::stack::DoraToNativeInfo;
-use crate::threads::ThreadLocalData;
+use crate::threads::{ThreadLocalData, THREAD};
 use crate::ty::{BuiltinType, MachineMode};
 use crate::vm::FctId;
-use crate::vm::VM;
+use crate::vm::{get_vm, VM};
 
 pub struct NativeStubs {
     map: HashMap<Address, JitFctId>,
@@ -40,6 +46,7 @@ impl NativeStubs {
 #[derive(Clone)]
 pub enum NativeFctDescriptor {
     NativeStub(FctId),
+    ExternStub(FctId),
     AllocStub,
     VerifyStub,
     TrapStub,
@@ -69,7 +76,9 @@ pub fn generate<'a, 'ast: 'a>(vm: &'a VM<'ast>, fct: NativeFct, dbg: bool) -> Ji
     let jit_fct_id: JitFctId = vm.jit_fcts.push(JitFct::Compiled(jit_fct)).into();
 
     let code_desc = match fct_desc {
-        NativeFctDescriptor::NativeStub(_) => CodeDescriptor::NativeStub(jit_fct_id),
+        NativeFctDescriptor::NativeStub(_) | NativeFctDescriptor::ExternStub(_) => {
+            CodeDescriptor::NativeStub(jit_fct_id)
+        }
         NativeFctDescriptor::TrapStub => CodeDescriptor::TrapStub,
         NativeFctDescriptor::VerifyStub => CodeDescriptor::VerifyStub,
         NativeFctDescriptor::AllocStub => CodeDescriptor::AllocStub,
@@ -94,10 +103,21 @@ where
     'ast: 'a,
 {
     pub fn generate(mut self) -> Code {
+        let is_extern = match self.fct.desc {
+            NativeFctDescriptor::ExternStub(_) => true,
+            _ => false,
+        };
         let save_return = self.fct.return_type != BuiltinType::Unit;
         let dtn_size = size_of::<DoraToNativeInfo>() as i32;
 
-        let (stack_args, temporaries, temporaries_desc, args_desc) = analyze(self.fct.args);
+        let conversions = self.extern_conversions();
+        let args = self.raw_args();
+
+        let (stack_args, temporaries, temporaries_desc, mut args_desc) = analyze(&args);
+
+        // converted arguments of extern functions get their own temporaries
+        let converted = temporaries;
+        let temporaries = temporaries + conversions.len() as u32;
 
         let offset_args = 0;
         let offset_temporaries = offset_args + stack_args as i32 * mem::ptr_width();
@@ -177,16 +197,58 @@ where
         );
 
         let mut offsets = Vec::new();
+        let mut byte_arrays = Vec::new();
 
-        for desc in args_desc {
-            let sp_offset = match desc.0 {
-                ArgumentSource::CallerArg(offset) => {
-                    framesize + PARAM_OFFSET + offset as i32 * mem::ptr_width()
+        for (idx, &(arg, conversion)) in conversions.iter().enumerate() {
+            let temporary = converted + idx as u32;
+            let sp_offset = source_offset(&args_desc[arg].0, framesize, offset_temporaries);
+            let temporary_offset = offset_temporaries + temporary as i32 * mem::ptr_width();
+
+            match conversion {
+                Conversion::CString => {
+                    self.masm.load_mem(
+                        MachineMode::Ptr,
+                        CCALL_REG_PARAMS[0].into(),
+                        Mem::Base(REG_SP, sp_offset),
+                    );
+                    self.masm.raw_call(extern_to_cstring as *const u8);
+                    self.masm.store_mem(
+                        MachineMode::Ptr,
+                        Mem::Base(REG_SP, temporary_offset),
+                        REG_RESULT.into(),
+                    );
                 }
-                ArgumentSource::Temporary(offset) => {
-                    offset_temporaries + offset as i32 * mem::ptr_width()
+
+                Conversion::ByteArray => {
+                    self.masm.load_mem(
+                        MachineMode::Ptr,
+                        CCALL_REG_PARAMS[0].into(),
+                        Mem::Base(REG_SP, sp_offset),
+                    );
+                    self.masm.raw_call(extern_to_bytes as *const u8);
+                    self.masm.store_mem(
+                        MachineMode::Ptr,
+                        Mem::Base(REG_SP, temporary_offset),
+                        REG_RESULT.into(),
+                    );
+
+                    // the array stays a root of this frame, the collector
+                    // updates the slot when it moves the array
+                    offsets.push(sp_offset - framesize);
+                    byte_arrays.push((sp_offset, temporary_offset));
                 }
-            };
+            }
+
+            args_desc[arg].0 = ArgumentSource::Temporary(temporary);
+        }
+
+        // extern functions might block, collections run in the meantime
+        if is_extern {
+            self.masm.raw_call(extern_park as *const u8);
+        }
+
+        for desc in args_desc {
+            let sp_offset = source_offset(&desc.0, framesize, offset_temporaries);
 
             match desc.1 {
                 ArgumentDestination::FloatRegister(mode, reg) => {
@@ -236,6 +298,58 @@ where
                 .fix_result(REG_RESULT, self.fct.return_type.mode());
         }
 
+        if is_extern {
+            let mode = self.fct.return_type.mode();
+            let result: AnyReg = if mode.is_float() {
+                FREG_RESULT.into()
+            } else {
+                REG_RESULT.into()
+            };
+
+            if save_return {
+                self.masm
+                    .store_mem(mode, Mem::Base(REG_SP, offset_return), result);
+            }
+
+            self.masm.raw_call(extern_unpark as *const u8);
+
+            for (array_offset, data_offset) in byte_arrays {
+                self.masm.load_mem(
+                    MachineMode::Ptr,
+                    CCALL_REG_PARAMS[0].into(),
+                    Mem::Base(REG_SP, array_offset),
+                );
+                self.masm.load_mem(
+                    MachineMode::Ptr,
+                    CCALL_REG_PARAMS[1].into(),
+                    Mem::Base(REG_SP, data_offset),
+                );
+                self.masm.raw_call(extern_from_bytes as *const u8);
+            }
+
+            for (idx, &(_, conversion)) in conversions.iter().enumerate() {
+                if conversion != Conversion::CString {
+                    continue;
+                }
+
+                let temporary = converted + idx as u32;
+                self.masm.load_mem(
+                    MachineMode::Ptr,
+                    CCALL_REG_PARAMS[0].into(),
+                    Mem::Base(
+                        REG_SP,
+                        offset_temporaries + temporary as i32 * mem::ptr_width(),
+                    ),
+                );
+                self.masm.raw_call(extern_free_cstring as *const u8);
+            }
+
+            if save_return {
+                self.masm
+                    .load_mem(mode, result, Mem::Base(REG_SP, offset_return));
+            }
+        }
+
         self.masm.load_mem(
             MachineMode::Ptr,
             REG_TMP1.into(),
@@ -252,7 +366,9 @@ where
         self.masm.nop();
 
         let desc = match self.fct.desc {
-            NativeFctDescriptor::NativeStub(fid) => JitDescriptor::NativeStub(fid),
+            NativeFctDescriptor::NativeStub(fid) | NativeFctDescriptor::ExternStub(fid) => {
+                JitDescriptor::NativeStub(fid)
+            }
             NativeFctDescriptor::AllocStub => JitDescriptor::AllocStub,
             NativeFctDescriptor::VerifyStub => JitDescriptor::VerifyStub,
             NativeFctDescriptor::TrapStub => JitDescriptor::TrapStub,
@@ -261,6 +377,124 @@ where
 
         self.masm.jit(self.vm, framesize, desc)
     }
+
+    // arguments of extern functions that need to be converted before the call
+    fn extern_conversions(&self) -> Vec<(usize, Conversion)> {
+        match self.fct.desc {
+            NativeFctDescriptor::ExternStub(_) => {}
+            _ => return Vec::new(),
+        }
+
+        let mut conversions = Vec::new();
+
+        for (idx, &ty) in self.fct.args.iter().enumerate() {
+            if externck::is_string(self.vm, ty) {
+                conversions.push((idx, Conversion::CString));
+            } else if externck::is_byte_array(self.vm, ty) {
+                conversions.push((idx, Conversion::ByteArray));
+            }
+        }
+
+        conversions
+    }
+
+    // extern functions get raw pointers instead of handles for references,
+    // these are passed like any other pointer-sized integer. The pointers
+    // refer to copies in native memory, the thread is parked during the
+    // call and collections might move the objects in the meantime.
+    fn raw_args(&self) -> Vec<BuiltinType> {
+        match self.fct.desc {
+            NativeFctDescriptor::ExternStub(_) => self
+                .fct
+                .args
+                .iter()
+                .map(|ty| {
+                    if ty.reference_type() {
+                        BuiltinType::Int64
+                    } else {
+                        *ty
+                    }
+                })
+                .collect(),
+            _ => self.fct.args.to_vec(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Conversion {
+    CString,
+    ByteArray,
+}
+
+// NUL-terminated copy of the string, C only sees the content up to the
+// first NUL character.
+extern "C" fn extern_to_cstring(string: Ref<Str>) -> *mut c_char {
+    if string.raw().is_null() {
+        return ptr::null_mut();
+    }
+
+    let content = string.content();
+    let len = content
+        .iter()
+        .position(|&b| b == 0)
+        .unwrap_or(content.len());
+
+    CString::new(&content[..len]).unwrap().into_raw()
+}
+
+extern "C" fn extern_free_cstring(string: *mut c_char) {
+    if !string.is_null() {
+        unsafe {
+            CString::from_raw(string);
+        }
+    }
+}
+
+// copy of the array's content in native memory
+extern "C" fn extern_to_bytes(array: Ref<UInt8Array>) -> *mut u8 {
+    if array.raw().is_null() {
+        return ptr::null_mut();
+    }
+
+    let data = unsafe { slice::from_raw_parts(array.data(), array.len()) };
+    let data: Box<[u8]> = data.into();
+
+    Box::into_raw(data) as *mut u8
+}
+
+// copies the content back into the array, which might have been moved
+// during the call, and frees the copy
+extern "C" fn extern_from_bytes(mut array: Ref<UInt8Array>, data: *mut u8) {
+    if data.is_null() {
+        return;
+    }
+
+    let len = array.len();
+
+    unsafe {
+        let data = Box::from_raw(slice::from_raw_parts_mut(data, len));
+        ptr::copy_nonoverlapping(data.as_ptr(), array.data_mut(), len);
+    }
+}
+
+extern "C" fn extern_park() {
+    let thread = THREAD.with(|thread| thread.borrow().clone());
+    thread.park(get_vm());
+}
+
+extern "C" fn extern_unpark() {
+    let thread = THREAD.with(|thread| thread.borrow().clone());
+    thread.unpark(get_vm());
+}
+
+fn source_offset(source: &ArgumentSource, framesize: i32, offset_temporaries: i32) -> i32 {
+    match *source {
+        ArgumentSource::CallerArg(offset) => {
+            framesize + PARAM_OFFSET + offset as i32 * mem::ptr_width()
+        }
+        ArgumentSource::Temporary(offset) => offset_temporaries + offset as i32 * mem::ptr_width(),
+    }
 }
 
 fn analyze(
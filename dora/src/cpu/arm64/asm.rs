@@ -154,6 +154,25 @@ fn cls_cond_branch_imm(cond: Cond, imm19: i32) -> u32 {
     0b01010100u32 << 24 | imm << 5 | cond.u32()
 }
 
+pub fn cbz(sf: u32, rt: Reg, imm19: i32) -> u32 {
+    cls_cmp_branch_imm(sf, 0, rt, imm19)
+}
+
+pub fn cbnz(sf: u32, rt: Reg, imm19: i32) -> u32 {
+    cls_cmp_branch_imm(sf, 1, rt, imm19)
+}
+
+fn cls_cmp_branch_imm(sf: u32, op: u32, rt: Reg, imm19: i32) -> u32 {
+    assert!(fits_bit(sf));
+    assert!(fits_bit(op));
+    assert!(fits_i19(imm19));
+    assert!(rt.is_gpr());
+
+    let imm = (imm19 as u32) & 0x7FFFF;
+
+    sf << 31 | 0b011010u32 << 25 | op << 24 | imm << 5 | rt.asm()
+}
+
 pub fn nop() -> u32 {
     cls_system(0)
 }
@@ -432,6 +451,56 @@ fn cls_ldst_regimm(size: u32, v: u32, opc: u32, imm12: u32, rn: Reg, rt: u32) ->
     0b111001u32 << 24 | size << 30 | v << 26 | opc << 22 | imm12 << 10 | rn.asm() << 5 | rt
 }
 
+// size is 0b00 for bytes, 0b10 for words and 0b11 for double words
+pub fn ldar(size: u32, rt: Reg, rn: Reg) -> u32 {
+    cls_ldst_exclusive(size, 1, 1, 0, REG_ZERO, 1, rn, rt)
+}
+
+pub fn stlr(size: u32, rt: Reg, rn: Reg) -> u32 {
+    cls_ldst_exclusive(size, 1, 0, 0, REG_ZERO, 1, rn, rt)
+}
+
+pub fn ldaxr(size: u32, rt: Reg, rn: Reg) -> u32 {
+    cls_ldst_exclusive(size, 0, 1, 0, REG_ZERO, 1, rn, rt)
+}
+
+pub fn stlxr(size: u32, rs: Reg, rt: Reg, rn: Reg) -> u32 {
+    assert!(rs.is_gpr());
+    cls_ldst_exclusive(size, 0, 0, 0, rs, 1, rn, rt)
+}
+
+fn cls_ldst_exclusive(
+    size: u32,
+    o2: u32,
+    l: u32,
+    o1: u32,
+    rs: Reg,
+    o0: u32,
+    rn: Reg,
+    rt: Reg,
+) -> u32 {
+    assert!(fits_u2(size));
+    assert!(fits_bit(o2));
+    assert!(fits_bit(l));
+    assert!(fits_bit(o1));
+    assert!(fits_bit(o0));
+    assert!(rn.is_gpr_or_sp());
+    assert!(rt.is_gpr());
+
+    let rt2 = 0b11111;
+
+    size << 30
+        | 0b001000u32 << 24
+        | o2 << 23
+        | l << 22
+        | o1 << 21
+        | rs.asm() << 16
+        | o0 << 15
+        | rt2 << 10
+        | rn.asm() << 5
+        | rt.asm()
+}
+
 pub fn ldrb_unscaled_imm(rt: Reg, rn: Reg, imm9: i32) -> u32 {
     assert!(rt.is_gpr());
     cls_ldst_reg_unscaledimm(0b00, 0, 0b01, imm9, rn, rt.asm())
@@ -1024,6 +1093,22 @@ pub fn fsqrt(ty: u32, rd: FReg, rn: FReg) -> u32 {
     cls_fp_dataproc1(0, 0, ty, 0b000011, rn, rd)
 }
 
+pub fn frintn(ty: u32, rd: FReg, rn: FReg) -> u32 {
+    cls_fp_dataproc1(0, 0, ty, 0b001000, rn, rd)
+}
+
+pub fn frintp(ty: u32, rd: FReg, rn: FReg) -> u32 {
+    cls_fp_dataproc1(0, 0, ty, 0b001001, rn, rd)
+}
+
+pub fn frintm(ty: u32, rd: FReg, rn: FReg) -> u32 {
+    cls_fp_dataproc1(0, 0, ty, 0b001010, rn, rd)
+}
+
+pub fn frintz(ty: u32, rd: FReg, rn: FReg) -> u32 {
+    cls_fp_dataproc1(0, 0, ty, 0b001011, rn, rd)
+}
+
 pub fn fcvt_sd(rd: FReg, rn: FReg) -> u32 {
     cls_fp_dataproc1(0, 0, 0b00, 0b000101, rn, rd)
 }
@@ -1691,6 +1776,28 @@ mod tests {
         assert_emit!(0x94000001; bl_imm(1));
     }
 
+    #[test]
+    fn test_cbz_cbnz() {
+        assert_emit!(0x34ffffe0; cbz(0, R0, -1));
+        assert_emit!(0xb4000041; cbz(1, R1, 2));
+        assert_emit!(0x35ffffe0; cbnz(0, R0, -1));
+        assert_emit!(0xb5000041; cbnz(1, R1, 2));
+    }
+
+    #[test]
+    fn test_ldst_exclusive() {
+        assert_emit!(0x08dffc20; ldar(0b00, R0, R1));
+        assert_emit!(0x88dffc20; ldar(0b10, R0, R1));
+        assert_emit!(0xc8dffc20; ldar(0b11, R0, R1));
+        assert_emit!(0x889ffc20; stlr(0b10, R0, R1));
+        assert_emit!(0xc89ffc20; stlr(0b11, R0, R1));
+        assert_emit!(0x885ffc20; ldaxr(0b10, R0, R1));
+        assert_emit!(0xc85ffc20; ldaxr(0b11, R0, R1));
+        assert_emit!(0x8802fc20; stlxr(0b10, R2, R0, R1));
+        assert_emit!(0xc802fc20; stlxr(0b11, R2, R0, R1));
+        assert_emit!(0x0802fc20; stlxr(0b00, R2, R0, R1));
+    }
+
     #[test]
     fn test_b_cond_imm() {
         assert_emit!(0x54ffffe0; b_cond_imm(Cond::EQ, -1));
@@ -1976,6 +2083,18 @@ mod tests {
         assert_eq!(0x1e61c149, fsqrt(1, F9, F10)); // fsqrt d9, d10
     }
 
+    #[test]
+    fn test_frint() {
+        assert_eq!(0x1e244020, frintn(0, F0, F1)); // frintn s0, s1
+        assert_eq!(0x1e644149, frintn(1, F9, F10)); // frintn d9, d10
+        assert_eq!(0x1e24c020, frintp(0, F0, F1)); // frintp s0, s1
+        assert_eq!(0x1e64c020, frintp(1, F0, F1)); // frintp d0, d1
+        assert_eq!(0x1e254020, frintm(0, F0, F1)); // frintm s0, s1
+        assert_eq!(0x1e654149, frintm(1, F9, F10)); // frintm d9, d10
+        assert_eq!(0x1e25c020, frintz(0, F0, F1)); // frintz s0, s1
+        assert_eq!(0x1e65c020, frintz(1, F0, F1)); // frintz d0, d1
+    }
+
     #[test]
     fn test_lsl_imm() {
         assert_eq!(0xd37ff820, lsl_imm(1, R0, R1, 1)); // lsl x0, x1, #1
@@ -47,15 +47,18 @@ Options:
     --gc-parallel-minor     Enable parallel minor collection.
     --gc-parallel           Enable both parallel minor and full collection.
     --gc-stats              Print GC statistics.
+    --gc-log=<path>         Write JSON record for each collection into file.
     --gc-verbose            Verbose GC.
     --gc-dev-verbose        Verbose GC for developers.
     --gc-verify             Verify heap before and after collections.
     --gc-verify-write       Verify references when storing in the heap.
     --gc-worker=<num>       Number of GC worker threads.
-    --gc=<name>             Switch GC. Possible values: zero, copy, swiper (default).
+    --gc=<name>             Switch GC. Possible values: zero, copy, region, swiper (default).
     --gc-young-size=<SIZE>  Use fixed size for young generation.
-    --gc-young-appel        Use Appel dynamic resizing of young generation.
+    --gc-young-appel        Use Appel dynamic resizing of young generation (default).
+    --gc-pause-target=<ms>  Resize young generation to meet given minor pause time.
     --gc-semi-ratio=<num>   Use fixed ratio of semi space in young generation.
+    --gc-idle-shrink        Shrink young generation when heap stays mostly empty.
 
     --compiler=<name>       Switch default compiler. Possible values: cannon [default: cannon].
     --test-filter=<name>    Filter tests.
@@ -63,6 +66,9 @@ Options:
 
     --disable-tlab          Disable tlab allocation.
     --disable-barrier       Disable barriers.
+    --compressed-refs       Store references in the heap as 32-bit values.
+    --check-lock-order      Report mutexes locked in inconsistent order.
+                            Always enabled in debug builds.
 
     --min-heap-size=<SIZE>  Set minimum heap size.
     --max-heap-size=<SIZE>  Set maximum heap size.
@@ -99,12 +105,16 @@ pub struct Args {
     flag_gc_parallel_minor: bool,
     flag_gc_parallel: bool,
     pub flag_gc_stats: bool,
+    pub flag_gc_log: Option<String>,
     pub flag_gc_verbose: bool,
     pub flag_gc_dev_verbose: bool,
     pub flag_gc_verify: bool,
     pub flag_gc_worker: usize,
     flag_gc_young_size: Option<MemSize>,
+    flag_gc_young_appel: bool,
+    flag_gc_pause_target: Option<f32>,
     pub flag_gc_semi_ratio: Option<usize>,
+    pub flag_gc_idle_shrink: bool,
     pub flag_gc: Option<CollectorName>,
     pub flag_compiler: Option<CompilerName>,
     pub flag_min_heap_size: Option<MemSize>,
@@ -114,6 +124,8 @@ pub struct Args {
     pub flag_check: bool,
     pub flag_disable_tlab: bool,
     pub flag_disable_barrier: bool,
+    pub flag_compressed_refs: bool,
+    flag_check_lock_order: bool,
     pub flag_stdlib: Option<String>,
     pub flag_boots: Option<String>,
     pub flag_test_filter: Option<String>,
@@ -156,12 +168,16 @@ impl Args {
         }
     }
 
-    pub fn young_size(&self) -> Option<usize> {
-        self.flag_gc_young_size.map(|young_size| *young_size)
-    }
-
-    pub fn young_appel(&self) -> bool {
-        self.flag_gc_young_size.is_none()
+    pub fn young_policy(&self) -> YoungGenPolicy {
+        if let Some(young_size) = self.flag_gc_young_size {
+            YoungGenPolicy::Fixed(*young_size)
+        } else if self.flag_gc_young_appel {
+            YoungGenPolicy::Appel
+        } else if let Some(pause_target) = self.flag_gc_pause_target {
+            YoungGenPolicy::PauseTarget(pause_target)
+        } else {
+            YoungGenPolicy::Appel
+        }
     }
 
     pub fn parallel_minor(&self) -> bool {
@@ -172,6 +188,14 @@ impl Args {
         self.flag_gc_parallel_full || self.flag_gc_parallel
     }
 
+    pub fn gc_phases(&self) -> bool {
+        self.flag_gc_stats || self.flag_gc_log.is_some()
+    }
+
+    pub fn check_lock_order(&self) -> bool {
+        self.flag_check_lock_order || cfg!(debug_assertions)
+    }
+
     pub fn compiler(&self) -> CompilerName {
         self.flag_compiler.unwrap_or(CompilerName::Cannon)
     }
@@ -204,12 +228,16 @@ impl Default for Args {
             flag_gc_parallel_minor: false,
             flag_gc_parallel: false,
             flag_gc_stats: false,
+            flag_gc_log: None,
             flag_gc_verbose: false,
             flag_gc_dev_verbose: false,
             flag_gc_verify: false,
             flag_gc_worker: 0,
             flag_gc_young_size: None,
+            flag_gc_young_appel: false,
+            flag_gc_pause_target: None,
             flag_gc_semi_ratio: None,
+            flag_gc_idle_shrink: false,
             flag_gc: None,
             flag_compiler: None,
             flag_min_heap_size: None,
@@ -219,6 +247,8 @@ impl Default for Args {
             flag_check: false,
             flag_disable_tlab: false,
             flag_disable_barrier: false,
+            flag_compressed_refs: false,
+            flag_check_lock_order: false,
             flag_stdlib: None,
             flag_boots: None,
             flag_test_filter: None,
@@ -235,10 +265,24 @@ pub enum CollectorName {
     Compact,
     Copy,
     Sweep,
+    Region,
     Swiper,
     SweepSwiper,
 }
 
+#[derive(Copy, Clone, Debug)]
+pub enum YoungGenPolicy {
+    // young generation always has the given size
+    Fixed(usize),
+
+    // young generation gets half of the heap not used by the old generation
+    Appel,
+
+    // young generation is resized such that minor collections stay below
+    // the given pause time in ms
+    PauseTarget(f32),
+}
+
 #[derive(Copy, Clone, Debug, Deserialize)]
 pub enum CompilerName {
     Cannon,
@@ -105,6 +105,7 @@ fn parse_all_files(vm: &mut VM, ast: &mut Ast, content: Option<&str>) -> Result<
 
     if let Some(stdlib) = stdlib_dir {
         parse_dir(&stdlib, vm, ast)?;
+        vm.stdlib_files = vm.files.len();
     } else {
         parse_bundled_stdlib(vm, ast)?;
     }
@@ -293,6 +294,8 @@ pub fn parse_bundled_stdlib(vm: &mut VM, ast: &mut Ast) -> Result<(), i32> {
         parse_bundled_stdlib_file(filename, content, vm, ast)?;
     }
 
+    vm.stdlib_files = vm.files.len();
+
     Ok(())
 }
 
@@ -0,0 +1,594 @@
+//! API for host programs that embed the VM: a `Builder` parses and checks
+//! in-memory sources, the resulting `Vm` looks up functions and calls them.
+//!
+//! Only one `Vm` can be alive at a time since the VM is stored in a global,
+//! `Builder::build` fails while another one wasn't dropped yet. A `Vm` is
+//! bound to the thread that created it.
+
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, MutexGuard};
+use std::mem;
+use std::thread::{self, ThreadId};
+
+use dora_parser::ast::Ast;
+use dora_parser::lexer::reader::Reader;
+use dora_parser::parser::Parser;
+
+use crate::cpu::{CCALL_FREG_PARAMS, CCALL_REG_PARAMS};
+use crate::driver::start::parse_bundled_stdlib;
+use crate::gc::Address;
+use crate::handle;
+use crate::semck;
+use crate::threads::{DoraThread, STACK_SIZE, THREAD};
+use crate::ty::BuiltinType;
+use crate::vm::{self, stack_pointer, FctId, VM};
+
+pub use crate::driver::cmd::Args;
+pub use crate::handle::Handle;
+pub use crate::object::{Obj, Ref, Str};
+
+lazy_static! {
+    static ref ACTIVE_VM: Mutex<()> = Mutex::new(());
+}
+
+#[derive(Default)]
+pub struct Builder {
+    args: Args,
+    sources: Vec<(String, String)>,
+    natives: Vec<(String, Address)>,
+}
+
+impl Builder {
+    pub fn new() -> Builder {
+        Default::default()
+    }
+
+    pub fn args(mut self, args: Args) -> Builder {
+        self.args = args;
+        self
+    }
+
+    /// adds a source file, the bundled stdlib is always parsed first.
+    pub fn source(mut self, name: &str, code: &str) -> Builder {
+        self.sources.push((name.into(), code.into()));
+        self
+    }
+
+    /// implements the top-level `@internal fun` with the given name with a
+    /// host function. `fctptr` needs to be an `extern "C" fn` with parameters
+    /// matching the Dora declaration, objects are passed as `Handle`s.
+    pub fn native(mut self, name: &str, fctptr: *const u8) -> Builder {
+        self.natives.push((name.into(), Address::from_ptr(fctptr)));
+        self
+    }
+
+    /// fails when another `Vm` is still alive.
+    pub fn build(self) -> Result<Vm, String> {
+        let active = match ACTIVE_VM.try_lock() {
+            Some(active) => active,
+            None => return Err("another Vm is still alive.".into()),
+        };
+
+        let ast = Box::into_raw(box Ast::new());
+        let empty = Box::into_raw(box Ast::new());
+        let vm = VM::new(self.args, unsafe { &*empty });
+
+        let mut embedded = Vm {
+            vm: Some(vm),
+            ast,
+            empty,
+            thread: thread::current().id(),
+            attached: false,
+            _active: active,
+        };
+
+        {
+            let vm = embedded.vm.as_mut().unwrap();
+            let ast = unsafe { &mut *ast };
+            vm.host_fcts = self.natives;
+
+            if parse_bundled_stdlib(vm, ast).is_err() {
+                return Err("failed parsing stdlib.".into());
+            }
+
+            for (name, code) in &self.sources {
+                let reader = Reader::from_string(name, code);
+                let parser = Parser::new(reader, &vm.id_generator, ast, &mut vm.interner);
+
+                match parser.parse() {
+                    Ok(file) => vm.files.push(file),
+                    Err(error) => {
+                        return Err(format!(
+                            "error in {} at {}: {}",
+                            name,
+                            error.pos,
+                            error.error.message()
+                        ));
+                    }
+                }
+            }
+
+            vm.ast = unsafe { &*embedded.ast };
+
+            semck::check(vm);
+            semck::prelude::install_conditional_intrinsics(vm);
+
+            let diag = vm.diag.lock();
+
+            if diag.has_errors() {
+                let messages: Vec<String> = diag
+                    .errors()
+                    .iter()
+                    .map(|error| error.message(vm))
+                    .collect();
+                return Err(messages.join("\n"));
+            }
+        }
+
+        // the thread stays parked while the host runs,
+        // it only runs Dora code during `call`
+        embedded.attached = true;
+        let vm = embedded.vm();
+        vm.threads.attach_current_thread();
+        current_thread().park(vm);
+
+        Ok(embedded)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Function(FctId);
+
+pub struct Vm {
+    vm: Option<Box<VM<'static>>>,
+    ast: *mut Ast,
+    empty: *mut Ast,
+    thread: ThreadId,
+    attached: bool,
+    _active: MutexGuard<'static, ()>,
+}
+
+impl Vm {
+    fn vm(&self) -> &VM<'static> {
+        self.vm.as_ref().unwrap()
+    }
+
+    /// looks up a top-level function.
+    pub fn function(&self, name: &str) -> Option<Function> {
+        let vm = self.vm();
+        let name = vm.interner.intern(name);
+        let fct_id = vm.sym.lock().get_fct(name);
+        fct_id.map(Function)
+    }
+
+    /// calls the function with the given tuple of arguments, returned
+    /// objects are rooted in a `Handle` of the current handle scope.
+    pub fn call<A: Arguments, R: ReturnValue>(&self, fct: Function, args: A) -> Result<R, String> {
+        assert_eq!(
+            self.thread,
+            thread::current().id(),
+            "Vm used on wrong thread"
+        );
+        let vm = self.vm();
+
+        {
+            let fct = vm.fcts.idx(fct.0);
+            let fct = fct.read();
+            let name = vm.interner.str(fct.name);
+
+            if !fct.type_params.is_empty() {
+                return Err(format!("function `{}` is generic.", name));
+            }
+
+            if !A::accepts(vm, &fct.param_types) {
+                return Err(format!("wrong arguments for function `{}`.", name));
+            }
+
+            if !R::accepts(vm, fct.return_type) {
+                return Err(format!("wrong return type for function `{}`.", name));
+            }
+        }
+
+        let mut values = Values::new();
+        args.push(&mut values);
+
+        if values.ints.len() > CCALL_REG_PARAMS.len() - 2
+            || values.floats.len() > CCALL_FREG_PARAMS.len()
+        {
+            return Err("too many arguments.".into());
+        }
+
+        let thread = current_thread();
+        thread.unpark(vm);
+
+        let stack_limit = stack_pointer().sub(STACK_SIZE);
+        thread.tld.set_stack_limit(stack_limit);
+
+        let tld = Address::from_ptr(&thread.tld as *const _);
+        let ptr = vm.ensure_compiled(fct.0);
+        let result = call_stub(vm.dora_stub(), tld, ptr, &values, R::FLOAT);
+
+        thread.park(vm);
+
+        Ok(R::from_raw(result))
+    }
+
+    /// allocates a string in the heap.
+    pub fn string(&self, value: &str) -> Handle<Str> {
+        assert_eq!(
+            self.thread,
+            thread::current().id(),
+            "Vm used on wrong thread"
+        );
+        let vm = self.vm();
+        let thread = current_thread();
+
+        thread.unpark(vm);
+        let string = handle::root(Str::from_buffer(vm, value.as_bytes()));
+        thread.park(vm);
+
+        string
+    }
+
+    /// handles created in `f` are freed when it returns.
+    pub fn scope<F: FnOnce() -> R, R>(&self, f: F) -> R {
+        handle::scope(f)
+    }
+}
+
+impl Drop for Vm {
+    fn drop(&mut self) {
+        if self.attached {
+            let vm = self.vm();
+            current_thread().unpark(vm);
+            vm.threads.detach_current_thread();
+            vm.threads.join_all();
+        }
+
+        self.vm = None;
+        vm::clear_vm();
+
+        // the next VM starts with fresh handles and TLAB on this thread
+        THREAD.with(|thread| {
+            *thread.borrow_mut() = DoraThread::main();
+        });
+
+        unsafe {
+            drop(Box::from_raw(self.ast));
+            drop(Box::from_raw(self.empty));
+        }
+    }
+}
+
+fn current_thread() -> std::sync::Arc<DoraThread> {
+    THREAD.with(|thread| thread.borrow().clone())
+}
+
+pub struct Values {
+    ints: Vec<u64>,
+    floats: Vec<f64>,
+}
+
+impl Values {
+    fn new() -> Values {
+        Values {
+            ints: Vec::new(),
+            floats: Vec::new(),
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub enum RawValue {
+    Int(u64),
+    Float(f64),
+}
+
+impl RawValue {
+    fn int(self) -> u64 {
+        match self {
+            RawValue::Int(value) => value,
+            RawValue::Float(_) => unreachable!(),
+        }
+    }
+
+    fn float(self) -> f64 {
+        match self {
+            RawValue::Float(value) => value,
+            RawValue::Int(_) => unreachable!(),
+        }
+    }
+}
+
+type IntStub = extern "C" fn(
+    Address,
+    Address,
+    u64,
+    u64,
+    u64,
+    u64,
+    u64,
+    u64,
+    f64,
+    f64,
+    f64,
+    f64,
+    f64,
+    f64,
+    f64,
+    f64,
+) -> u64;
+type FloatStub = extern "C" fn(
+    Address,
+    Address,
+    u64,
+    u64,
+    u64,
+    u64,
+    u64,
+    u64,
+    f64,
+    f64,
+    f64,
+    f64,
+    f64,
+    f64,
+    f64,
+    f64,
+) -> f64;
+
+// passes more arguments than needed, the dora stub forwards all
+// argument registers and the callee ignores the surplus.
+fn call_stub(stub: Address, tld: Address, ptr: Address, values: &Values, float: bool) -> RawValue {
+    let mut i = [0u64; 6];
+    let mut f = [0f64; 8];
+
+    i[..values.ints.len()].copy_from_slice(&values.ints);
+    f[..values.floats.len()].copy_from_slice(&values.floats);
+
+    if float {
+        let fct: FloatStub = unsafe { mem::transmute(stub) };
+        RawValue::Float(fct(
+            tld, ptr, i[0], i[1], i[2], i[3], i[4], i[5], f[0], f[1], f[2], f[3], f[4], f[5], f[6],
+            f[7],
+        ))
+    } else {
+        let fct: IntStub = unsafe { mem::transmute(stub) };
+        RawValue::Int(fct(
+            tld, ptr, i[0], i[1], i[2], i[3], i[4], i[5], f[0], f[1], f[2], f[3], f[4], f[5], f[6],
+            f[7],
+        ))
+    }
+}
+
+/// a value that can be passed to a Dora function.
+pub trait Argument {
+    fn accepts(vm: &VM, ty: BuiltinType) -> bool;
+    fn push(self, values: &mut Values);
+}
+
+macro_rules! int_argument {
+    ($ty:ty, $builtin:ident) => {
+        impl Argument for $ty {
+            fn accepts(_: &VM, ty: BuiltinType) -> bool {
+                ty == BuiltinType::$builtin
+            }
+
+            fn push(self, values: &mut Values) {
+                values.ints.push(self as u64);
+            }
+        }
+    };
+}
+
+int_argument!(bool, Bool);
+int_argument!(u8, UInt8);
+int_argument!(char, Char);
+int_argument!(i32, Int32);
+int_argument!(i64, Int64);
+
+impl Argument for f32 {
+    fn accepts(_: &VM, ty: BuiltinType) -> bool {
+        ty == BuiltinType::Float32
+    }
+
+    fn push(self, values: &mut Values) {
+        values.floats.push(f64::from_bits(self.to_bits() as u64));
+    }
+}
+
+impl Argument for f64 {
+    fn accepts(_: &VM, ty: BuiltinType) -> bool {
+        ty == BuiltinType::Float64
+    }
+
+    fn push(self, values: &mut Values) {
+        values.floats.push(self);
+    }
+}
+
+impl<T: HostObject> Argument for Handle<T> {
+    fn accepts(vm: &VM, ty: BuiltinType) -> bool {
+        T::accepts_param(vm, ty)
+    }
+
+    fn push(self, values: &mut Values) {
+        values.ints.push(self.direct().address().to_usize() as u64);
+    }
+}
+
+/// an object type that can be passed to or returned from Dora in a `Handle`.
+/// Implementations need to make sure that a `Handle<Self>` only ever refers
+/// to objects with the layout of `Self`.
+pub unsafe trait HostObject {
+    fn accepts_param(vm: &VM, ty: BuiltinType) -> bool;
+    fn accepts_return(vm: &VM, ty: BuiltinType) -> bool;
+}
+
+unsafe impl HostObject for Str {
+    fn accepts_param(vm: &VM, ty: BuiltinType) -> bool {
+        let cls_id = ty.cls_id(vm);
+        cls_id == Some(vm.vips.string_class) || cls_id == Some(vm.vips.object_class)
+    }
+
+    fn accepts_return(vm: &VM, ty: BuiltinType) -> bool {
+        ty.cls_id(vm) == Some(vm.vips.string_class)
+    }
+}
+
+// any object can be returned as `Obj`, the exact class is unknown
+// though and can only be passed to parameters of type `Object`.
+unsafe impl HostObject for Obj {
+    fn accepts_param(vm: &VM, ty: BuiltinType) -> bool {
+        ty.cls_id(vm) == Some(vm.vips.object_class)
+    }
+
+    fn accepts_return(_: &VM, ty: BuiltinType) -> bool {
+        ty.reference_type()
+    }
+}
+
+/// the argument list of a call, implemented for tuples of `Argument`s.
+pub trait Arguments {
+    fn accepts(vm: &VM, tys: &[BuiltinType]) -> bool;
+    fn push(self, values: &mut Values);
+}
+
+macro_rules! arguments {
+    ($len:expr; $($name:ident $idx:tt),*) => {
+        impl<$($name: Argument),*> Arguments for ($($name,)*) {
+            #[allow(unused_variables)]
+            fn accepts(vm: &VM, tys: &[BuiltinType]) -> bool {
+                tys.len() == $len $(&& $name::accepts(vm, tys[$idx]))*
+            }
+
+            #[allow(unused_variables)]
+            fn push(self, values: &mut Values) {
+                $(self.$idx.push(values);)*
+            }
+        }
+    };
+}
+
+arguments!(0;);
+arguments!(1; A 0);
+arguments!(2; A 0, B 1);
+arguments!(3; A 0, B 1, C 2);
+arguments!(4; A 0, B 1, C 2, D 3);
+arguments!(5; A 0, B 1, C 2, D 3, E 4);
+arguments!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+
+/// a value that can be returned from a Dora function.
+pub trait ReturnValue: Sized {
+    const FLOAT: bool;
+
+    fn accepts(vm: &VM, ty: BuiltinType) -> bool;
+    fn from_raw(value: RawValue) -> Self;
+}
+
+impl ReturnValue for () {
+    const FLOAT: bool = false;
+
+    fn accepts(_: &VM, ty: BuiltinType) -> bool {
+        ty.is_unit()
+    }
+
+    fn from_raw(_: RawValue) {}
+}
+
+impl ReturnValue for bool {
+    const FLOAT: bool = false;
+
+    fn accepts(_: &VM, ty: BuiltinType) -> bool {
+        ty == BuiltinType::Bool
+    }
+
+    fn from_raw(value: RawValue) -> bool {
+        value.int() as u8 != 0
+    }
+}
+
+impl ReturnValue for u8 {
+    const FLOAT: bool = false;
+
+    fn accepts(_: &VM, ty: BuiltinType) -> bool {
+        ty == BuiltinType::UInt8
+    }
+
+    fn from_raw(value: RawValue) -> u8 {
+        value.int() as u8
+    }
+}
+
+impl ReturnValue for char {
+    const FLOAT: bool = false;
+
+    fn accepts(_: &VM, ty: BuiltinType) -> bool {
+        ty == BuiltinType::Char
+    }
+
+    fn from_raw(value: RawValue) -> char {
+        std::char::from_u32(value.int() as u32).expect("invalid char")
+    }
+}
+
+impl ReturnValue for i32 {
+    const FLOAT: bool = false;
+
+    fn accepts(_: &VM, ty: BuiltinType) -> bool {
+        ty == BuiltinType::Int32
+    }
+
+    fn from_raw(value: RawValue) -> i32 {
+        value.int() as i32
+    }
+}
+
+impl ReturnValue for i64 {
+    const FLOAT: bool = false;
+
+    fn accepts(_: &VM, ty: BuiltinType) -> bool {
+        ty == BuiltinType::Int64
+    }
+
+    fn from_raw(value: RawValue) -> i64 {
+        value.int() as i64
+    }
+}
+
+impl ReturnValue for f32 {
+    const FLOAT: bool = true;
+
+    fn accepts(_: &VM, ty: BuiltinType) -> bool {
+        ty == BuiltinType::Float32
+    }
+
+    fn from_raw(value: RawValue) -> f32 {
+        f32::from_bits(value.float().to_bits() as u32)
+    }
+}
+
+impl ReturnValue for f64 {
+    const FLOAT: bool = true;
+
+    fn accepts(_: &VM, ty: BuiltinType) -> bool {
+        ty == BuiltinType::Float64
+    }
+
+    fn from_raw(value: RawValue) -> f64 {
+        value.float()
+    }
+}
+
+impl<T: HostObject> ReturnValue for Handle<T> {
+    const FLOAT: bool = false;
+
+    fn accepts(vm: &VM, ty: BuiltinType) -> bool {
+        T::accepts_return(vm, ty)
+    }
+
+    fn from_raw(value: RawValue) -> Handle<T> {
+        let obj: Ref<T> = (value.int() as usize).into();
+        handle::root(obj)
+    }
+}
@@ -43,6 +43,7 @@ pub enum SemError {
     VarNeedsTypeInfo(String),
     ParamTypesIncompatible(String, Vec<String>, Vec<String>),
     WhileCondType(String),
+    SynchronizedType(String),
     IfCondType(String),
     ReturnType(String, String),
     LvalueExpected,
@@ -94,6 +95,7 @@ pub enum SemError {
     ExpectedType(String),
     ExpectedIdentifier(String),
     ExpectedStringable(String),
+    InvalidFormatSpec(String),
     ExpectedSomeIdentifier,
     MisplacedElse,
     IoError,
@@ -129,8 +131,7 @@ pub enum SemError {
     MissingAbstractOverride(String, String),
     ModifierNotAllowedForStaticMethod(String),
     GlobalInitializerNotSupported,
-    MakeIteratorNotFound(String),
-    MakeIteratorReturnType(String),
+    TypeNotIterable(String),
     UnknownStructField(String, String),
     StructFieldNotInitialized(String, String),
     InvalidLeftSideOfSeparator,
@@ -140,6 +141,11 @@ pub enum SemError {
     NameExpected,
     IndexExpected,
     IllegalTupleIndex(u64, String),
+    ExternWithBody,
+    ExternGeneric,
+    ExternTypeNotSupported(String),
+    ExternLibraryNotFound(String, String),
+    ExternSymbolNotFound(String, String),
 }
 
 impl SemError {
@@ -262,6 +268,10 @@ impl SemError {
             SemError::WhileCondType(ref ty) => {
                 format!("`while` expects condition of type `bool` but got `{}`.", ty)
             }
+            SemError::SynchronizedType(ref ty) => format!(
+                "`synchronized` expects value of type `Mutex` but got `{}`.",
+                ty
+            ),
             SemError::IfCondType(ref ty) => {
                 format!("`if` expects condition of type `bool` but got `{}`.", ty)
             }
@@ -362,6 +372,9 @@ impl SemError {
             SemError::ExpectedStringable(ref ty) => {
                 format!("type {} does not implement Stringable.", ty)
             }
+            SemError::InvalidFormatSpec(ref ty) => {
+                format!("format specifier not supported for type {}.", ty)
+            }
             SemError::MisplacedAnnotation(ref modifier) => {
                 format!("misplaced annotation `{}`.", modifier)
             }
@@ -457,13 +470,9 @@ impl SemError {
             SemError::GlobalInitializerNotSupported => {
                 "global variables do no support initial assignment for now.".into()
             }
-            SemError::MakeIteratorNotFound(ref ty) => {
-                format!("`{}` does not support `makeIterator` method.", ty)
+            SemError::TypeNotIterable(ref ty) => {
+                format!("`{}` does not implement Iterable or Iterator.", ty)
             }
-            SemError::MakeIteratorReturnType(ref ty) => format!(
-                "makeIterator() returns `{}` which does not implement Iterator.",
-                ty
-            ),
             SemError::UnknownStructField(ref struc, ref field) => {
                 format!("struct `{}` does not have field named `{}`.", struc, field)
             }
@@ -486,6 +495,17 @@ impl SemError {
             SemError::IllegalTupleIndex(idx, ref ty) => {
                 format!("illegal index `{}` for type `{}`", idx, ty)
             }
+            SemError::ExternWithBody => "extern function can't have a body.".into(),
+            SemError::ExternGeneric => "extern function can't have type params.".into(),
+            SemError::ExternTypeNotSupported(ref ty) => {
+                format!("type `{}` not supported in extern function.", ty)
+            }
+            SemError::ExternLibraryNotFound(ref library, ref reason) => {
+                format!("can't load library `{}`: {}.", library, reason)
+            }
+            SemError::ExternSymbolNotFound(ref library, ref symbol) => {
+                format!("symbol `{}` not found in library `{}`.", symbol, library)
+            }
         }
     }
 }
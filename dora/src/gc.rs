@@ -2,8 +2,10 @@ use std::cmp::{Ord, Ordering, PartialOrd};
 use std::fmt;
 
 use crate::driver::cmd::{Args, CollectorName};
+use crate::gc::code::CodeSpace;
 use crate::gc::compact::MarkCompactCollector;
 use crate::gc::copy::CopyCollector;
+use crate::gc::region::RegionCollector;
 use crate::gc::space::{Space, SpaceConfig};
 use crate::gc::sweep::SweepCollector;
 use crate::gc::swiper::sweep::SweepSwiper;
@@ -17,11 +19,14 @@ use crate::vm::VM;
 use crate::vtable::VTable;
 
 pub mod bump;
+pub mod code;
 pub mod compact;
 pub mod copy;
 pub mod freelist;
+pub mod log;
 pub mod marking;
 pub mod pmarking;
+pub mod region;
 pub mod root;
 pub mod space;
 pub mod sweep;
@@ -43,20 +48,14 @@ const GEN_SIZE: usize = 1 << GEN_ALIGNMENT_BITS;
 pub struct Gc {
     collector: Box<dyn Collector + Sync>,
     supports_tlab: bool,
+    compressed_refs: bool,
 
-    code_space: Space,
+    code_space: CodeSpace,
     perm_space: Space,
 }
 
 impl Gc {
     pub fn new(args: &Args) -> Gc {
-        let code_config = SpaceConfig {
-            executable: true,
-            chunk: CHUNK_SIZE,
-            limit: args.code_size(),
-            align: 64,
-        };
-
         let perm_config = SpaceConfig {
             executable: false,
             chunk: CHUNK_SIZE,
@@ -66,26 +65,43 @@ impl Gc {
 
         let collector_name = args.flag_gc.unwrap_or(CollectorName::Swiper);
 
+        // compressed references need heap and perm space below COMPRESSED_REF_LIMIT,
+        // fall back to uncompressed references when the OS places memory elsewhere
+        os::begin_low_memory(args.flag_compressed_refs);
+
         let collector: Box<dyn Collector + Sync> = match collector_name {
             CollectorName::Zero => box ZeroCollector::new(args),
             CollectorName::Compact => box MarkCompactCollector::new(args),
             CollectorName::Copy => box CopyCollector::new(args),
             CollectorName::Sweep => box SweepCollector::new(args),
+            CollectorName::Region => box RegionCollector::new(args),
             CollectorName::Swiper => box Swiper::new(args),
             CollectorName::SweepSwiper => box SweepSwiper::new(args),
         };
 
+        let perm_space = Space::new(perm_config, "perm");
+        let compressed_refs = os::end_low_memory();
+
+        if args.flag_compressed_refs && !compressed_refs {
+            eprintln!("GC: heap could not be reserved below 32G, compressed references disabled.");
+        }
+
         let supports_tlab = !args.flag_disable_tlab && collector.supports_tlab();
 
         Gc {
             collector,
             supports_tlab,
+            compressed_refs,
 
-            code_space: Space::new(code_config, "code"),
-            perm_space: Space::new(perm_config, "perm"),
+            code_space: CodeSpace::new(args.code_size(), CHUNK_SIZE, 64),
+            perm_space,
         }
     }
 
+    pub fn compressed_refs(&self) -> bool {
+        self.compressed_refs
+    }
+
     pub fn needs_write_barrier(&self) -> bool {
         self.collector.needs_write_barrier()
     }
@@ -102,7 +118,15 @@ impl Gc {
         self.perm_space.alloc(size)
     }
 
+    pub fn code_space(&self) -> &CodeSpace {
+        &self.code_space
+    }
+
     pub fn alloc(&self, vm: &VM, size: usize, array_ref: bool) -> Address {
+        if self.code_space.collection_requested() {
+            code::collect(vm);
+        }
+
         if vm.args.flag_gc_stress_minor {
             self.minor_collect(vm, GcReason::StressMinor);
         }
@@ -128,7 +152,10 @@ impl Gc {
         tlab::make_iterable_current(vm);
 
         // allocate new tlab
-        if let Some(tlab) = self.collector.alloc_tlab_area(vm, tlab::calculate_size()) {
+        if let Some(tlab) = self
+            .collector
+            .alloc_tlab_area(vm, size, tlab::calculate_size())
+        {
             let object_start = tlab.start;
             let tlab = Region::new(tlab.start.offset(size), tlab.end);
 
@@ -145,6 +172,10 @@ impl Gc {
 
     pub fn collect(&self, vm: &VM, reason: GcReason) {
         self.collector.collect(vm, reason);
+
+        if reason == GcReason::ForceCollect {
+            code::collect(vm);
+        }
     }
 
     pub fn minor_collect(&self, vm: &VM, reason: GcReason) {
@@ -165,8 +196,11 @@ impl Gc {
 }
 
 trait Collector {
+    // allocate TLAB of given size, collectors that reuse free holes
+    // may return a smaller area that still fits at least min_size
+    fn alloc_tlab_area(&self, vm: &VM, min_size: usize, size: usize) -> Option<Region>;
+
     // allocate object of given size
-    fn alloc_tlab_area(&self, vm: &VM, size: usize) -> Option<Region>;
     fn alloc(&self, vm: &VM, size: usize, array_ref: bool) -> Address;
 
     // collect garbage
@@ -0,0 +1,419 @@
+use parking_lot::Mutex;
+use std::cmp;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::compiler::map::CodeDescriptor;
+use crate::compiler::{JitFct, JitFctId};
+use crate::gc::{formatted_size, Address, Region};
+use crate::mem;
+use crate::os;
+use crate::safepoint;
+use crate::threads::DoraThread;
+use crate::timer::Timer;
+use crate::vm::VM;
+
+/// Space for machine code. Memory is bump allocated in chunks,
+/// code freed by the code collector is put into a free list and
+/// reused by later allocations.
+pub struct CodeSpace {
+    total: Region,
+    chunk: usize,
+    align: usize,
+    alloc: Mutex<CodeAllocator>,
+
+    // set when usage crosses threshold, collection is
+    // performed at next safepoint
+    collection_requested: AtomicBool,
+}
+
+struct CodeAllocator {
+    top: Address,
+    end: Address,
+    used: usize,
+    threshold: usize,
+
+    // maps start of free block to its size
+    free: BTreeMap<Address, usize>,
+}
+
+impl CodeSpace {
+    pub fn new(limit: usize, chunk: usize, align: usize) -> CodeSpace {
+        let limit = mem::page_align(limit);
+        let chunk = mem::page_align(chunk);
+
+        let reservation = os::reserve_align(limit, os::page_size());
+        let start = reservation.start;
+
+        os::commit_at(start, chunk, true);
+
+        CodeSpace {
+            total: Region::new(start, start.offset(limit)),
+            chunk,
+            align,
+            alloc: Mutex::new(CodeAllocator {
+                top: start,
+                end: start.offset(chunk),
+                used: 0,
+                threshold: limit / 2,
+                free: BTreeMap::new(),
+            }),
+
+            collection_requested: AtomicBool::new(false),
+        }
+    }
+
+    pub fn alloc(&self, size: usize) -> Address {
+        let size = mem::align_usize(size, self.align);
+        let mut alloc = self.alloc.lock();
+
+        let ptr = self
+            .alloc_free_list(&mut alloc, size)
+            .unwrap_or_else(|| self.alloc_bump(&mut alloc, size));
+
+        if ptr.is_non_null() {
+            alloc.used += size;
+
+            if alloc.used > alloc.threshold {
+                self.collection_requested.store(true, Ordering::Relaxed);
+            }
+        }
+
+        ptr
+    }
+
+    fn alloc_free_list(&self, alloc: &mut CodeAllocator, size: usize) -> Option<Address> {
+        let (start, free_size) = alloc
+            .free
+            .iter()
+            .find(|&(_, &free_size)| free_size >= size)
+            .map(|(&start, &free_size)| (start, free_size))?;
+
+        alloc.free.remove(&start);
+
+        if free_size > size {
+            alloc.free.insert(start.offset(size), free_size - size);
+        }
+
+        Some(start)
+    }
+
+    fn alloc_bump(&self, alloc: &mut CodeAllocator, size: usize) -> Address {
+        let ptr = alloc.top;
+        let next_top = ptr.offset(size);
+
+        if next_top > alloc.end {
+            let size = mem::align_usize(next_top.offset_from(alloc.end), self.chunk);
+            let new_end = cmp::min(alloc.end.offset(size), self.total.end);
+
+            if next_top > new_end {
+                return Address::null();
+            }
+
+            os::commit_at(alloc.end, new_end.offset_from(alloc.end), true);
+            alloc.end = new_end;
+        }
+
+        alloc.top = next_top;
+        ptr
+    }
+
+    pub fn free(&self, start: Address, size: usize) {
+        let size = mem::align_usize(size, self.align);
+        let mut alloc = self.alloc.lock();
+        debug_assert!(alloc.used >= size);
+        alloc.used -= size;
+
+        let mut start = start;
+        let mut end = start.offset(size);
+
+        // merge with previous and next free block
+        let prev = alloc
+            .free
+            .range(..start)
+            .next_back()
+            .map(|(&prev_start, &prev_size)| (prev_start, prev_size));
+
+        if let Some((prev_start, prev_size)) = prev {
+            if prev_start.offset(prev_size) == start {
+                alloc.free.remove(&prev_start);
+                start = prev_start;
+            }
+        }
+
+        if let Some(next_size) = alloc.free.remove(&end) {
+            end = end.offset(next_size);
+        }
+
+        if end == alloc.top {
+            alloc.top = start;
+        } else {
+            alloc.free.insert(start, end.offset_from(start));
+        }
+    }
+
+    pub fn used(&self) -> usize {
+        self.alloc.lock().used
+    }
+
+    pub fn contains(&self, addr: Address) -> bool {
+        self.total.contains(addr)
+    }
+
+    pub fn collection_requested(&self) -> bool {
+        self.collection_requested.load(Ordering::Relaxed)
+    }
+
+    fn collection_finished(&self) {
+        let mut alloc = self.alloc.lock();
+        let limit = self.total.size();
+
+        // collect again when half of the remaining space is used
+        alloc.threshold = cmp::max(limit / 2, alloc.used + (limit - alloc.used) / 2);
+        self.collection_requested.store(false, Ordering::Relaxed);
+    }
+}
+
+impl Drop for CodeSpace {
+    fn drop(&mut self) {
+        os::free(self.total.start, self.total.size());
+    }
+}
+
+/// Frees machine code of functions that can't be called anymore: code is
+/// reachable when it is executed on some stack, installed in a vtable or
+/// referenced from the data segment of other reachable code (patched call
+/// sites). Freed functions are compiled again on their next call.
+pub fn collect(vm: &VM) {
+    safepoint::stop_the_world(vm, |threads| {
+        let timer = Timer::new(vm.args.flag_gc_verbose);
+        let used_before = vm.gc.code_space().used();
+
+        // data segments of code under construction might reference
+        // functions, only free code when no compilation is running.
+        let freed = if vm.active_compilations.load(Ordering::SeqCst) == 0 {
+            CodeCollector::new(vm).collect(threads)
+        } else {
+            0
+        };
+
+        vm.gc.code_space().collection_finished();
+
+        timer.stop_with(|time_pause| {
+            println!(
+                "Code GC: {:.1} ms, {}->{} code, {} functions freed",
+                time_pause,
+                formatted_size(used_before),
+                formatted_size(vm.gc.code_space().used()),
+                freed,
+            );
+        });
+    });
+}
+
+struct CodeCollector<'a, 'ast: 'a> {
+    vm: &'a VM<'ast>,
+    marked: Vec<bool>,
+    worklist: Vec<JitFctId>,
+}
+
+impl<'a, 'ast> CodeCollector<'a, 'ast> {
+    fn new(vm: &'a VM<'ast>) -> CodeCollector<'a, 'ast> {
+        CodeCollector {
+            vm,
+            marked: vec![false; vm.jit_fcts.len()],
+            worklist: Vec::new(),
+        }
+    }
+
+    fn collect(&mut self, threads: &[Arc<DoraThread>]) -> usize {
+        if !self.mark_vtables() {
+            return 0;
+        }
+
+        self.mark_stacks(threads);
+        self.trace();
+        self.sweep()
+    }
+
+    fn mark_vtables(&mut self) -> bool {
+        for cls_def in self.vm.class_defs.iter() {
+            // class might be under construction
+            let cls_def = match cls_def.try_read() {
+                Some(cls_def) => cls_def,
+                None => return false,
+            };
+
+            if let Some(ref vtable) = cls_def.vtable {
+                for &entry in vtable.table() {
+                    self.mark_address(entry.into());
+                }
+            }
+        }
+
+        true
+    }
+
+    fn mark_stacks(&mut self, threads: &[Arc<DoraThread>]) {
+        for thread in threads {
+            let mut dtn = thread.dtn();
+
+            while !dtn.is_null() {
+                let info = unsafe { &*dtn };
+                let mut pc = info.pc;
+                let mut fp = info.fp;
+
+                while fp != 0 {
+                    if !self.mark_frame(pc) {
+                        break;
+                    }
+
+                    pc = unsafe { *((fp + 8) as *const usize) };
+                    fp = unsafe { *(fp as *const usize) };
+                }
+
+                dtn = info.last;
+            }
+        }
+    }
+
+    fn mark_frame(&mut self, pc: usize) -> bool {
+        let data = self.vm.code_map.lock().get(pc.into());
+
+        match data {
+            Some(CodeDescriptor::DoraFct(jit_fct_id)) => {
+                self.mark(jit_fct_id);
+                true
+            }
+
+            Some(CodeDescriptor::DoraStub) | None => false,
+            Some(_) => true,
+        }
+    }
+
+    fn mark_address(&mut self, addr: Address) {
+        let data = self.vm.code_map.lock().get(addr);
+
+        if let Some(CodeDescriptor::DoraFct(jit_fct_id)) = data {
+            self.mark(jit_fct_id);
+        }
+    }
+
+    fn mark(&mut self, jit_fct_id: JitFctId) {
+        if !self.marked[jit_fct_id.idx()] {
+            self.marked[jit_fct_id.idx()] = true;
+            self.worklist.push(jit_fct_id);
+        }
+    }
+
+    fn trace(&mut self) {
+        while let Some(jit_fct_id) = self.worklist.pop() {
+            let jit_fct = self.vm.jit_fcts.idx(jit_fct_id);
+            let mut addr = jit_fct.ptr_start();
+
+            // data segment is located before the first instruction
+            while addr < jit_fct.instruction_start() {
+                let entry = unsafe { *addr.to_ptr::<usize>() };
+                self.mark_address(entry.into());
+                addr = addr.add_ptr(1);
+            }
+        }
+    }
+
+    fn sweep(&mut self) -> usize {
+        let mut code_map = self.vm.code_map.lock();
+        let mut freed = 0;
+
+        for (idx, &marked) in self.marked.iter().enumerate() {
+            let jit_fct_id = JitFctId::from(idx);
+
+            if marked {
+                continue;
+            }
+
+            let jit_fct = self.vm.jit_fcts.idx(jit_fct_id);
+
+            if let JitFct::Uncompiled = *jit_fct {
+                continue;
+            }
+
+            let start = jit_fct.ptr_start();
+
+            match code_map.get(start) {
+                Some(CodeDescriptor::DoraFct(id)) if id == jit_fct_id => {}
+                _ => continue,
+            }
+
+            let fct = self.vm.fcts.idx(jit_fct.fct_id());
+            let fct = fct.read();
+
+            // function might be compiled right now
+            let src = match fct.src().try_write() {
+                Some(src) => src,
+                None => continue,
+            };
+
+            // remove compiled function, next call compiles it again
+            src.specializations
+                .write()
+                .retain(|_, &mut value| value != jit_fct_id);
+
+            code_map.remove(start);
+            self.vm
+                .gc
+                .code_space()
+                .free(start, jit_fct.ptr_end().offset_from(start));
+
+            // drop metadata of freed code, slot is reused for the next compiled function
+            self.vm.jit_fcts.lock()[idx] = Arc::new(JitFct::Uncompiled);
+            self.vm.free_jit_fcts.lock().push(jit_fct_id);
+
+            freed += 1;
+        }
+
+        freed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CodeSpace;
+    use crate::gc::K;
+
+    #[test]
+    fn test_alloc_free() {
+        let space = CodeSpace::new(64 * K, 8 * K, 64);
+
+        let a = space.alloc(100);
+        let b = space.alloc(64);
+        let c = space.alloc(64);
+        assert_eq!(a.offset(128), b);
+        assert_eq!(b.offset(64), c);
+        assert_eq!(256, space.used());
+
+        // freed memory is reused
+        space.free(a, 100);
+        assert_eq!(a, space.alloc(64));
+        assert_eq!(a.offset(64), space.alloc(64));
+
+        // free blocks are merged
+        space.free(a, 64);
+        space.free(a.offset(64), 64);
+        space.free(b, 64);
+        assert_eq!(a, space.alloc(192));
+
+        // memory at the end is given back to bump allocation
+        space.free(c, 64);
+        assert_eq!(c, space.alloc(16 * K));
+        assert_eq!(192 + 16 * K, space.used());
+    }
+
+    #[test]
+    fn test_alloc_limit() {
+        let space = CodeSpace::new(64 * K, 8 * K, 64);
+
+        assert!(space.alloc(64 * K).is_non_null());
+        assert!(space.alloc(64).is_null());
+    }
+}
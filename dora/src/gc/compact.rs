@@ -2,6 +2,7 @@ use parking_lot::Mutex;
 
 use crate::driver::cmd::Args;
 use crate::gc::bump::BumpAllocator;
+use crate::gc::log::GcLog;
 use crate::gc::marking;
 use crate::gc::root::{get_rootset, Slot};
 use crate::gc::space::Space;
@@ -17,6 +18,7 @@ pub struct MarkCompactCollector {
     heap: Region,
     alloc: BumpAllocator,
     stats: Mutex<CollectionStats>,
+    log: Option<GcLog>,
 }
 
 impl MarkCompactCollector {
@@ -39,6 +41,7 @@ impl MarkCompactCollector {
             heap,
             alloc: BumpAllocator::new(heap_start, heap_end),
             stats: Mutex::new(CollectionStats::new()),
+            log: args.flag_gc_log.as_ref().map(|path| GcLog::new(path)),
         }
     }
 }
@@ -48,7 +51,7 @@ impl Collector for MarkCompactCollector {
         true
     }
 
-    fn alloc_tlab_area(&self, vm: &VM, size: usize) -> Option<Region> {
+    fn alloc_tlab_area(&self, vm: &VM, _min_size: usize, size: usize) -> Option<Region> {
         let ptr = self.alloc.bump_alloc(size);
 
         if ptr.is_non_null() {
@@ -78,7 +81,8 @@ impl Collector for MarkCompactCollector {
     }
 
     fn collect(&self, vm: &VM, reason: GcReason) {
-        let mut timer = Timer::new(vm.args.flag_gc_stats);
+        let mut timer = Timer::new(vm.args.gc_phases());
+        let memory_before = self.memory_size();
 
         safepoint::stop_the_world(vm, |threads| {
             tlab::make_iterable_all(vm, threads);
@@ -86,10 +90,21 @@ impl Collector for MarkCompactCollector {
             self.mark_compact(vm, &rootset, reason);
         });
 
-        if vm.args.flag_gc_stats {
+        if vm.args.gc_phases() {
             let duration = timer.stop();
             let mut stats = self.stats.lock();
             stats.add(duration);
+
+            if let Some(ref log) = self.log {
+                let memory_after = self.memory_size();
+                log.write_full(
+                    stats.collections(),
+                    reason,
+                    duration,
+                    memory_before,
+                    memory_after,
+                );
+            }
         }
     }
 
@@ -128,6 +143,10 @@ impl Drop for MarkCompactCollector {
 }
 
 impl MarkCompactCollector {
+    fn memory_size(&self) -> usize {
+        self.alloc.top().offset_from(self.heap.start)
+    }
+
     fn mark_compact(&self, vm: &VM, rootset: &[Slot], reason: GcReason) {
         let mut mark_compact = MarkCompact {
             vm,
@@ -162,6 +181,7 @@ impl<'a, 'ast> MarkCompact<'a, 'ast> {
     fn collect(&mut self) {
         self.mark_live();
         self.compute_forward();
+        self.process_weak();
         self.update_references();
         self.relocate();
     }
@@ -179,6 +199,24 @@ impl<'a, 'ast> MarkCompact<'a, 'ast> {
         });
     }
 
+    fn process_weak(&mut self) {
+        let heap = self.heap;
+
+        self.vm.monitors.process_weak(|object| {
+            if !heap.contains(object) {
+                return Some(object);
+            }
+
+            let header = object.to_obj().header();
+
+            if header.is_marked_non_atomic() {
+                Some(header.fwdptr_non_atomic())
+            } else {
+                None
+            }
+        });
+    }
+
     fn allocate(&mut self, object_size: usize) -> Address {
         let addr = self.top;
         let next = self.top.offset(object_size);
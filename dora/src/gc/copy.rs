@@ -2,6 +2,7 @@ use parking_lot::Mutex;
 
 use crate::driver::cmd::Args;
 use crate::gc::bump::BumpAllocator;
+use crate::gc::log::GcLog;
 use crate::gc::root::{get_rootset, Slot};
 use crate::gc::tlab;
 use crate::gc::{formatted_size, Address, CollectionStats, Collector, GcReason, Region};
@@ -18,6 +19,7 @@ pub struct CopyCollector {
 
     alloc: BumpAllocator,
     stats: Mutex<CollectionStats>,
+    log: Option<GcLog>,
 }
 
 impl CopyCollector {
@@ -44,6 +46,7 @@ impl CopyCollector {
             separator,
             alloc: BumpAllocator::new(heap_start, separator),
             stats: Mutex::new(CollectionStats::new()),
+            log: args.flag_gc_log.as_ref().map(|path| GcLog::new(path)),
         }
     }
 }
@@ -53,7 +56,7 @@ impl Collector for CopyCollector {
         true
     }
 
-    fn alloc_tlab_area(&self, vm: &VM, size: usize) -> Option<Region> {
+    fn alloc_tlab_area(&self, vm: &VM, _min_size: usize, size: usize) -> Option<Region> {
         let ptr = self.alloc.bump_alloc(size);
 
         if ptr.is_non_null() {
@@ -83,7 +86,8 @@ impl Collector for CopyCollector {
     }
 
     fn collect(&self, vm: &VM, reason: GcReason) {
-        let mut timer = Timer::new(vm.args.flag_gc_stats);
+        let mut timer = Timer::new(vm.args.gc_phases());
+        let memory_before = self.memory_size();
 
         safepoint::stop_the_world(vm, |threads| {
             tlab::make_iterable_all(vm, &*threads);
@@ -91,10 +95,21 @@ impl Collector for CopyCollector {
             self.copy_collect(vm, &rootset, reason);
         });
 
-        if vm.args.flag_gc_stats {
+        if vm.args.gc_phases() {
             let duration = timer.stop();
             let mut stats = self.stats.lock();
             stats.add(duration);
+
+            if let Some(ref log) = self.log {
+                let memory_after = self.memory_size();
+                log.write_full(
+                    stats.collections(),
+                    reason,
+                    duration,
+                    memory_before,
+                    memory_after,
+                );
+            }
         }
     }
 
@@ -133,6 +148,10 @@ impl Drop for CopyCollector {
 }
 
 impl CopyCollector {
+    fn memory_size(&self) -> usize {
+        self.alloc.top().offset_from(self.from_space().start)
+    }
+
     fn copy_collect(&self, vm: &VM, rootset: &[Slot], reason: GcReason) {
         let timer = Timer::new(vm.args.flag_gc_verbose);
 
@@ -174,6 +193,14 @@ impl CopyCollector {
             scan = scan.offset(object.size());
         }
 
+        vm.monitors.process_weak(|object| {
+            if from_space.contains(object) {
+                object.to_obj().header().vtblptr_forwarded()
+            } else {
+                Some(object)
+            }
+        });
+
         // disable access in current from-space
         // makes sure that no pointer into from-space is left (in debug-builds)
         if cfg!(debug_assertions) {
@@ -0,0 +1,148 @@
+use parking_lot::Mutex;
+use std::fmt::Write as FmtWrite;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::gc::GcReason;
+use crate::timer;
+
+// machine-readable GC log: every collection is written as one
+// JSON object on a single line into the file given by --gc-log.
+pub struct GcLog {
+    file: Mutex<BufWriter<File>>,
+    start: u64,
+}
+
+impl GcLog {
+    pub fn new(path: &str) -> GcLog {
+        let file = match File::create(path) {
+            Ok(file) => file,
+            Err(_) => panic!("could not create GC log file `{}`.", path),
+        };
+
+        GcLog {
+            file: Mutex::new(BufWriter::new(file)),
+            start: timer::timestamp(),
+        }
+    }
+
+    // returns time since creation of the log in ms
+    pub fn elapsed(&self) -> f32 {
+        timer::in_ms(timer::timestamp() - self.start)
+    }
+
+    pub fn write(&self, record: GcLogRecord) {
+        let mut file = self.file.lock();
+
+        // flush after each record: VM might exit without running destructors
+        writeln!(file, "{}", record.finish())
+            .and_then(|_| file.flush())
+            .expect("could not write GC log.");
+    }
+
+    // collectors without generations only know the pause time and the
+    // memory in use before and after the collection.
+    pub fn write_full(&self, id: usize, reason: GcReason, pause: f32, before: usize, after: usize) {
+        let mut record = GcLogRecord::new();
+        record.size("id", id);
+        record.ms("time", self.elapsed());
+        record.str("kind", "full");
+        record.str("reason", reason.message());
+        record.ms("pause", pause);
+        record.object("before", |record| record.size("memory", before));
+        record.object("after", |record| record.size("memory", after));
+        self.write(record);
+    }
+}
+
+pub struct GcLogRecord {
+    buffer: String,
+    needs_comma: bool,
+}
+
+impl GcLogRecord {
+    pub fn new() -> GcLogRecord {
+        GcLogRecord {
+            buffer: String::from("{"),
+            needs_comma: false,
+        }
+    }
+
+    pub fn str(&mut self, key: &str, value: &str) {
+        self.key(key);
+        self.buffer.push('"');
+
+        for ch in value.chars() {
+            match ch {
+                '"' => self.buffer.push_str("\\\""),
+                '\\' => self.buffer.push_str("\\\\"),
+                '\n' => self.buffer.push_str("\\n"),
+                ch if (ch as u32) < 0x20 => {
+                    write!(self.buffer, "\\u{:04x}", ch as u32).unwrap();
+                }
+                ch => self.buffer.push(ch),
+            }
+        }
+
+        self.buffer.push('"');
+    }
+
+    pub fn size(&mut self, key: &str, value: usize) {
+        self.key(key);
+        write!(self.buffer, "{}", value).unwrap();
+    }
+
+    pub fn ms(&mut self, key: &str, value: f32) {
+        self.key(key);
+        write!(self.buffer, "{:.3}", value).unwrap();
+    }
+
+    pub fn object<F>(&mut self, key: &str, fct: F)
+    where
+        F: FnOnce(&mut GcLogRecord),
+    {
+        self.key(key);
+        self.buffer.push('{');
+        self.needs_comma = false;
+        fct(self);
+        self.buffer.push('}');
+        self.needs_comma = true;
+    }
+
+    fn key(&mut self, key: &str) {
+        if self.needs_comma {
+            self.buffer.push(',');
+        }
+
+        write!(self.buffer, "\"{}\":", key).unwrap();
+        self.needs_comma = true;
+    }
+
+    fn finish(mut self) -> String {
+        self.buffer.push('}');
+        self.buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GcLogRecord;
+
+    #[test]
+    fn test_record() {
+        let mut record = GcLogRecord::new();
+        record.size("id", 1);
+        record.str("kind", "minor");
+        record.object("phases", |record| {
+            record.ms("roots", 0.5);
+            record.ms("tracing", 1.25);
+        });
+        record.object("empty", |_| {});
+        record.str("reason", "a\"b\\c\n");
+
+        assert_eq!(
+            "{\"id\":1,\"kind\":\"minor\",\"phases\":{\"roots\":0.500,\"tracing\":1.250},\"empty\":{},\"reason\":\"a\\\"b\\\\c\\n\"}",
+            record.finish()
+        );
+    }
+}
@@ -0,0 +1,813 @@
+use parking_lot::Mutex;
+use scoped_threadpool::Pool;
+
+use crate::driver::cmd::Args;
+use crate::gc::log::GcLog;
+use crate::gc::pmarking;
+use crate::gc::root::{get_rootset, Slot};
+use crate::gc::space::Space;
+use crate::gc::tlab::{self, TLAB_OBJECT_SIZE};
+use crate::gc::{
+    fill_region, formatted_size, Address, CollectionStats, Collector, GcReason, Region, K,
+};
+use crate::mem;
+use crate::object::{Header, Obj};
+use crate::os;
+use crate::safepoint;
+use crate::timer::Timer;
+use crate::vm::VM;
+
+// heap is divided into blocks, which are further divided into lines.
+// objects never cross block boundaries, after a collection memory
+// is reused in runs of free lines (holes).
+pub const BLOCK_SIZE: usize = 32 * K;
+pub const LINE_SIZE: usize = 256;
+
+// objects of at least this size get their own run of blocks
+const LARGE_OBJECT_SIZE: usize = BLOCK_SIZE / 2;
+
+// blocks with at most this amount of live bytes are evacuated
+const EVACUATION_LIVE_SIZE: usize = BLOCK_SIZE / 4;
+
+pub struct RegionCollector {
+    heap: Region,
+    alloc: Mutex<RegionAllocator>,
+    threadpool: Mutex<Pool>,
+    stats: Mutex<CollectionStats>,
+    log: Option<GcLog>,
+}
+
+impl RegionCollector {
+    pub fn new(args: &Args) -> RegionCollector {
+        let heap_size = mem::align_usize(args.max_heap_size(), BLOCK_SIZE);
+        let heap_start = os::commit(heap_size, false);
+
+        if heap_start.is_null() {
+            panic!("could not allocate heap of size {} bytes", heap_size);
+        }
+
+        let heap_end = heap_start.offset(heap_size);
+        let heap = Region::new(heap_start, heap_end);
+
+        if args.flag_gc_verbose {
+            println!(
+                "GC: {} {}; {} blocks",
+                heap,
+                formatted_size(heap_size),
+                heap_size / BLOCK_SIZE
+            );
+        }
+
+        RegionCollector {
+            heap,
+            alloc: Mutex::new(RegionAllocator::new(heap)),
+            threadpool: Mutex::new(Pool::new(args.gc_workers() as u32)),
+            stats: Mutex::new(CollectionStats::new()),
+            log: args.flag_gc_log.as_ref().map(|path| GcLog::new(path)),
+        }
+    }
+}
+
+impl Collector for RegionCollector {
+    fn supports_tlab(&self) -> bool {
+        true
+    }
+
+    fn alloc_tlab_area(&self, vm: &VM, min_size: usize, _size: usize) -> Option<Region> {
+        if let Some(area) = self.alloc.lock().alloc_area(min_size) {
+            return Some(area);
+        }
+
+        self.collect(vm, GcReason::AllocationFailure);
+        self.alloc.lock().alloc_area(min_size)
+    }
+
+    fn alloc(&self, vm: &VM, size: usize, _array_ref: bool) -> Address {
+        let ptr = self.inner_alloc(vm, size);
+
+        if ptr.is_non_null() {
+            return ptr;
+        }
+
+        self.collect(vm, GcReason::AllocationFailure);
+        self.inner_alloc(vm, size)
+    }
+
+    fn collect(&self, vm: &VM, reason: GcReason) {
+        let mut timer = Timer::new(vm.args.gc_phases());
+        let memory_before = self.memory_size();
+
+        safepoint::stop_the_world(vm, |threads| {
+            tlab::make_iterable_all(vm, threads);
+            let rootset = get_rootset(vm, threads);
+            self.mark_region(vm, &rootset, reason);
+        });
+
+        if vm.args.gc_phases() {
+            let duration = timer.stop();
+            let mut stats = self.stats.lock();
+            stats.add(duration);
+
+            if let Some(ref log) = self.log {
+                let memory_after = self.memory_size();
+                log.write_full(
+                    stats.collections(),
+                    reason,
+                    duration,
+                    memory_before,
+                    memory_after,
+                );
+            }
+        }
+    }
+
+    fn minor_collect(&self, vm: &VM, reason: GcReason) {
+        self.collect(vm, reason);
+    }
+
+    fn dump_summary(&self, runtime: f32) {
+        let stats = self.stats.lock();
+        let (mutator, gc) = stats.percentage(runtime);
+
+        println!("GC stats: total={:.1}", runtime);
+        println!("GC stats: mutator={:.1}", stats.mutator(runtime));
+        println!("GC stats: collection={:.1}", stats.pause());
+
+        println!("");
+        println!("GC stats: collection-count={}", stats.collections());
+        println!("GC stats: collection-pauses={}", stats.pauses());
+
+        println!(
+            "GC summary: {:.1}ms collection ({}), {:.1}ms mutator, {:.1}ms total ({}% mutator, {}% GC)",
+            stats.pause(),
+            stats.collections(),
+            stats.mutator(runtime),
+            runtime,
+            mutator,
+            gc,
+        );
+    }
+}
+
+impl Drop for RegionCollector {
+    fn drop(&mut self) {
+        os::free(self.heap.start, self.heap.size());
+    }
+}
+
+impl RegionCollector {
+    fn memory_size(&self) -> usize {
+        self.alloc.lock().used_blocks() * BLOCK_SIZE
+    }
+
+    fn inner_alloc(&self, vm: &VM, size: usize) -> Address {
+        let mut alloc = self.alloc.lock();
+
+        if size >= LARGE_OBJECT_SIZE {
+            alloc.alloc_large(size)
+        } else {
+            alloc.alloc_medium(vm, size)
+        }
+    }
+
+    fn mark_region(&self, vm: &VM, rootset: &[Slot], reason: GcReason) {
+        let timer = Timer::new(vm.args.flag_gc_verbose);
+
+        let mut alloc = self.alloc.lock();
+        alloc.make_iterable(vm);
+
+        let mut threadpool = self.threadpool.lock();
+        let used_blocks = alloc.used_blocks();
+
+        let mut collector = MarkRegion {
+            vm,
+            heap: self.heap,
+            perm_space: &vm.gc.perm_space,
+            alloc: &mut *alloc,
+            threadpool: &mut *threadpool,
+
+            rootset,
+            reason,
+
+            live: Vec::new(),
+            evacuation_top: Address::null(),
+            evacuation_limit: Address::null(),
+            evacuated_blocks: 0,
+            evacuated_size: 0,
+            live_size: 0,
+        };
+
+        collector.collect();
+
+        let evacuated_blocks = collector.evacuated_blocks;
+        let evacuated_size = collector.evacuated_size;
+        let live_size = collector.live_size;
+
+        timer.stop_with(|time_pause| {
+            println!(
+                "Region GC: {:.1} ms, {}->{} blocks, {} live, {} evacuated from {} blocks ({})",
+                time_pause,
+                used_blocks,
+                alloc.used_blocks(),
+                formatted_size(live_size),
+                formatted_size(evacuated_size),
+                evacuated_blocks,
+                reason,
+            );
+        });
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum BlockState {
+    Free,
+    Used,
+
+    // live objects are copied out of this block during collection
+    Evacuate,
+
+    // first block of large object, stores number of blocks
+    Large(usize),
+    LargeTail,
+}
+
+struct RegionAllocator {
+    heap: Region,
+    blocks: Vec<BlockState>,
+    free_blocks: Vec<usize>,
+    holes: Vec<Region>,
+
+    // bump area for objects not allocated in TLABs
+    top: Address,
+    limit: Address,
+}
+
+impl RegionAllocator {
+    fn new(heap: Region) -> RegionAllocator {
+        let number_blocks = heap.size() / BLOCK_SIZE;
+
+        RegionAllocator {
+            heap,
+            blocks: vec![BlockState::Free; number_blocks],
+            free_blocks: (0..number_blocks).rev().collect(),
+            holes: Vec::new(),
+
+            top: Address::null(),
+            limit: Address::null(),
+        }
+    }
+
+    fn block_region(&self, idx: usize) -> Region {
+        let start = self.heap.start.offset(idx * BLOCK_SIZE);
+        Region::new(start, start.offset(BLOCK_SIZE))
+    }
+
+    fn block_index(&self, addr: Address) -> usize {
+        addr.offset_from(self.heap.start) / BLOCK_SIZE
+    }
+
+    fn used_blocks(&self) -> usize {
+        self.blocks.len() - self.free_blocks.len()
+    }
+
+    fn alloc_area(&mut self, min_size: usize) -> Option<Region> {
+        if min_size < TLAB_OBJECT_SIZE {
+            // small objects reuse holes first, holes too small for
+            // the object are skipped until the next collection.
+            while let Some(hole) = self.holes.pop() {
+                if hole.size() >= min_size {
+                    return Some(hole);
+                }
+            }
+        }
+
+        if let Some(block) = self.alloc_block() {
+            return Some(block);
+        }
+
+        let idx = self.holes.iter().position(|hole| hole.size() >= min_size)?;
+        Some(self.holes.swap_remove(idx))
+    }
+
+    fn alloc_block(&mut self) -> Option<Region> {
+        let idx = self.free_blocks.pop()?;
+        debug_assert!(self.blocks[idx] == BlockState::Free);
+        self.blocks[idx] = BlockState::Used;
+
+        Some(self.block_region(idx))
+    }
+
+    fn alloc_medium(&mut self, vm: &VM, size: usize) -> Address {
+        let object = self.top;
+        let next_top = object.offset(size);
+
+        if object.is_non_null() && next_top <= self.limit {
+            self.top = next_top;
+            return object;
+        }
+
+        self.make_iterable(vm);
+
+        if let Some(area) = self.alloc_area(size) {
+            self.top = area.start.offset(size);
+            self.limit = area.end;
+            return area.start;
+        }
+
+        Address::null()
+    }
+
+    fn alloc_large(&mut self, size: usize) -> Address {
+        let number_blocks = mem::align_usize(size, BLOCK_SIZE) / BLOCK_SIZE;
+        let mut start = 0;
+
+        for idx in 0..self.blocks.len() {
+            if self.blocks[idx] != BlockState::Free {
+                start = idx + 1;
+            } else if idx + 1 - start == number_blocks {
+                self.blocks[start] = BlockState::Large(number_blocks);
+
+                for tail in start + 1..=idx {
+                    self.blocks[tail] = BlockState::LargeTail;
+                }
+
+                let blocks = &self.blocks;
+                self.free_blocks
+                    .retain(|&idx| blocks[idx] == BlockState::Free);
+
+                return self.block_region(start).start;
+            }
+        }
+
+        Address::null()
+    }
+
+    fn make_iterable(&mut self, vm: &VM) {
+        if self.top.is_non_null() {
+            fill_region(vm, self.top, self.limit);
+        }
+
+        self.top = Address::null();
+        self.limit = Address::null();
+    }
+}
+
+struct MarkRegion<'a, 'ast: 'a> {
+    vm: &'a VM<'ast>,
+    heap: Region,
+    perm_space: &'a Space,
+    alloc: &'a mut RegionAllocator,
+    threadpool: &'a mut Pool,
+
+    rootset: &'a [Slot],
+    reason: GcReason,
+
+    live: Vec<usize>,
+    evacuation_top: Address,
+    evacuation_limit: Address,
+    evacuated_blocks: usize,
+    evacuated_size: usize,
+    live_size: usize,
+}
+
+impl<'a, 'ast> MarkRegion<'a, 'ast> {
+    fn collect(&mut self) {
+        let dev_verbose = self.vm.args.flag_gc_dev_verbose;
+
+        if dev_verbose {
+            println!("Region GC: Phase 1 (marking)");
+        }
+
+        self.mark();
+        self.remove_garbage_monitors();
+
+        if dev_verbose {
+            println!("Region GC: Phase 2 (compute live)");
+        }
+
+        self.compute_live();
+
+        if self.select_evacuation_candidates() > 0 {
+            if dev_verbose {
+                println!("Region GC: Phase 3 (evacuate)");
+            }
+
+            self.evacuate();
+
+            if dev_verbose {
+                println!("Region GC: Phase 4 (update refs)");
+            }
+
+            self.update_references();
+            self.forward_monitors();
+        }
+
+        if dev_verbose {
+            println!("Region GC: Phase 5 (sweep)");
+        }
+
+        self.sweep();
+
+        if self.vm.args.flag_gc_verify {
+            if dev_verbose {
+                println!("Region GC: Verify");
+            }
+
+            self.verify();
+        }
+
+        if dev_verbose {
+            println!("Region GC: Stop ({})", self.reason);
+        }
+    }
+
+    fn mark(&mut self) {
+        pmarking::start(
+            self.rootset,
+            self.heap,
+            self.perm_space.total(),
+            self.threadpool,
+        );
+    }
+
+    // needs to happen before blocks without live objects are reused
+    // for evacuation, since that overwrites the garbage.
+    fn remove_garbage_monitors(&mut self) {
+        let heap = self.heap;
+
+        self.vm.monitors.process_weak(|object| {
+            if !heap.contains(object) || object.to_obj().header().is_marked_non_atomic() {
+                Some(object)
+            } else {
+                None
+            }
+        });
+    }
+
+    fn forward_monitors(&mut self) {
+        let heap = self.heap;
+        let alloc = &self.alloc;
+
+        self.vm.monitors.process_weak(|object| {
+            if heap.contains(object)
+                && alloc.blocks[alloc.block_index(object)] == BlockState::Evacuate
+            {
+                let fwd_addr = object.to_obj().header().fwdptr_non_atomic();
+
+                if fwd_addr.is_non_null() {
+                    return Some(fwd_addr);
+                }
+            }
+
+            Some(object)
+        });
+    }
+
+    fn compute_live(&mut self) {
+        self.live = vec![0; self.alloc.blocks.len()];
+
+        for idx in 0..self.alloc.blocks.len() {
+            match self.alloc.blocks[idx] {
+                BlockState::Used => {
+                    let mut live = 0;
+
+                    self.walk_block(idx, |_, object, _, object_size| {
+                        if object.header().is_marked_non_atomic() {
+                            live += object_size;
+                        }
+                    });
+
+                    // blocks without live objects can be reused without sweeping
+                    if live == 0 {
+                        self.free_block(idx);
+                    }
+
+                    self.live[idx] = live;
+                }
+
+                BlockState::Large(number_blocks) => {
+                    let object = self.alloc.block_region(idx).start.to_obj();
+
+                    if object.header().is_marked_non_atomic() {
+                        self.live[idx] = object.size();
+                    } else {
+                        for block in idx..idx + number_blocks {
+                            self.free_block(block);
+                        }
+                    }
+                }
+
+                BlockState::Free | BlockState::LargeTail => {}
+                BlockState::Evacuate => unreachable!(),
+            }
+        }
+    }
+
+    fn free_block(&mut self, idx: usize) {
+        self.alloc.blocks[idx] = BlockState::Free;
+        self.alloc.free_blocks.push(idx);
+    }
+
+    fn select_evacuation_candidates(&mut self) -> usize {
+        // every target block might leave space for the largest object in the
+        // block unused, also leave half of the free blocks to the mutator.
+        let free_blocks = self.alloc.free_blocks.len();
+        let mut budget = free_blocks * (BLOCK_SIZE - LARGE_OBJECT_SIZE) / 2;
+
+        let mut candidates: Vec<usize> = (0..self.alloc.blocks.len())
+            .filter(|&idx| {
+                self.alloc.blocks[idx] == BlockState::Used && self.live[idx] <= EVACUATION_LIVE_SIZE
+            })
+            .collect();
+
+        candidates.sort_by_key(|&idx| self.live[idx]);
+
+        for idx in candidates {
+            let live = self.live[idx];
+
+            if live > budget {
+                break;
+            }
+
+            budget -= live;
+            self.alloc.blocks[idx] = BlockState::Evacuate;
+            self.evacuated_blocks += 1;
+        }
+
+        self.evacuated_blocks
+    }
+
+    fn evacuate(&mut self) {
+        for idx in 0..self.alloc.blocks.len() {
+            if self.alloc.blocks[idx] != BlockState::Evacuate {
+                continue;
+            }
+
+            self.walk_block(idx, |mr, object, _, object_size| {
+                if !object.header().is_marked_non_atomic() {
+                    return;
+                }
+
+                let dest = mr.evacuation_alloc(object_size);
+
+                // no space left: object just stays in its block
+                if dest.is_null() {
+                    return;
+                }
+
+                object.copy_to(dest, object_size);
+
+                let copy = dest.to_mut_obj();
+                copy.header().clear_fwdptr();
+                copy.header_mut().mark_non_atomic();
+
+                object.header_mut().unmark_non_atomic();
+                object.header_mut().set_fwdptr_non_atomic(dest);
+
+                mr.evacuated_size += object_size;
+            });
+        }
+
+        if self.evacuation_top.is_non_null() {
+            self.fill(self.evacuation_top, self.evacuation_limit);
+        }
+    }
+
+    fn evacuation_alloc(&mut self, size: usize) -> Address {
+        let object = self.evacuation_top;
+        let next_top = object.offset(size);
+
+        if object.is_non_null() && next_top <= self.evacuation_limit {
+            self.evacuation_top = next_top;
+            return object;
+        }
+
+        if let Some(block) = self.alloc.alloc_block() {
+            if object.is_non_null() {
+                self.fill(self.evacuation_top, self.evacuation_limit);
+            }
+
+            self.evacuation_top = block.start.offset(size);
+            self.evacuation_limit = block.end;
+            return block.start;
+        }
+
+        Address::null()
+    }
+
+    fn update_references(&mut self) {
+        for root in self.rootset {
+            self.forward_reference(*root);
+        }
+
+        for idx in 0..self.alloc.blocks.len() {
+            match self.alloc.blocks[idx] {
+                BlockState::Used | BlockState::Evacuate => {
+                    self.walk_block(idx, |mr, object, _, _| {
+                        if object.header().is_marked_non_atomic() {
+                            object.visit_reference_fields(|field| {
+                                mr.forward_reference(field);
+                            });
+                        }
+                    });
+                }
+
+                BlockState::Large(_) => {
+                    let object = self.alloc.block_region(idx).start.to_mut_obj();
+
+                    object.visit_reference_fields(|field| {
+                        self.forward_reference(field);
+                    });
+                }
+
+                BlockState::Free | BlockState::LargeTail => {}
+            }
+        }
+    }
+
+    fn forward_reference(&self, slot: Slot) {
+        let object_addr = slot.get();
+
+        if !self.heap.contains(object_addr) {
+            debug_assert!(object_addr.is_null() || self.perm_space.contains(object_addr));
+            return;
+        }
+
+        let idx = self.alloc.block_index(object_addr);
+
+        if self.alloc.blocks[idx] == BlockState::Evacuate {
+            let fwd_addr = object_addr.to_obj().header().fwdptr_non_atomic();
+
+            if fwd_addr.is_non_null() {
+                slot.set(fwd_addr);
+            }
+        }
+    }
+
+    fn sweep(&mut self) {
+        let mut free_blocks = Vec::new();
+        let mut holes = Vec::new();
+        let mut block_holes = Vec::new();
+
+        for idx in 0..self.alloc.blocks.len() {
+            match self.alloc.blocks[idx] {
+                BlockState::Used | BlockState::Evacuate => {
+                    block_holes.clear();
+                    let live = self.sweep_block(idx, &mut block_holes);
+
+                    if live == 0 {
+                        self.alloc.blocks[idx] = BlockState::Free;
+                        free_blocks.push(idx);
+                    } else {
+                        self.alloc.blocks[idx] = BlockState::Used;
+                        holes.extend_from_slice(&block_holes);
+                        self.live_size += live;
+                    }
+                }
+
+                BlockState::Large(_) => {
+                    let object = self.alloc.block_region(idx).start.to_mut_obj();
+                    object.header_mut().unmark_non_atomic();
+                    self.live_size += object.size();
+                }
+
+                BlockState::Free => {
+                    free_blocks.push(idx);
+                }
+
+                BlockState::LargeTail => {}
+            }
+        }
+
+        // allocate in lower blocks first
+        free_blocks.reverse();
+        holes.reverse();
+
+        self.alloc.free_blocks = free_blocks;
+        self.alloc.holes = holes;
+    }
+
+    fn sweep_block(&mut self, idx: usize, holes: &mut Vec<Region>) -> usize {
+        let block = self.alloc.block_region(idx);
+        let mut live_end = block.start;
+        let mut live = 0;
+
+        self.walk_block(idx, |mr, object, address, object_size| {
+            if object.header().is_marked_non_atomic() {
+                object.header_mut().unmark_non_atomic();
+                mr.free_gap(live_end, address, holes);
+
+                live_end = address.offset(object_size);
+                live += object_size;
+            }
+        });
+
+        if live > 0 {
+            self.free_gap(live_end, block.end, holes);
+        }
+
+        live
+    }
+
+    // replaces dead objects between two live objects with filler objects,
+    // lines not touched by any live object are made available as hole.
+    fn free_gap(&mut self, start: Address, end: Address, holes: &mut Vec<Region>) {
+        let hole_start: Address = mem::align_usize(start.to_usize(), LINE_SIZE).into();
+        let hole_end: Address = (end.to_usize() & !(LINE_SIZE - 1)).into();
+
+        if hole_start < hole_end {
+            self.fill(start, hole_start);
+            self.fill(hole_start, hole_end);
+            self.fill(hole_end, end);
+
+            holes.push(Region::new(hole_start, hole_end));
+        } else {
+            self.fill(start, end);
+        }
+    }
+
+    fn fill(&mut self, start: Address, end: Address) {
+        fill_region(self.vm, start, end);
+
+        // filler might contain mark bit of dead object
+        if end.offset_from(start) >= Header::size() as usize {
+            start.to_obj().header().clear_fwdptr();
+        }
+    }
+
+    fn verify(&mut self) {
+        for root in self.rootset {
+            self.verify_reference(root.get());
+        }
+
+        for idx in 0..self.alloc.blocks.len() {
+            match self.alloc.blocks[idx] {
+                BlockState::Used => {
+                    self.walk_block(idx, |mr, object, _, _| {
+                        mr.verify_object(object);
+                    });
+                }
+
+                BlockState::Large(_) => {
+                    let object = self.alloc.block_region(idx).start.to_mut_obj();
+                    self.verify_object(object);
+                }
+
+                BlockState::Free | BlockState::LargeTail => {}
+                BlockState::Evacuate => unreachable!(),
+            }
+        }
+    }
+
+    fn verify_object(&self, object: &mut Obj) {
+        assert!(!object.header().is_marked_non_atomic());
+
+        object.visit_reference_fields(|field| {
+            self.verify_reference(field.get());
+        });
+    }
+
+    fn verify_reference(&self, object_addr: Address) {
+        if !self.heap.contains(object_addr) {
+            assert!(object_addr.is_null() || self.perm_space.contains(object_addr));
+            return;
+        }
+
+        let idx = self.alloc.block_index(object_addr);
+
+        match self.alloc.blocks[idx] {
+            BlockState::Used => {}
+            BlockState::Large(_) => {
+                assert!(object_addr == self.alloc.block_region(idx).start);
+            }
+            state => panic!("reference {} into {:?} block", object_addr, state),
+        }
+
+        assert!(object_addr.to_obj().header().vtblptr().is_non_null());
+    }
+
+    fn walk_block<F>(&mut self, idx: usize, mut fct: F)
+    where
+        F: FnMut(&mut MarkRegion<'a, 'ast>, &mut Obj, Address, usize),
+    {
+        let block = self.alloc.block_region(idx);
+        let mut scan = block.start;
+
+        while scan < block.end {
+            let object = scan.to_mut_obj();
+
+            if object.header().vtblptr().is_null() {
+                scan = scan.add_ptr(1);
+                continue;
+            }
+
+            let object_size = object.size();
+
+            fct(self, object, scan, object_size);
+
+            scan = scan.offset(object_size);
+        }
+
+        debug_assert!(scan == block.end);
+    }
+}
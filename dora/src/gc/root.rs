@@ -2,17 +2,20 @@ use std::sync::Arc;
 
 use crate::compiler::map::CodeDescriptor;
 use crate::gc::Address;
+use crate::mem;
 use crate::stack::DoraToNativeInfo;
 use crate::threads::DoraThread;
-use crate::vm::VM;
+use crate::vm::{get_vm, GlobalData, VM};
 
 pub fn get_rootset(vm: &VM, threads: &[Arc<DoraThread>]) -> Vec<Slot> {
     let mut rootset = Vec::new();
 
     determine_rootset_from_stack(&mut rootset, vm, threads);
     determine_rootset_from_handles(&mut rootset, threads);
+    determine_rootset_from_thread_objects(&mut rootset, threads);
 
     determine_rootset_from_globals(&mut rootset, vm);
+    determine_rootset_from_thread_locals(&mut rootset, vm, threads);
 
     rootset
 }
@@ -26,26 +29,68 @@ fn determine_rootset_from_handles(rootset: &mut Vec<Slot>, threads: &[Arc<DoraTh
     }
 }
 
+fn determine_rootset_from_thread_objects(rootset: &mut Vec<Slot>, threads: &[Arc<DoraThread>]) {
+    for thread in threads {
+        if thread.object().is_non_null() {
+            rootset.push(Slot::at(thread.object_address()));
+        }
+    }
+}
+
 fn determine_rootset_from_globals(rootset: &mut Vec<Slot>, vm: &VM) {
     for glob in vm.globals.iter() {
         let glob = glob.read();
 
-        if glob.ty.reference_type() {
-            let slot = Slot::at(glob.address_value);
-            rootset.push(slot);
-        } else if let Some(tuple_id) = glob.ty.tuple_id() {
-            let tuples = vm.tuples.lock();
-            let tuple = tuples.get_tuple(tuple_id);
-
-            for &offset in tuple.offsets() {
-                let slot_address = glob.address_value.offset(offset as usize);
-                let slot = Slot::at(slot_address);
-                rootset.push(slot);
+        if !glob.thread_local {
+            determine_rootset_from_global(rootset, vm, &*glob, glob.address_value);
+        }
+    }
+}
+
+fn determine_rootset_from_thread_locals(
+    rootset: &mut Vec<Slot>,
+    vm: &VM,
+    threads: &[Arc<DoraThread>],
+) {
+    for thread in threads {
+        let table = thread.tld.thread_locals();
+
+        if table.is_null() {
+            continue;
+        }
+
+        for glob in vm.globals.iter() {
+            let glob = glob.read();
+
+            if glob.thread_local {
+                let address = table.offset(glob.thread_local_value as usize);
+                determine_rootset_from_global(rootset, vm, &*glob, address);
             }
         }
     }
 }
 
+fn determine_rootset_from_global(
+    rootset: &mut Vec<Slot>,
+    vm: &VM,
+    glob: &GlobalData,
+    address: Address,
+) {
+    if glob.ty.reference_type() {
+        let slot = Slot::at(address);
+        rootset.push(slot);
+    } else if let Some(tuple_id) = glob.ty.tuple_id() {
+        let tuples = vm.tuples.lock();
+        let tuple = tuples.get_tuple(tuple_id);
+
+        for &offset in tuple.references() {
+            let slot_address = address.offset(offset as usize);
+            let slot = Slot::at(slot_address);
+            rootset.push(slot);
+        }
+    }
+}
+
 fn determine_rootset_from_stack(rootset: &mut Vec<Slot>, vm: &VM, threads: &[Arc<DoraThread>]) {
     for thread in threads {
         let dtn = Address::from_ptr(thread.dtn());
@@ -128,25 +173,80 @@ fn determine_rootset(rootset: &mut Vec<Slot>, vm: &VM, fp: usize, pc: usize) ->
     }
 }
 
+// slots of compressed references are tagged with the lowest bit,
+// references are at least 4-byte aligned so this bit is always free
+const COMPRESSED_SLOT: usize = 1;
+
 #[derive(Copy, Clone)]
 pub struct Slot(Address);
 
 impl Slot {
+    /// slot with full-width reference, used for roots
     pub fn at(addr: Address) -> Slot {
         Slot(addr)
     }
 
+    /// slot of reference stored in the heap, compressed
+    /// when compressed references are enabled
+    pub fn field(addr: Address) -> Slot {
+        if get_vm().compressed_refs() {
+            Slot((addr.to_usize() | COMPRESSED_SLOT).into())
+        } else {
+            Slot(addr)
+        }
+    }
+
+    fn is_compressed(self) -> bool {
+        (self.0.to_usize() & COMPRESSED_SLOT) != 0
+    }
+
     pub fn address(self) -> Address {
-        self.0
+        (self.0.to_usize() & !COMPRESSED_SLOT).into()
     }
 
     pub fn get(self) -> Address {
-        unsafe { *self.0.to_ptr::<Address>() }
+        if self.is_compressed() {
+            let value = unsafe { *self.address().to_ptr::<u32>() };
+            decompress(value)
+        } else {
+            unsafe { *self.0.to_ptr::<Address>() }
+        }
     }
 
     pub fn set(self, obj: Address) {
-        unsafe {
-            *self.0.to_mut_ptr::<Address>() = obj;
+        if self.is_compressed() {
+            unsafe {
+                *self.address().to_mut_ptr::<u32>() = compress(obj);
+            }
+        } else {
+            unsafe {
+                *self.0.to_mut_ptr::<Address>() = obj;
+            }
         }
     }
 }
+
+pub fn compress(obj: Address) -> u32 {
+    debug_assert!(obj.to_usize() < mem::COMPRESSED_REF_LIMIT);
+    (obj.to_usize() >> mem::COMPRESSED_REF_SHIFT) as u32
+}
+
+pub fn decompress(value: u32) -> Address {
+    ((value as usize) << mem::COMPRESSED_REF_SHIFT).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress, decompress};
+    use crate::gc::Address;
+
+    #[test]
+    fn test_compress() {
+        assert_eq!(0, compress(Address::null()));
+        assert!(decompress(0).is_null());
+
+        let obj: Address = 0x7_ffff_fff8.into();
+        assert_eq!(0xffff_ffff, compress(obj));
+        assert_eq!(obj, decompress(compress(obj)));
+    }
+}
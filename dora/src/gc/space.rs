@@ -24,8 +24,8 @@ fn adapt_to_page_size(config: SpaceConfig) -> SpaceConfig {
     }
 }
 
-/// Non-contiguous space of memory. Used for permanent space
-/// and code space.
+/// Non-contiguous space of memory. Used for permanent space,
+/// code is allocated in `CodeSpace` which supports freeing memory.
 pub struct Space {
     name: &'static str,
     config: SpaceConfig,
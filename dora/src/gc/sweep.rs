@@ -2,6 +2,7 @@ use parking_lot::Mutex;
 
 use crate::driver::cmd::Args;
 use crate::gc::freelist::FreeList;
+use crate::gc::log::GcLog;
 use crate::gc::marking;
 use crate::gc::root::{get_rootset, Slot};
 use crate::gc::space::Space;
@@ -18,6 +19,7 @@ pub struct SweepCollector {
     heap: Region,
     alloc: Mutex<SweepAllocator>,
     stats: Mutex<CollectionStats>,
+    log: Option<GcLog>,
 }
 
 impl SweepCollector {
@@ -40,6 +42,7 @@ impl SweepCollector {
             heap,
             alloc: Mutex::new(SweepAllocator::new(heap)),
             stats: Mutex::new(CollectionStats::new()),
+            log: args.flag_gc_log.as_ref().map(|path| GcLog::new(path)),
         }
     }
 }
@@ -49,7 +52,7 @@ impl Collector for SweepCollector {
         true
     }
 
-    fn alloc_tlab_area(&self, vm: &VM, size: usize) -> Option<Region> {
+    fn alloc_tlab_area(&self, vm: &VM, _min_size: usize, size: usize) -> Option<Region> {
         let ptr = self.inner_alloc(vm, size);
 
         if ptr.is_non_null() {
@@ -79,7 +82,8 @@ impl Collector for SweepCollector {
     }
 
     fn collect(&self, vm: &VM, reason: GcReason) {
-        let mut timer = Timer::new(vm.args.flag_gc_stats);
+        let mut timer = Timer::new(vm.args.gc_phases());
+        let memory_before = self.memory_size();
 
         safepoint::stop_the_world(vm, |threads| {
             tlab::make_iterable_all(vm, threads);
@@ -87,10 +91,21 @@ impl Collector for SweepCollector {
             self.mark_sweep(vm, &rootset, reason);
         });
 
-        if vm.args.flag_gc_stats {
+        if vm.args.gc_phases() {
             let duration = timer.stop();
             let mut stats = self.stats.lock();
             stats.add(duration);
+
+            if let Some(ref log) = self.log {
+                let memory_after = self.memory_size();
+                log.write_full(
+                    stats.collections(),
+                    reason,
+                    duration,
+                    memory_before,
+                    memory_after,
+                );
+            }
         }
     }
 
@@ -129,6 +144,10 @@ impl Drop for SweepCollector {
 }
 
 impl SweepCollector {
+    fn memory_size(&self) -> usize {
+        self.alloc.lock().top.offset_from(self.heap.start)
+    }
+
     fn inner_alloc(&self, vm: &VM, size: usize) -> Address {
         let mut alloc = self.alloc.lock();
         alloc.allocate(vm, size)
@@ -174,6 +193,7 @@ impl<'a, 'ast> MarkSweep<'a, 'ast> {
         }
 
         self.mark();
+        self.process_weak();
 
         if dev_verbose {
             println!("Sweep GC: Phase 2 (sweep)");
@@ -190,6 +210,18 @@ impl<'a, 'ast> MarkSweep<'a, 'ast> {
         marking::start(self.rootset, self.heap, self.perm_space.total());
     }
 
+    fn process_weak(&mut self) {
+        let heap = self.heap;
+
+        self.vm.monitors.process_weak(|object| {
+            if !heap.contains(object) || object.to_obj().header().is_marked_non_atomic() {
+                Some(object)
+            } else {
+                None
+            }
+        });
+    }
+
     fn sweep(&mut self) {
         let start = self.heap.start;
         let end = self.heap.end;
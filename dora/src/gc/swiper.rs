@@ -5,6 +5,7 @@ use std::mem::size_of;
 use std::sync::Arc;
 
 use crate::driver::cmd::Args;
+use crate::gc::log::GcLog;
 use crate::gc::root::{get_rootset, Slot};
 use crate::gc::swiper::card::CardTable;
 use crate::gc::swiper::compact::FullCollector;
@@ -79,6 +80,7 @@ pub struct Swiper {
 
     threadpool: Option<Mutex<Pool>>,
     config: SharedHeapConfig,
+    log: Option<GcLog>,
 }
 
 impl Swiper {
@@ -186,6 +188,8 @@ impl Swiper {
             None
         };
 
+        let log = args.flag_gc_log.as_ref().map(|path| GcLog::new(path));
+
         Swiper {
             heap: Region::new(heap_start, heap_end),
             reserved_area,
@@ -206,6 +210,7 @@ impl Swiper {
             max_heap_size,
 
             threadpool,
+            log,
         }
     }
 
@@ -253,6 +258,7 @@ impl Swiper {
                 &self.large,
                 &vm.args,
                 reason,
+                self.log.as_ref(),
             );
 
             kind
@@ -298,7 +304,7 @@ impl Swiper {
 
             let promotion_failed = collector.collect();
 
-            if vm.args.flag_gc_stats {
+            if vm.args.gc_phases() {
                 let mut config = self.config.lock();
                 config.add_minor(collector.phases());
             }
@@ -321,7 +327,7 @@ impl Swiper {
 
             let promotion_failed = collector.collect();
 
-            if vm.args.flag_gc_stats {
+            if vm.args.gc_phases() {
                 let mut config = self.config.lock();
                 config.add_minor(collector.phases());
             }
@@ -373,7 +379,7 @@ impl Swiper {
             );
             collector.collect(&mut pool);
 
-            if vm.args.flag_gc_stats {
+            if vm.args.gc_phases() {
                 let mut config = self.config.lock();
                 config.add_full(collector.phases());
             }
@@ -394,7 +400,7 @@ impl Swiper {
             );
             collector.collect();
 
-            if vm.args.flag_gc_stats {
+            if vm.args.gc_phases() {
                 let mut config = self.config.lock();
                 config.add_full(collector.phases());
             }
@@ -479,7 +485,7 @@ impl Collector for Swiper {
         true
     }
 
-    fn alloc_tlab_area(&self, vm: &VM, size: usize) -> Option<Region> {
+    fn alloc_tlab_area(&self, vm: &VM, _min_size: usize, size: usize) -> Option<Region> {
         let ptr = self.young.bump_alloc(size);
 
         if !ptr.is_null() {
@@ -91,7 +91,7 @@ impl<'a, 'ast> FullCollector<'a, 'ast> {
 
     pub fn collect(&mut self) {
         let dev_verbose = self.vm.args.flag_gc_dev_verbose;
-        let stats = self.vm.args.flag_gc_stats;
+        let stats = self.vm.args.gc_phases();
         self.init_old_top = self.old_protected.regions.iter().map(|r| r.top()).collect();
 
         let mut timer = Timer::new(stats);
@@ -129,6 +129,7 @@ impl<'a, 'ast> FullCollector<'a, 'ast> {
         }
 
         self.compute_forward();
+        self.process_weak();
 
         if stats {
             let duration = timer.stop();
@@ -201,6 +202,29 @@ impl<'a, 'ast> FullCollector<'a, 'ast> {
         self.old_committed = Region::new(self.old.total_start(), self.old_top);
     }
 
+    // large objects don't move, all other live objects have a forwarding
+    // pointer. Needs to run before garbage large objects get freed.
+    fn process_weak(&mut self) {
+        let heap = self.heap;
+        let large_space = self.large_space;
+
+        self.vm.monitors.process_weak(|object| {
+            if !heap.contains(object) {
+                return Some(object);
+            }
+
+            let header = object.to_obj().header();
+
+            if !header.is_marked_non_atomic() {
+                None
+            } else if large_space.contains(object) {
+                Some(object)
+            } else {
+                Some(header.fwdptr_non_atomic())
+            }
+        });
+    }
+
     fn fits_into_heap(&mut self) -> bool {
         let (eden_size, semi_size) = self.young.committed_size();
         let young_size = eden_size + semi_size;
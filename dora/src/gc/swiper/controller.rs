@@ -4,7 +4,8 @@ use std::f32;
 use std::fmt;
 use std::sync::Arc;
 
-use crate::driver::cmd::Args;
+use crate::driver::cmd::{Args, YoungGenPolicy};
+use crate::gc::log::{GcLog, GcLogRecord};
 use crate::gc::swiper::large::LargeSpace;
 use crate::gc::swiper::young::YoungGen;
 use crate::gc::swiper::{CollectionKind, CommonOldGen};
@@ -18,16 +19,45 @@ const INIT_HEAP_SIZE_RATIO: usize = 2;
 const INIT_YOUNG_RATIO: usize = 4;
 const INIT_SEMI_RATIO: usize = 3;
 
+// weight of the latest sample for the moving averages of pause
+// times, survival rates and promoted bytes
+const AVERAGE_WEIGHT: f32 = 0.3;
+
+// pause target policy never resizes young generation by more than
+// this factor in a single collection
+const MAX_RESIZE_FACTOR: f32 = 2.0;
+
+// adaptive policies never shrink young generation below
+// heap size / MIN_YOUNG_RATIO
+const MIN_YOUNG_RATIO: usize = 16;
+
+// heap is considered idle when at most 1/IDLE_OCCUPANCY_RATIO of the
+// committed memory is live after collection and the mutator ran
+// for at least IDLE_INTERVAL ms since the last collection
+const IDLE_OCCUPANCY_RATIO: usize = 8;
+const IDLE_INTERVAL: f32 = 1000.0;
+
+// number of consecutive idle collections before young generation
+// gets shrunk and memory is given back to the OS
+const IDLE_COLLECTIONS: usize = 3;
+
 pub fn init(config: &mut HeapConfig, args: &Args) {
     assert!(config.min_heap_size <= config.max_heap_size);
 
-    let young_size = if let Some(young_size) = args.young_size() {
-        min(align_gen(young_size), config.max_heap_size - GEN_SIZE)
-    } else if args.young_appel() {
-        let max_heap_size = (config.max_heap_size as f64 * 0.9) as usize;
-        align_gen(max_heap_size / 2)
-    } else {
-        unreachable!();
+    let young_size = match args.young_policy() {
+        YoungGenPolicy::Fixed(young_size) => {
+            min(align_gen(young_size), config.max_heap_size - GEN_SIZE)
+        }
+
+        YoungGenPolicy::Appel => {
+            let max_heap_size = (config.max_heap_size as f64 * 0.9) as usize;
+            align_gen(max_heap_size / 2)
+        }
+
+        YoungGenPolicy::PauseTarget(_) => {
+            // no pause times measured yet, start with small young generation
+            align_gen(config.max_heap_size / INIT_YOUNG_RATIO)
+        }
     };
 
     let young_size = max(young_size, GEN_SIZE);
@@ -67,18 +97,29 @@ fn calculate_young_size(args: &Args, young_size: usize, min_semi_size: usize) ->
 }
 
 pub fn choose_collection_kind(
-    _config: &SharedHeapConfig,
+    config: &SharedHeapConfig,
     _args: &Args,
     young: &YoungGen,
 ) -> CollectionKind {
     let (eden_size, semi_size) = young.committed_size();
     let young_size = eden_size + semi_size;
 
-    return if young_size <= M {
+    if young_size <= M {
+        return CollectionKind::Full;
+    }
+
+    let config = config.lock();
+
+    // old generation is expected to run out of space during the next
+    // minor collection: skip the minor collection that would most likely
+    // end in a promotion failure and collect the whole heap right away.
+    let predicted_old_size = config.old_size + config.avg_minor_promoted as usize;
+
+    if config.total_minor_collections > 0 && predicted_old_size > config.old_limit {
         CollectionKind::Full
     } else {
         CollectionKind::Minor
-    };
+    }
 }
 
 pub fn start(
@@ -90,8 +131,11 @@ pub fn start(
     let mut config = config.lock();
 
     config.gc_start = timer::timestamp();
+    config.mutator_duration = timer::in_ms(config.gc_start - config.gc_end);
     config.start_object_size = object_size(young, old, large);
     config.start_memory_size = memory_size(young, old, large);
+    config.start_spaces = SpaceSizes::new(young, old, large);
+    config.start_young_size = young.active_size();
 }
 
 pub fn stop(
@@ -102,32 +146,52 @@ pub fn stop(
     large: &LargeSpace,
     args: &Args,
     reason: GcReason,
+    log: Option<&GcLog>,
 ) {
     let mut config = config.lock();
 
     let gc_end = timer::timestamp();
+    config.gc_end = gc_end;
     config.gc_duration = timer::in_ms(gc_end - config.gc_start);
 
     assert!(young.eden_active().empty());
     assert!(young.from_active().empty());
 
+    if kind.is_minor() {
+        config.update_minor_averages();
+    }
+
     let old_size = old.committed_size() + large.committed_size();
     config.old_size = old_size;
 
-    let max_young_size = if let Some(young_size) = args.young_size() {
-        align_gen(young_size)
-    } else {
-        std::usize::MAX
+    let rest = config.max_heap_size - config.old_size;
+    let max_young_size = align_gen_down(rest / 2);
+    let current_young_size = config.start_spaces.eden + config.start_spaces.semi;
+
+    let target_young_size = match args.young_policy() {
+        YoungGenPolicy::Fixed(young_size) => min(align_gen(young_size), max_young_size),
+        YoungGenPolicy::Appel => max_young_size,
+        YoungGenPolicy::PauseTarget(pause_target) => {
+            pause_target_young_size(&*config, pause_target, current_young_size, max_young_size)
+        }
     };
 
-    let rest = config.max_heap_size - config.old_size;
-    let target_young_size = align_gen_down(rest / 2);
-    let target_young_size = min(target_young_size, max_young_size);
-    let target_young_size = max(target_young_size, GEN_SIZE);
+    // idle shrinking is opt-in: only the young generation is resized,
+    // old generation keeps its committed memory.
+    let target_young_size = match args.young_policy() {
+        YoungGenPolicy::Fixed(_) => target_young_size,
+        _ if args.flag_gc_idle_shrink => {
+            config.idle_young_size(current_young_size, target_young_size)
+        }
+        _ => target_young_size,
+    };
 
     let to_size = young.to_active().size();
     let min_semi_size = align_gen(mem::page_align(to_size) * 2);
 
+    // young generation needs to fit survivors and at least a small eden
+    let target_young_size = max(target_young_size, min_semi_size + GEN_SIZE);
+
     let (eden_size, semi_size) = calculate_young_size(args, target_young_size, min_semi_size);
     let young_size = eden_size + semi_size;
 
@@ -135,12 +199,15 @@ pub fn stop(
         stdlib::trap(Trap::OOM.int());
     }
 
+    // shrinking the young generation discards the memory and
+    // hands it back to the OS
     young.set_limit(eden_size, semi_size);
     config.old_limit = config.max_heap_size - young_size;
     assert!(config.old_limit >= old_size);
 
     config.end_object_size = object_size(young, old, large);
     config.end_memory_size = memory_size(young, old, large);
+    config.end_spaces = SpaceSizes::new(young, old, large);
 
     assert!(young_size + config.old_limit <= config.max_heap_size);
 
@@ -149,7 +216,7 @@ pub fn stop(
             config.total_minor_collections += 1;
             config.total_minor_pause += config.gc_duration;
 
-            if args.flag_gc_stats {
+            if args.gc_phases() {
                 config.minor_phases.last_mut().unwrap().total = config.gc_duration;
             }
         }
@@ -158,7 +225,7 @@ pub fn stop(
             config.total_full_collections += 1;
             config.total_full_pause += config.gc_duration;
 
-            if args.flag_gc_stats {
+            if args.gc_phases() {
                 config.full_phases.last_mut().unwrap().total = config.gc_duration;
             }
         }
@@ -167,6 +234,38 @@ pub fn stop(
     if args.flag_gc_verbose {
         print(&*config, kind, reason);
     }
+
+    if let Some(log) = log {
+        write_log(log, &*config, kind, reason);
+    }
+}
+
+// Minor pause times grow with the size of the surviving objects: estimate the
+// cost of copying a single byte and choose the young generation size for which
+// the expected survivors can be copied within the pause target.
+fn pause_target_young_size(
+    config: &HeapConfig,
+    pause_target: f32,
+    current_young_size: usize,
+    max_young_size: usize,
+) -> usize {
+    if config.total_minor_collections == 0 || config.avg_minor_survived < 1.0 {
+        return min(current_young_size, max_young_size);
+    }
+
+    let cost_per_byte = config.avg_minor_pause / config.avg_minor_survived;
+    let survival_rate = config.avg_survival_rate.max(f32::EPSILON);
+    let target_young_size = pause_target / (cost_per_byte * survival_rate);
+
+    let current = current_young_size as f32;
+    let target_young_size = target_young_size
+        .max(current / MAX_RESIZE_FACTOR)
+        .min(current * MAX_RESIZE_FACTOR);
+
+    let target_young_size = align_gen_down(target_young_size as usize);
+    let target_young_size = max(target_young_size, config.min_young_size());
+
+    min(target_young_size, max_young_size)
 }
 
 fn print(config: &HeapConfig, kind: CollectionKind, reason: GcReason) {
@@ -202,6 +301,59 @@ fn print(config: &HeapConfig, kind: CollectionKind, reason: GcReason) {
     }
 }
 
+fn write_log(log: &GcLog, config: &HeapConfig, kind: CollectionKind, reason: GcReason) {
+    let mut record = GcLogRecord::new();
+
+    let id = config.total_minor_collections + config.total_full_collections;
+    record.size("id", id);
+    record.ms("time", log.elapsed());
+
+    match kind {
+        CollectionKind::Minor => record.str("kind", "minor"),
+        CollectionKind::Full => record.str("kind", "full"),
+    }
+
+    record.str("reason", reason.message());
+    record.ms("pause", config.gc_duration);
+
+    record.object("phases", |record| match kind {
+        CollectionKind::Minor => {
+            let phases = config.minor_phases.last().unwrap();
+            record.ms("roots", phases.roots);
+            record.ms("tracing", phases.tracing);
+        }
+
+        CollectionKind::Full => {
+            let phases = config.full_phases.last().unwrap();
+            record.ms("marking", phases.marking);
+            record.ms("compute_forward", phases.compute_forward);
+            record.ms("update_refs", phases.update_refs);
+            record.ms("relocate", phases.relocate);
+            record.ms("reset_cards", phases.reset_cards);
+        }
+    });
+
+    if kind.is_minor() {
+        record.size("promoted", config.minor_promoted);
+        record.size("copied", config.minor_copied);
+        record.size("garbage", config.minor_dead);
+    }
+
+    record.object("before", |record| {
+        record.size("object", config.start_object_size);
+        record.size("memory", config.start_memory_size);
+        config.start_spaces.write(record);
+    });
+
+    record.object("after", |record| {
+        record.size("object", config.end_object_size);
+        record.size("memory", config.end_memory_size);
+        config.end_spaces.write(record);
+    });
+
+    log.write(record);
+}
+
 fn object_size(young: &YoungGen, old: &dyn CommonOldGen, large: &LargeSpace) -> usize {
     young.active_size() + old.active_size() + large.committed_size()
 }
@@ -223,17 +375,29 @@ pub struct HeapConfig {
     pub old_limit: usize,
 
     gc_start: u64,
+    gc_end: u64,
     gc_duration: f32,
+    mutator_duration: f32,
 
     start_object_size: usize,
     start_memory_size: usize,
     end_object_size: usize,
     end_memory_size: usize,
 
+    start_spaces: SpaceSizes,
+    end_spaces: SpaceSizes,
+
     pub minor_promoted: usize,
     pub minor_copied: usize,
     pub minor_dead: usize,
 
+    start_young_size: usize,
+    avg_minor_pause: f32,
+    avg_minor_survived: f32,
+    avg_minor_promoted: f32,
+    avg_survival_rate: f32,
+    idle_collections: usize,
+
     pub total_minor_collections: usize,
     pub total_minor_pause: f32,
     pub total_full_collections: usize,
@@ -257,17 +421,29 @@ impl HeapConfig {
             old_limit: 0,
 
             gc_start: 0,
+            gc_end: timer::timestamp(),
             gc_duration: 0f32,
+            mutator_duration: 0f32,
 
             start_object_size: 0,
             start_memory_size: 0,
             end_object_size: 0,
             end_memory_size: 0,
 
+            start_spaces: SpaceSizes::zero(),
+            end_spaces: SpaceSizes::zero(),
+
             minor_promoted: 0,
             minor_copied: 0,
             minor_dead: 0,
 
+            start_young_size: 0,
+            avg_minor_pause: 0f32,
+            avg_minor_survived: 0f32,
+            avg_minor_promoted: 0f32,
+            avg_survival_rate: 0f32,
+            idle_collections: 0,
+
             total_minor_collections: 0,
             total_minor_pause: 0f32,
             total_full_collections: 0,
@@ -367,6 +543,64 @@ impl HeapConfig {
         AllNumbers(self.minor_phases.iter().map(|x| x.total).collect())
     }
 
+    fn update_minor_averages(&mut self) {
+        let survived = self.minor_promoted + self.minor_copied;
+        let survival_rate = if self.start_young_size > 0 {
+            survived as f32 / self.start_young_size as f32
+        } else {
+            0f32
+        };
+
+        if self.total_minor_collections == 0 {
+            self.avg_minor_pause = self.gc_duration;
+            self.avg_minor_survived = survived as f32;
+            self.avg_minor_promoted = self.minor_promoted as f32;
+            self.avg_survival_rate = survival_rate;
+        } else {
+            self.avg_minor_pause = average(self.avg_minor_pause, self.gc_duration);
+            self.avg_minor_survived = average(self.avg_minor_survived, survived as f32);
+            self.avg_minor_promoted = average(self.avg_minor_promoted, self.minor_promoted as f32);
+            self.avg_survival_rate = average(self.avg_survival_rate, survival_rate);
+        }
+    }
+
+    // Shrinks young generation when heap was mostly empty for the last couple
+    // of collections, so that unused memory can be returned to the OS.
+    fn idle_young_size(&mut self, current_young_size: usize, target_young_size: usize) -> usize {
+        let live_size = self.old_size + self.minor_copied;
+        let memory_size = self.old_size + current_young_size;
+        let is_idle = self.mutator_duration >= IDLE_INTERVAL
+            && live_size * IDLE_OCCUPANCY_RATIO <= memory_size;
+
+        if !is_idle {
+            self.idle_collections = 0;
+            return target_young_size;
+        }
+
+        self.idle_collections += 1;
+
+        if self.idle_collections < IDLE_COLLECTIONS {
+            return target_young_size;
+        }
+
+        // never shrink heap below minimum heap size
+        let min_young_size = if self.min_heap_size > self.old_size {
+            align_gen(self.min_heap_size - self.old_size)
+        } else {
+            0
+        };
+        let min_young_size = max(min_young_size, self.min_young_size());
+
+        let young_size = align_gen_down(current_young_size / 2);
+        let young_size = max(young_size, min_young_size);
+
+        min(young_size, target_young_size)
+    }
+
+    fn min_young_size(&self) -> usize {
+        align_gen(self.max_heap_size / MIN_YOUNG_RATIO)
+    }
+
     pub fn grow_old(&mut self, size: usize) -> bool {
         if self.old_size + size <= self.old_limit {
             self.old_size += size;
@@ -382,6 +616,44 @@ impl HeapConfig {
     }
 }
 
+// committed memory per space
+#[derive(Copy, Clone)]
+struct SpaceSizes {
+    eden: usize,
+    semi: usize,
+    old: usize,
+    large: usize,
+}
+
+impl SpaceSizes {
+    fn new(young: &YoungGen, old: &dyn CommonOldGen, large: &LargeSpace) -> SpaceSizes {
+        let (eden, semi) = young.committed_size();
+
+        SpaceSizes {
+            eden,
+            semi,
+            old: old.committed_size(),
+            large: large.committed_size(),
+        }
+    }
+
+    fn zero() -> SpaceSizes {
+        SpaceSizes {
+            eden: 0,
+            semi: 0,
+            old: 0,
+            large: 0,
+        }
+    }
+
+    fn write(&self, record: &mut GcLogRecord) {
+        record.size("eden", self.eden);
+        record.size("semi", self.semi);
+        record.size("old", self.old);
+        record.size("large", self.large);
+    }
+}
+
 pub struct Numbers {
     pub avg: f32,
     pub sum: f32,
@@ -435,6 +707,10 @@ fn calculate_numbers(data: &[f32]) -> Numbers {
     }
 }
 
+fn average(avg: f32, sample: f32) -> f32 {
+    (1.0 - AVERAGE_WEIGHT) * avg + AVERAGE_WEIGHT * sample
+}
+
 pub type SharedHeapConfig = Arc<Mutex<HeapConfig>>;
 
 #[derive(Clone)]
@@ -476,3 +752,79 @@ impl MinorCollectorPhases {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idle_config(min_heap_size: usize, max_heap_size: usize) -> HeapConfig {
+        let mut config = HeapConfig::new(min_heap_size, max_heap_size);
+        config.old_size = M;
+        config.mutator_duration = IDLE_INTERVAL;
+        config
+    }
+
+    #[test]
+    fn test_calculate_young_size() {
+        let args = Args::default();
+        assert_eq!((8 * M, 4 * M), calculate_young_size(&args, 12 * M, 0));
+        assert_eq!((6 * M, 6 * M), calculate_young_size(&args, 12 * M, 6 * M));
+    }
+
+    #[test]
+    fn test_idle_young_size_shrinks_after_idle_collections() {
+        let mut config = idle_config(4 * M, 64 * M);
+
+        for _ in 1..IDLE_COLLECTIONS {
+            assert_eq!(32 * M, config.idle_young_size(32 * M, 32 * M));
+        }
+
+        assert_eq!(16 * M, config.idle_young_size(32 * M, 32 * M));
+    }
+
+    #[test]
+    fn test_idle_young_size_resets_when_busy() {
+        let mut config = idle_config(4 * M, 64 * M);
+
+        for _ in 1..IDLE_COLLECTIONS {
+            config.idle_young_size(32 * M, 32 * M);
+        }
+
+        config.mutator_duration = 0.0;
+        assert_eq!(32 * M, config.idle_young_size(32 * M, 32 * M));
+
+        config.mutator_duration = IDLE_INTERVAL;
+        assert_eq!(32 * M, config.idle_young_size(32 * M, 32 * M));
+    }
+
+    #[test]
+    fn test_idle_young_size_respects_min_heap_size() {
+        let mut config = idle_config(48 * M, 64 * M);
+
+        for _ in 1..IDLE_COLLECTIONS {
+            config.idle_young_size(60 * M, 60 * M);
+        }
+
+        assert_eq!(47 * M, config.idle_young_size(60 * M, 60 * M));
+    }
+
+    #[test]
+    fn test_pause_target_young_size() {
+        let mut config = HeapConfig::new(M, 16 * M);
+
+        // without measured pauses young generation is kept as is
+        assert_eq!(3 * M, pause_target_young_size(&config, 1.0, 3 * M, 8 * M));
+
+        config.total_minor_collections = 1;
+        config.avg_minor_pause = 1.0;
+        config.avg_minor_survived = M as f32;
+        config.avg_survival_rate = 0.5;
+
+        assert_eq!(2 * M, pause_target_young_size(&config, 1.0, 3 * M, 8 * M));
+
+        // resizing is limited by MAX_RESIZE_FACTOR and the maximum size
+        assert_eq!(6 * M, pause_target_young_size(&config, 100.0, 3 * M, 8 * M));
+        assert_eq!(4 * M, pause_target_young_size(&config, 100.0, 3 * M, 4 * M));
+        assert_eq!(M, pause_target_young_size(&config, 0.01, 2 * M, 8 * M));
+    }
+}
@@ -97,7 +97,7 @@ impl<'a, 'ast: 'a> MinorCollector<'a, 'ast> {
         self.init_old_top = self.old_protected.regions.iter().map(|r| r.top()).collect();
 
         let dev_verbose = self.vm.args.flag_gc_dev_verbose;
-        let mut timer = Timer::new(self.vm.args.flag_gc_stats);
+        let mut timer = Timer::new(self.vm.args.gc_phases());
 
         self.young.unprotect_from();
         self.young.swap_semi();
@@ -121,7 +121,7 @@ impl<'a, 'ast: 'a> MinorCollector<'a, 'ast> {
 
         self.visit_dirty_cards();
 
-        if self.vm.args.flag_gc_stats {
+        if self.vm.args.gc_phases() {
             let duration = timer.stop();
             self.phases.roots = duration;
         }
@@ -132,7 +132,7 @@ impl<'a, 'ast: 'a> MinorCollector<'a, 'ast> {
 
         self.trace_gray_objects();
 
-        if self.vm.args.flag_gc_stats {
+        if self.vm.args.gc_phases() {
             let duration = timer.stop();
             self.phases.tracing = duration;
         }
@@ -149,6 +149,16 @@ impl<'a, 'ast: 'a> MinorCollector<'a, 'ast> {
             return true;
         }
 
+        let young = self.young;
+
+        self.vm.monitors.process_weak(|object| {
+            if young.contains(object) {
+                object.to_obj().header().vtblptr_forwarded()
+            } else {
+                Some(object)
+            }
+        });
+
         self.young.minor_success(self.young_top);
 
         assert!(self.young.eden_active().empty());
@@ -405,7 +415,7 @@ impl<'a, 'ast: 'a> MinorCollector<'a, 'ast> {
         let mut ptr = start;
 
         while ptr < end {
-            let slot = Slot::at(ptr);
+            let slot = Slot::field(ptr);
             let obj = slot.get();
 
             if self.young.contains(obj) {
@@ -417,7 +427,7 @@ impl<'a, 'ast: 'a> MinorCollector<'a, 'ast> {
                 }
             }
 
-            ptr = ptr.add_ptr(1);
+            ptr = ptr.offset(self.vm.ref_width() as usize);
         }
     }
 
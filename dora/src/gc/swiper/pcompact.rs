@@ -97,7 +97,7 @@ impl<'a, 'ast> ParallelFullCollector<'a, 'ast> {
 
     pub fn collect(&mut self, pool: &mut Pool) {
         let dev_verbose = self.vm.args.flag_gc_dev_verbose;
-        let stats = self.vm.args.flag_gc_stats;
+        let stats = self.vm.args.gc_phases();
 
         let mut timer = Timer::new(stats);
 
@@ -134,6 +134,7 @@ impl<'a, 'ast> ParallelFullCollector<'a, 'ast> {
         }
 
         self.compute_forward(pool);
+        self.process_weak();
 
         if stats {
             let duration = timer.stop();
@@ -258,6 +259,29 @@ impl<'a, 'ast> ParallelFullCollector<'a, 'ast> {
         }
     }
 
+    // large objects don't move, all other live objects have a forwarding
+    // pointer. Needs to run before garbage large objects get freed.
+    fn process_weak(&mut self) {
+        let heap = self.heap;
+        let large_space = self.large_space;
+
+        self.vm.monitors.process_weak(|object| {
+            if !heap.contains(object) {
+                return Some(object);
+            }
+
+            let header = object.to_obj().header();
+
+            if !header.is_marked_non_atomic() {
+                None
+            } else if large_space.contains(object) {
+                Some(object)
+            } else {
+                Some(header.fwdptr_non_atomic())
+            }
+        });
+    }
+
     fn verify_mapped_regions(&mut self, regions: &[Region]) {
         let mut new_regions = regions.to_vec();
         let old_regions = self
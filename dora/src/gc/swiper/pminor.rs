@@ -123,6 +123,7 @@ impl<'a, 'ast: 'a> ParallelMinorCollector<'a, 'ast> {
         self.young_limit = to_committed.end;
 
         self.from_active = self.young.from_active();
+        let init_young_size = self.young.eden_active().size() + self.young.from_active().size();
 
         let dev_verbose = self.vm.args.flag_gc_dev_verbose;
 
@@ -144,6 +145,16 @@ impl<'a, 'ast: 'a> ParallelMinorCollector<'a, 'ast> {
             return true;
         }
 
+        let young = self.young;
+
+        self.vm.monitors.process_weak(|object| {
+            if young.contains(object) {
+                object.to_obj().header().vtblptr_forwarded_atomic().ok()
+            } else {
+                Some(object)
+            }
+        });
+
         self.young.minor_success(self.young_top);
 
         assert!(self.young.eden_active().empty());
@@ -151,7 +162,9 @@ impl<'a, 'ast: 'a> ParallelMinorCollector<'a, 'ast> {
 
         let mut config = self.config.lock();
         config.minor_promoted = self.promoted_size;
-        config.minor_copied = self.young.from_active().size();
+        let copied_size = self.young.to_active().size();
+        config.minor_copied = copied_size;
+        config.minor_dead = init_young_size - self.promoted_size - copied_size;
 
         self.promotion_failed
     }
@@ -161,7 +174,7 @@ impl<'a, 'ast: 'a> ParallelMinorCollector<'a, 'ast> {
         let mut stealers = Vec::with_capacity(self.number_workers);
         let injector = Injector::new();
 
-        let stats = self.vm.args.flag_gc_stats;
+        let stats = self.vm.args.gc_phases();
         let timer = Timer::new(stats);
 
         for _ in 0..self.number_workers {
@@ -661,7 +674,7 @@ where
         let mut ptr = start;
 
         while ptr < end {
-            let slot = Slot::at(ptr);
+            let slot = Slot::field(ptr);
             let obj = slot.get();
 
             if self.young.contains(obj) {
@@ -673,7 +686,7 @@ where
                 }
             }
 
-            ptr = ptr.add_ptr(1);
+            ptr = ptr.offset(self.vm.ref_width() as usize);
         }
     }
 
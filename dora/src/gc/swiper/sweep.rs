@@ -172,7 +172,7 @@ impl Collector for SweepSwiper {
         true
     }
 
-    fn alloc_tlab_area(&self, _vm: &VM, _size: usize) -> Option<Region> {
+    fn alloc_tlab_area(&self, _vm: &VM, _min_size: usize, _size: usize) -> Option<Region> {
         unimplemented!()
     }
 
@@ -254,6 +254,7 @@ impl SweepSwiper {
                 &self.large,
                 &vm.args,
                 reason,
+                None,
             );
 
             kind
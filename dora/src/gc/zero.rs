@@ -33,7 +33,7 @@ impl Collector for ZeroCollector {
         true
     }
 
-    fn alloc_tlab_area(&self, _vm: &VM, size: usize) -> Option<Region> {
+    fn alloc_tlab_area(&self, _vm: &VM, _min_size: usize, size: usize) -> Option<Region> {
         let ptr = self.alloc.bump_alloc(size);
 
         if ptr.is_null() {
@@ -28,11 +28,13 @@ mod cpu;
 mod disassembler;
 mod driver;
 mod dseg;
+pub mod embed;
 mod error;
 mod gc;
 mod handle;
 mod masm;
 mod mem;
+mod monitors;
 mod object;
 mod os;
 mod safepoint;
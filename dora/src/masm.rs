@@ -356,6 +356,14 @@ pub enum CondCode {
     UnsignedLessEq,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum RoundingMode {
+    NearestEven,
+    Down,
+    Up,
+    Zero,
+}
+
 #[derive(Debug)]
 pub struct ScratchReg {
     ind: u32,
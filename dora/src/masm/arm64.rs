@@ -8,8 +8,8 @@ use crate::cpu::asm::*;
 use crate::cpu::reg::*;
 use crate::cpu::{FReg, Mem, Reg};
 use crate::gc::swiper::CARD_SIZE_BITS;
-use crate::masm::{CondCode, Label, MacroAssembler};
-use crate::mem::ptr_width;
+use crate::masm::{CondCode, Label, MacroAssembler, RoundingMode};
+use crate::mem::{ptr_width, COMPRESSED_REF_SHIFT};
 use crate::object::{offset_of_array_data, offset_of_array_length, Header};
 use crate::threads::ThreadLocalData;
 use crate::ty::{MachineMode, TypeList};
@@ -728,6 +728,29 @@ impl MacroAssembler {
         self.emit_u32(asm::fsqrt(dbl, dest, src));
     }
 
+    pub fn float_round(
+        &mut self,
+        mode: MachineMode,
+        dest: FReg,
+        src: FReg,
+        rounding: RoundingMode,
+    ) {
+        let dbl = match mode {
+            MachineMode::Float32 => 0,
+            MachineMode::Float64 => 1,
+            _ => unimplemented!(),
+        };
+
+        let inst = match rounding {
+            RoundingMode::NearestEven => asm::frintn(dbl, dest, src),
+            RoundingMode::Down => asm::frintm(dbl, dest, src),
+            RoundingMode::Up => asm::frintp(dbl, dest, src),
+            RoundingMode::Zero => asm::frintz(dbl, dest, src),
+        };
+
+        self.emit_u32(inst);
+    }
+
     pub fn float_cmp(
         &mut self,
         mode: MachineMode,
@@ -947,6 +970,93 @@ impl MacroAssembler {
         }
     }
 
+    pub fn load_compressed_ref(&mut self, dest: Reg, mem: Mem) {
+        // 32-bit load zero-extends into the full register
+        self.load_mem(MachineMode::Int32, dest.into(), mem);
+        self.decompress_ref(dest);
+    }
+
+    // clobbers src
+    pub fn store_compressed_ref(&mut self, mem: Mem, src: Reg) {
+        self.compress_ref(src);
+        self.store_mem(MachineMode::Int32, mem, src.into());
+    }
+
+    pub fn compress_ref(&mut self, reg: Reg) {
+        self.emit_u32(asm::lsr_imm(1, reg, reg, COMPRESSED_REF_SHIFT as u32));
+    }
+
+    // expects the upper 32 bits of reg to be zero
+    pub fn decompress_ref(&mut self, reg: Reg) {
+        self.emit_u32(asm::lsl_imm(1, reg, reg, COMPRESSED_REF_SHIFT as u32));
+    }
+
+    pub fn atomic_load(&mut self, mode: MachineMode, dest: Reg, mem: Mem) {
+        let address = self.get_scratch();
+        self.lea(*address, mem);
+        self.emit_u32(asm::ldar(atomic_size(mode), dest, *address));
+    }
+
+    // clobbers src
+    pub fn atomic_store(&mut self, mode: MachineMode, mem: Mem, src: Reg) {
+        let address = self.get_scratch();
+        self.lea(*address, mem);
+        self.emit_u32(asm::stlr(atomic_size(mode), src, *address));
+    }
+
+    pub fn atomic_exchange(&mut self, mode: MachineMode, dest: Reg, mem: Mem, src: Reg) {
+        let size = atomic_size(mode);
+        let address = self.get_scratch();
+        self.lea(*address, mem);
+        let status = self.get_scratch();
+
+        let start = self.pos();
+        self.emit_u32(asm::ldaxr(size, dest, *address));
+        self.emit_u32(asm::stlxr(size, *status, src, *address));
+        self.emit_u32(asm::cbnz(0, *status, self.backward_offset(start)));
+    }
+
+    // compares the value in memory with REG_RESULT and stores src on equality,
+    // the previous value is returned in REG_RESULT
+    pub fn atomic_compare_exchange(&mut self, mode: MachineMode, mem: Mem, src: Reg) {
+        let size = atomic_size(mode);
+        let address = self.get_scratch();
+        self.lea(*address, mem);
+        let old = self.get_scratch();
+        let status = self.get_scratch();
+        let lbl_done = self.create_label();
+
+        let start = self.pos();
+        self.emit_u32(asm::ldaxr(size, *old, *address));
+        self.cmp_reg(mode, *old, REG_RESULT);
+        self.jump_if(CondCode::NotEqual, lbl_done);
+        self.emit_u32(asm::stlxr(size, *status, src, *address));
+        self.emit_u32(asm::cbnz(0, *status, self.backward_offset(start)));
+        self.bind_label(lbl_done);
+        self.copy_reg(mode, REG_RESULT, *old);
+    }
+
+    // returns the previous value in dest
+    pub fn atomic_fetch_add(&mut self, mode: MachineMode, dest: Reg, mem: Mem, src: Reg) {
+        let size = atomic_size(mode);
+        let address = self.get_scratch();
+        self.lea(*address, mem);
+        let sum = self.get_scratch();
+        let status = self.get_scratch();
+
+        let start = self.pos();
+        self.emit_u32(asm::ldaxr(size, dest, *address));
+        self.emit_u32(asm::add_reg(size_flag(mode), *sum, dest, src));
+        self.emit_u32(asm::stlxr(size, *status, *sum, *address));
+        self.emit_u32(asm::cbnz(0, *status, self.backward_offset(start)));
+    }
+
+    fn backward_offset(&self, target: usize) -> i32 {
+        let diff = -((self.pos() - target) as i32);
+        assert!(diff % 4 == 0);
+        diff / 4
+    }
+
     pub fn lea(&mut self, dest: Reg, mem: Mem) {
         match mem {
             Mem::Local(offset) => {
@@ -1305,6 +1415,16 @@ fn size_flag(mode: MachineMode) -> u32 {
     }
 }
 
+// size field of exclusive and acquire/release loads and stores
+fn atomic_size(mode: MachineMode) -> u32 {
+    match mode {
+        MachineMode::Int8 => 0b00,
+        MachineMode::Int32 => 0b10,
+        MachineMode::IntPtr | MachineMode::Ptr | MachineMode::Int64 => 0b11,
+        MachineMode::Float32 | MachineMode::Float64 => unimplemented!(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
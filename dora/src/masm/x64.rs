@@ -8,8 +8,8 @@ use crate::compiler::codegen::AnyReg;
 use crate::compiler::fct::LazyCompilationSite;
 use crate::cpu::*;
 use crate::gc::swiper::CARD_SIZE_BITS;
-use crate::masm::{CondCode, Label, MacroAssembler};
-use crate::mem::{fits_i32, ptr_width};
+use crate::masm::{CondCode, Label, MacroAssembler, RoundingMode};
+use crate::mem::{fits_i32, ptr_width, COMPRESSED_REF_SHIFT};
 use crate::object::{offset_of_array_data, offset_of_array_length, Header};
 use crate::threads::ThreadLocalData;
 use crate::ty::{MachineMode, TypeList};
@@ -837,6 +837,88 @@ impl MacroAssembler {
         }
     }
 
+    pub fn load_compressed_ref(&mut self, dest: Reg, mem: Mem) {
+        // movl zero-extends into the full register
+        self.asm.movl_ra(dest.into(), address_from_mem(mem));
+        self.decompress_ref(dest);
+    }
+
+    // clobbers src
+    pub fn store_compressed_ref(&mut self, mem: Mem, src: Reg) {
+        self.compress_ref(src);
+        self.asm.movl_ar(address_from_mem(mem), src.into());
+    }
+
+    pub fn compress_ref(&mut self, reg: Reg) {
+        self.asm
+            .shrq_ri(reg.into(), Immediate(COMPRESSED_REF_SHIFT as i64));
+    }
+
+    // expects the upper 32 bits of reg to be zero
+    pub fn decompress_ref(&mut self, reg: Reg) {
+        self.asm
+            .shlq_ri(reg.into(), Immediate(COMPRESSED_REF_SHIFT as i64));
+    }
+
+    // loads are never reordered with other loads on x64, a plain
+    // mov is enough as long as stores use xchg
+    pub fn atomic_load(&mut self, mode: MachineMode, dest: Reg, mem: Mem) {
+        self.load_mem(mode, dest.into(), mem);
+    }
+
+    // clobbers src
+    pub fn atomic_store(&mut self, mode: MachineMode, mem: Mem, src: Reg) {
+        let address = address_from_mem(mem);
+
+        match mode {
+            MachineMode::Int8 => self.asm.xchgb_ar(address, src.into()),
+            MachineMode::Int32 => self.asm.xchgl_ar(address, src.into()),
+            MachineMode::Int64 | MachineMode::Ptr | MachineMode::IntPtr => {
+                self.asm.xchgq_ar(address, src.into())
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn atomic_exchange(&mut self, mode: MachineMode, dest: Reg, mem: Mem, src: Reg) {
+        self.copy_reg(mode, dest, src);
+        self.atomic_store(mode, mem, dest);
+
+        if mode == MachineMode::Int8 {
+            self.asm.movzxb_rr(dest.into(), dest.into());
+        }
+    }
+
+    // compares the value in memory with REG_RESULT and stores src on equality,
+    // the previous value is returned in REG_RESULT
+    pub fn atomic_compare_exchange(&mut self, mode: MachineMode, mem: Mem, src: Reg) {
+        let address = address_from_mem(mem);
+
+        match mode {
+            MachineMode::Int8 => {
+                self.asm.lock_cmpxchgb_ar(address, src.into());
+                self.asm.movzxb_rr(REG_RESULT.into(), REG_RESULT.into());
+            }
+            MachineMode::Int32 => self.asm.lock_cmpxchgl_ar(address, src.into()),
+            MachineMode::Int64 | MachineMode::Ptr | MachineMode::IntPtr => {
+                self.asm.lock_cmpxchgq_ar(address, src.into())
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // returns the previous value in dest
+    pub fn atomic_fetch_add(&mut self, mode: MachineMode, dest: Reg, mem: Mem, src: Reg) {
+        self.copy_reg(mode, dest, src);
+        let address = address_from_mem(mem);
+
+        match mode {
+            MachineMode::Int32 => self.asm.lock_xaddl_ar(address, dest.into()),
+            MachineMode::Int64 => self.asm.lock_xaddq_ar(address, dest.into()),
+            _ => unreachable!(),
+        }
+    }
+
     pub fn lea(&mut self, dest: Reg, mem: Mem) {
         self.asm.lea(dest.into(), address_from_mem(mem));
     }
@@ -1112,6 +1194,27 @@ impl MacroAssembler {
         }
     }
 
+    pub fn float_round(
+        &mut self,
+        mode: MachineMode,
+        dest: FReg,
+        src: FReg,
+        rounding: RoundingMode,
+    ) {
+        let rounding = match rounding {
+            RoundingMode::NearestEven => 0,
+            RoundingMode::Down => 1,
+            RoundingMode::Up => 2,
+            RoundingMode::Zero => 3,
+        };
+
+        match mode {
+            MachineMode::Float32 => self.asm.roundss_ri(dest.into(), src.into(), rounding),
+            MachineMode::Float64 => self.asm.roundsd_ri(dest.into(), src.into(), rounding),
+            _ => unreachable!(),
+        }
+    }
+
     pub fn trap(&mut self, trap: Trap, pos: Position) {
         let vm = get_vm();
         self.load_int_const(MachineMode::Int32, REG_PARAMS[0], trap.int() as i64);
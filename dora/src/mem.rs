@@ -15,6 +15,14 @@ pub fn ptr_width_usize() -> usize {
     size_of::<*const u8>() as usize
 }
 
+/// compressed references store the object address shifted by
+/// this amount in 32 bits, objects are always 8-byte aligned
+pub const COMPRESSED_REF_SHIFT: usize = 3;
+
+/// the whole heap needs to be below this address when
+/// compressed references are enabled
+pub const COMPRESSED_REF_LIMIT: usize = 1 << (32 + COMPRESSED_REF_SHIFT);
+
 /// returns true if given value is a multiple of a page size.
 pub fn is_page_aligned(val: usize) -> bool {
     let align = os::page_size_bits();
@@ -0,0 +1,520 @@
+use parking_lot::{Condvar, Mutex};
+use std::collections::hash_map::HashMap;
+use std::collections::hash_set::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::gc::Address;
+use crate::object::{self, Obj, Ref};
+use crate::stack::stacktrace_from_last_dtn;
+use crate::threads::{DoraThread, THREAD};
+use crate::vm::VM;
+
+/// Runtime state for the Mutex and ConditionVariable classes in the stdlib.
+///
+/// Objects can be moved by the GC, so they only store an id that is
+/// assigned on first use. A thread that needs to block is parked first,
+/// such that it never holds up a safepoint.
+///
+/// With --check-lock-order the order in which mutexes are acquired is
+/// recorded, the collectors call process_weak() to drop mutexes that
+/// are not reachable anymore.
+pub struct Monitors {
+    data: Mutex<MonitorData>,
+}
+
+#[derive(Debug)]
+pub enum MonitorError {
+    NotOwner,
+}
+
+impl Monitors {
+    pub fn new() -> Monitors {
+        Monitors {
+            data: Mutex::new(MonitorData {
+                next_id: 1,
+                mutexes: HashMap::new(),
+                conditions: HashMap::new(),
+                objects: HashMap::new(),
+                lock_order: LockOrder::new(),
+            }),
+        }
+    }
+
+    pub fn lock(&self, vm: &VM, mutex: Ref<Obj>) {
+        let thread = THREAD.with(|thread| thread.borrow().clone());
+        let mut data = self.data.lock();
+        let id = data.id(vm, mutex);
+
+        let inversion = if vm.args.check_lock_order() {
+            data.lock_order.acquire(thread.id(), id)
+        } else {
+            None
+        };
+
+        let state = data.mutexes.entry(id).or_insert_with(MutexState::new);
+
+        if state.try_acquire(thread.id()) {
+            drop(data);
+            report_inversion(vm, inversion);
+            return;
+        }
+
+        state.waiters += 1;
+        let cond = state.cond.clone();
+
+        thread.park(vm);
+
+        loop {
+            let state = data.mutexes.get_mut(&id).expect("mutex missing");
+
+            if state.try_acquire(thread.id()) {
+                state.waiters -= 1;
+                break;
+            }
+
+            cond.wait(&mut data);
+        }
+
+        drop(data);
+        thread.unpark(vm);
+        report_inversion(vm, inversion);
+    }
+
+    pub fn try_lock(&self, vm: &VM, mutex: Ref<Obj>) -> bool {
+        let thread_id = THREAD.with(|thread| thread.borrow().id());
+        let mut data = self.data.lock();
+        let id = data.id(vm, mutex);
+        let state = data.mutexes.entry(id).or_insert_with(MutexState::new);
+
+        if !state.try_acquire(thread_id) {
+            return false;
+        }
+
+        // a failing tryLock() can't deadlock, only record successful ones
+        if vm.args.check_lock_order() {
+            let inversion = data.lock_order.acquire(thread_id, id);
+            drop(data);
+            report_inversion(vm, inversion);
+        }
+
+        true
+    }
+
+    pub fn unlock(&self, vm: &VM, mutex: Ref<Obj>) -> Result<(), MonitorError> {
+        let thread_id = THREAD.with(|thread| thread.borrow().id());
+        let mut data = self.data.lock();
+        let id = data.id(vm, mutex);
+
+        let released = match data.mutexes.get_mut(&id) {
+            Some(state) if state.owner == Some(thread_id) => {
+                state.depth -= 1;
+                state.depth == 0
+            }
+
+            _ => return Err(MonitorError::NotOwner),
+        };
+
+        if released {
+            data.release(id);
+            data.lock_order.release(thread_id, id);
+        }
+
+        Ok(())
+    }
+
+    /// releases the mutex, waits for a notification and acquires the mutex
+    /// again, with the same recursion depth as before. Returns false if
+    /// the timeout expired before a notification arrived.
+    pub fn wait(
+        &self,
+        vm: &VM,
+        cond: Ref<Obj>,
+        mutex: Ref<Obj>,
+        timeout: Option<Duration>,
+    ) -> Result<bool, MonitorError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let thread = THREAD.with(|thread| thread.borrow().clone());
+        let thread_id = thread.id();
+        let mut data = self.data.lock();
+        let cond_id = data.id(vm, cond);
+        let mutex_id = data.id(vm, mutex);
+
+        let depth = match data.mutexes.get(&mutex_id) {
+            Some(state) if state.owner == Some(thread_id) => state.depth,
+            _ => return Err(MonitorError::NotOwner),
+        };
+
+        thread.park(vm);
+
+        data.mutexes.get_mut(&mutex_id).unwrap().depth = 0;
+        data.release(mutex_id);
+        data.lock_order.release(thread_id, mutex_id);
+
+        let state = data
+            .conditions
+            .entry(cond_id)
+            .or_insert_with(ConditionState::new);
+        state.waiters += 1;
+        let condvar = state.cond.clone();
+
+        let notified = loop {
+            let state = data.conditions.get_mut(&cond_id).unwrap();
+
+            let notified = if state.permits > 0 {
+                state.permits -= 1;
+                true
+            } else if deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+                false
+            } else {
+                match deadline {
+                    Some(deadline) => {
+                        condvar.wait_until(&mut data, deadline);
+                    }
+                    None => condvar.wait(&mut data),
+                }
+
+                continue;
+            };
+
+            state.waiters -= 1;
+
+            if state.waiters == 0 {
+                data.conditions.remove(&cond_id);
+            }
+
+            break notified;
+        };
+
+        let state = data.mutexes.entry(mutex_id).or_insert_with(MutexState::new);
+        state.waiters += 1;
+        let condvar = state.cond.clone();
+
+        loop {
+            let state = data.mutexes.get_mut(&mutex_id).unwrap();
+
+            if state.try_acquire(thread_id) {
+                state.depth = depth;
+                state.waiters -= 1;
+                break;
+            }
+
+            condvar.wait(&mut data);
+        }
+
+        let inversion = if vm.args.check_lock_order() {
+            data.lock_order.acquire(thread_id, mutex_id)
+        } else {
+            None
+        };
+
+        drop(data);
+        thread.unpark(vm);
+        report_inversion(vm, inversion);
+
+        Ok(notified)
+    }
+
+    pub fn notify(&self, vm: &VM, cond: Ref<Obj>, all: bool) {
+        let mut data = self.data.lock();
+        let id = data.id(vm, cond);
+
+        if let Some(state) = data.conditions.get_mut(&id) {
+            if all {
+                state.permits = state.waiters;
+                state.cond.notify_all();
+            } else if state.permits < state.waiters {
+                state.permits += 1;
+                state.cond.notify_one();
+            }
+        }
+    }
+
+    /// forgets about all locks held by the thread, used
+    /// when a thread finishes.
+    pub fn detach_thread(&self, thread: &DoraThread) {
+        let mut data = self.data.lock();
+        data.lock_order.held.remove(&thread.id());
+    }
+
+    /// called by the collector during a collection: `forward` returns
+    /// the address of a recorded object after the collection or None
+    /// if it is garbage. Garbage mutexes are removed from the lock order.
+    pub fn process_weak<F>(&self, mut forward: F)
+    where
+        F: FnMut(Address) -> Option<Address>,
+    {
+        let mut data = self.data.lock();
+        let mut garbage = Vec::new();
+
+        for (&id, object) in data.objects.iter_mut() {
+            match forward(*object) {
+                Some(fwd) => *object = fwd,
+                None => garbage.push(id),
+            }
+        }
+
+        for id in garbage {
+            data.objects.remove(&id);
+            data.lock_order.remove(id);
+        }
+    }
+}
+
+// reports an inversion found by LockOrder::acquire(), the monitor
+// data must not be locked since dumping the stacktrace may allocate.
+fn report_inversion(vm: &VM, inversion: Option<(i64, i64)>) {
+    if let Some((held, acquired)) = inversion {
+        eprintln!(
+            "error: lock order inversion: locking mutex #{} while holding mutex #{}, \
+             but they were locked in the opposite order before",
+            acquired, held
+        );
+
+        let stacktrace = stacktrace_from_last_dtn(vm);
+        stacktrace.dump_err(vm);
+    }
+}
+
+struct MonitorData {
+    next_id: i64,
+    mutexes: HashMap<i64, MutexState>,
+    conditions: HashMap<i64, ConditionState>,
+    objects: HashMap<i64, Address>,
+    lock_order: LockOrder,
+}
+
+impl MonitorData {
+    // ids are only read and assigned while holding the lock, the
+    // object can't move since the current thread isn't parked.
+    fn id(&mut self, vm: &VM, obj: Ref<Obj>) -> i64 {
+        let cls_def_id = obj.header().vtbl().class().id;
+        let fid = vm.field_in_class(cls_def_id, "id");
+        let id = object::read_int64(vm, obj, cls_def_id, fid);
+
+        if id != 0 {
+            return id;
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        object::write_int64(vm, obj, cls_def_id, fid, id);
+
+        // remember the object to notice when it gets collected
+        if vm.args.check_lock_order() {
+            self.objects.insert(id, obj.address());
+        }
+
+        id
+    }
+
+    fn release(&mut self, id: i64) {
+        let state = self.mutexes.get_mut(&id).unwrap();
+        state.owner = None;
+
+        if state.waiters > 0 {
+            state.cond.notify_one();
+        } else {
+            self.mutexes.remove(&id);
+        }
+    }
+}
+
+struct MutexState {
+    owner: Option<usize>,
+    depth: usize,
+    waiters: usize,
+    cond: Arc<Condvar>,
+}
+
+impl MutexState {
+    fn new() -> MutexState {
+        MutexState {
+            owner: None,
+            depth: 0,
+            waiters: 0,
+            cond: Arc::new(Condvar::new()),
+        }
+    }
+
+    fn try_acquire(&mut self, thread_id: usize) -> bool {
+        match self.owner {
+            None => {
+                self.owner = Some(thread_id);
+                self.depth = 1;
+                true
+            }
+
+            Some(owner) if owner == thread_id => {
+                self.depth += 1;
+                true
+            }
+
+            Some(_) => false,
+        }
+    }
+}
+
+struct ConditionState {
+    waiters: usize,
+    permits: usize,
+    cond: Arc<Condvar>,
+}
+
+impl ConditionState {
+    fn new() -> ConditionState {
+        ConditionState {
+            waiters: 0,
+            permits: 0,
+            cond: Arc::new(Condvar::new()),
+        }
+    }
+}
+
+/// Records for each mutex which other mutexes were acquired while
+/// holding it. Acquiring mutexes in an order that contradicts an
+/// earlier one is reported, even if it didn't deadlock this time.
+/// Mutexes are removed once they are collected.
+struct LockOrder {
+    held: HashMap<usize, Vec<i64>>,
+    edges: HashMap<i64, HashSet<i64>>,
+}
+
+impl LockOrder {
+    fn new() -> LockOrder {
+        LockOrder {
+            held: HashMap::new(),
+            edges: HashMap::new(),
+        }
+    }
+
+    /// records that the thread acquires the mutex, returns the held mutex
+    /// and the acquired one if they were locked the other way round before.
+    fn acquire(&mut self, thread_id: usize, id: i64) -> Option<(i64, i64)> {
+        let held = self.held.entry(thread_id).or_insert_with(Vec::new);
+
+        // recursive locking doesn't introduce a new order
+        if held.contains(&id) {
+            return None;
+        }
+
+        // don't add the inverted order, otherwise the graph has a cycle
+        // and each following acquisition would be reported.
+        for &before in held.iter() {
+            if reachable(&self.edges, id, before) {
+                held.push(id);
+                return Some((before, id));
+            }
+        }
+
+        for &before in held.iter() {
+            self.edges
+                .entry(before)
+                .or_insert_with(HashSet::new)
+                .insert(id);
+        }
+
+        held.push(id);
+
+        None
+    }
+
+    fn release(&mut self, thread_id: usize, id: i64) {
+        if let Some(held) = self.held.get_mut(&thread_id) {
+            held.retain(|&elem| elem != id);
+        }
+    }
+
+    /// forgets a collected mutex, orders between other mutexes that were
+    /// only established through it are kept.
+    fn remove(&mut self, id: i64) {
+        let succs = self.edges.remove(&id).unwrap_or_default();
+
+        for targets in self.edges.values_mut() {
+            if targets.remove(&id) {
+                targets.extend(succs.iter().cloned());
+            }
+        }
+
+        for held in self.held.values_mut() {
+            held.retain(|&elem| elem != id);
+        }
+    }
+}
+
+fn reachable(edges: &HashMap<i64, HashSet<i64>>, from: i64, to: i64) -> bool {
+    let mut visited = HashSet::new();
+    let mut worklist = vec![from];
+
+    while let Some(id) = worklist.pop() {
+        if id == to {
+            return true;
+        }
+
+        if !visited.insert(id) {
+            continue;
+        }
+
+        if let Some(succs) = edges.get(&id) {
+            worklist.extend(succs.iter().cloned());
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_order() {
+        let mut order = LockOrder::new();
+
+        // thread 1: lock 1, then 2
+        assert!(order.acquire(1, 1).is_none());
+        assert!(order.acquire(1, 2).is_none());
+        order.release(1, 2);
+        order.release(1, 1);
+
+        // thread 2: lock 2, then 1
+        assert!(order.acquire(2, 2).is_none());
+        assert!(order.acquire(2, 1).is_some());
+    }
+
+    #[test]
+    fn test_lock_order_transitive() {
+        let mut order = LockOrder::new();
+
+        assert!(order.acquire(1, 1).is_none());
+        assert!(order.acquire(1, 2).is_none());
+        order.release(1, 1);
+        assert!(order.acquire(1, 3).is_none());
+        order.release(1, 3);
+        order.release(1, 2);
+
+        // 1 -> 2 -> 3, acquiring 1 while holding 3 is inconsistent
+        assert!(order.acquire(1, 3).is_none());
+        assert!(order.acquire(1, 1).is_some());
+    }
+
+    #[test]
+    fn test_lock_order_remove() {
+        let mut order = LockOrder::new();
+
+        assert!(order.acquire(1, 1).is_none());
+        assert!(order.acquire(1, 2).is_none());
+        order.release(1, 1);
+        assert!(order.acquire(1, 3).is_none());
+        order.release(1, 3);
+        order.release(1, 2);
+
+        // 1 -> 3 is still known after 2 was collected
+        order.remove(2);
+        assert!(!order.edges.contains_key(&2));
+        assert!(order.edges.values().all(|targets| !targets.contains(&2)));
+
+        assert!(order.acquire(1, 3).is_none());
+        assert!(order.acquire(1, 1).is_some());
+    }
+}
@@ -14,7 +14,7 @@ use crate::handle::{root, Handle};
 use crate::mem;
 use crate::size::InstanceSize;
 use crate::ty::BuiltinType;
-use crate::vm::{ClassDef, ClassDefId, FieldId, VM};
+use crate::vm::{get_vm, ClassDef, ClassDefId, FieldId, VM};
 use crate::vtable::VTable;
 
 #[repr(C)]
@@ -322,6 +322,10 @@ where
     F: FnMut(Slot),
 {
     for &offset in &cls.ref_fields {
+        f(Slot::field(object.offset(offset as usize)));
+    }
+
+    for &offset in &cls.tuple_ref_fields {
         f(Slot::at(object.offset(offset as usize)));
     }
 }
@@ -331,10 +335,11 @@ where
     F: FnMut(Slot),
 {
     let array = unsafe { &*object.to_ptr::<StrArray>() };
+    let ref_width = get_vm().ref_width() as usize;
 
     // walk through all objects in array
     let mut ptr = Address::from_ptr(array.data());
-    let mut limit = ptr.add_ptr(array.len() as usize);
+    let mut limit = ptr.offset(array.len() as usize * ref_width);
 
     // visit elements until `limit` reached
     if let Some(range) = range {
@@ -343,8 +348,8 @@ where
     }
 
     while ptr < limit {
-        f(Slot::at(ptr));
-        ptr = ptr.add_ptr(1);
+        f(Slot::field(ptr));
+        ptr = ptr.offset(ref_width);
     }
 }
 
@@ -359,7 +364,7 @@ fn visit_tuple_array_refs<F>(
 {
     let array = unsafe { &*object.to_ptr::<StrArray>() };
 
-    if cls.ref_fields.is_empty() {
+    if cls.tuple_ref_fields.is_empty() {
         return;
     }
 
@@ -382,7 +387,7 @@ fn visit_tuple_array_refs<F>(
 
     while ptr < limit {
         // each of those elements might have multiple references
-        for &offset in &cls.ref_fields {
+        for &offset in &cls.tuple_ref_fields {
             f(Slot::at(ptr.offset(offset as usize)));
         }
         ptr = ptr.offset(element_size as usize);
@@ -497,6 +502,10 @@ impl Str {
         self.length
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
     pub fn data(&self) -> *const u8 {
         &self.data as *const u8
     }
@@ -595,6 +604,23 @@ impl Str {
         handle
     }
 
+    // `count` copies of the string, the length needs to fit into an isize
+    pub fn repeat(vm: &VM, val: Handle<Str>, count: usize) -> Ref<Str> {
+        let part = val.len();
+        let len = part * count;
+        let mut handle = str_alloc_heap(vm, len);
+        handle.length = len;
+
+        for idx in 0..count {
+            unsafe {
+                let dest = handle.data().offset((idx * part) as isize) as *mut u8;
+                ptr::copy_nonoverlapping(val.data(), dest, part);
+            }
+        }
+
+        handle
+    }
+
     // duplicate string into a new object
     pub fn dup(&self, vm: &VM) -> Ref<Str> {
         let len = self.len();
@@ -840,12 +866,10 @@ pub fn write_ref(vm: &VM, obj: Ref<Obj>, cls_id: ClassDefId, fid: FieldId, value
     let cls_def = vm.class_defs.idx(cls_id);
     let cls_def = cls_def.read();
     let field = &cls_def.fields[fid.idx()];
-    let slot = obj.address().offset(field.offset as usize);
+    let slot = Slot::field(obj.address().offset(field.offset as usize));
     assert!(field.ty.reference_type());
 
-    unsafe {
-        *slot.to_mut_ptr::<Address>() = value.address();
-    }
+    slot.set(value.address());
 }
 
 pub fn write_int32(vm: &VM, obj: Ref<Obj>, cls_id: ClassDefId, fid: FieldId, value: i32) {
@@ -860,16 +884,83 @@ pub fn write_int32(vm: &VM, obj: Ref<Obj>, cls_id: ClassDefId, fid: FieldId, val
     }
 }
 
+pub fn write_int64(vm: &VM, obj: Ref<Obj>, cls_id: ClassDefId, fid: FieldId, value: i64) {
+    let cls_def = vm.class_defs.idx(cls_id);
+    let cls_def = cls_def.read();
+    let field = &cls_def.fields[fid.idx()];
+    let slot = obj.address().offset(field.offset as usize);
+    assert!(field.ty == BuiltinType::Int64);
+
+    unsafe {
+        *slot.to_mut_ptr::<i64>() = value;
+    }
+}
+
+pub fn read_ref(vm: &VM, obj: Ref<Obj>, cls_id: ClassDefId, fid: FieldId) -> Ref<Obj> {
+    let cls_def = vm.class_defs.idx(cls_id);
+    let cls_def = cls_def.read();
+    let field = &cls_def.fields[fid.idx()];
+    let slot = Slot::field(obj.address().offset(field.offset as usize));
+    assert!(field.ty.reference_type());
+
+    slot.get().to_usize().into()
+}
+
+pub fn read_int64(vm: &VM, obj: Ref<Obj>, cls_id: ClassDefId, fid: FieldId) -> i64 {
+    let cls_def = vm.class_defs.idx(cls_id);
+    let cls_def = cls_def.read();
+    let field = &cls_def.fields[fid.idx()];
+    let slot = obj.address().offset(field.offset as usize);
+    assert!(field.ty == BuiltinType::Int64);
+
+    unsafe { *slot.to_ptr::<i64>() }
+}
+
+// references are compressed depending on the configuration, hence fields
+// are accessed through the offset of the field after the header
+fn field_slot(obj: Address, idx: usize) -> Slot {
+    let offset = Header::size() as usize + idx * get_vm().ref_width() as usize;
+    Slot::field(obj.offset(offset))
+}
+
 pub struct Stacktrace {
     pub header: Header,
-    pub backtrace: Ref<Int32Array>,
-    pub elements: Ref<Obj>,
+}
+
+impl Stacktrace {
+    fn address(&self) -> Address {
+        Address::from_ptr(self as *const _)
+    }
+
+    pub fn backtrace(&self) -> Ref<Int32Array> {
+        field_slot(self.address(), 0).get().to_usize().into()
+    }
+
+    pub fn set_backtrace(&mut self, backtrace: Ref<Int32Array>) {
+        field_slot(self.address(), 0).set(backtrace.address());
+    }
 }
 
 pub struct StacktraceElement {
     pub header: Header,
-    pub name: Ref<Str>,
-    pub line: i32,
+}
+
+impl StacktraceElement {
+    fn address(&self) -> Address {
+        Address::from_ptr(self as *const _)
+    }
+
+    pub fn set_name(&mut self, name: Ref<Str>) {
+        field_slot(self.address(), 0).set(name.address());
+    }
+
+    pub fn set_line(&mut self, line: i32) {
+        let slot = field_slot(self.address(), 1);
+
+        unsafe {
+            *slot.address().to_mut_ptr::<i32>() = line;
+        }
+    }
 }
 
 #[cfg(test)]
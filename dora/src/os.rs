@@ -2,5 +2,6 @@ pub use self::allocator::*;
 pub use self::page::*;
 
 pub mod allocator;
+pub mod library;
 pub mod page;
 pub mod perf;
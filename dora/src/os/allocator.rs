@@ -1,45 +1,164 @@
 use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use crate::gc::Address;
 use crate::mem;
 use crate::os::page_size;
 
+// with compressed references heap and perm space are reserved at low
+// addresses, this is the next address tried when reserving memory
+static LOW_MEMORY_TOP: AtomicUsize = AtomicUsize::new(1 << 30);
+
+// set while memory needs to be placed below COMPRESSED_REF_LIMIT,
+// cleared as soon as the OS doesn't place memory there
+static LOW_MEMORY: AtomicBool = AtomicBool::new(false);
+
+/// memory reserved or committed until `end_low_memory` is placed
+/// below COMPRESSED_REF_LIMIT as long as the OS allows it
+pub fn begin_low_memory(enabled: bool) {
+    LOW_MEMORY.store(enabled, Ordering::Relaxed);
+}
+
+/// returns true if all memory since `begin_low_memory` was
+/// placed below COMPRESSED_REF_LIMIT
+pub fn end_low_memory() -> bool {
+    LOW_MEMORY.swap(false, Ordering::Relaxed)
+}
+
+fn next_low_memory(size: usize) -> Option<Address> {
+    let start = mem::align_usize(LOW_MEMORY_TOP.load(Ordering::Relaxed), 1 << 16);
+
+    if start + size > mem::COMPRESSED_REF_LIMIT {
+        None
+    } else {
+        Some(start.into())
+    }
+}
+
+fn low_memory_used(ptr: Address, size: usize) {
+    LOW_MEMORY_TOP.fetch_max(ptr.offset(size).to_usize(), Ordering::Relaxed);
+}
+
+// kernels before 4.17 don't know this flag and treat the address as hint
+#[cfg(target_os = "linux")]
+const MAP_FIXED_NOREPLACE: libc::c_int = 0x10_0000;
+
+#[cfg(all(target_family = "unix", not(target_os = "linux")))]
+const MAP_FIXED_NOREPLACE: libc::c_int = 0;
+
 #[cfg(target_family = "unix")]
-fn reserve(size: usize) -> Address {
-    debug_assert!(mem::is_page_aligned(size));
+fn mmap(size: usize, prot: libc::c_int, flags: libc::c_int) -> Option<Address> {
+    if LOW_MEMORY.load(Ordering::Relaxed) {
+        if let Some(ptr) = mmap_low(size, prot, flags) {
+            return Some(ptr);
+        }
 
-    let ptr = unsafe {
-        libc::mmap(
-            ptr::null_mut(),
-            size,
-            libc::PROT_NONE,
-            libc::MAP_PRIVATE | libc::MAP_ANON | libc::MAP_NORESERVE,
-            -1,
-            0,
-        ) as *mut libc::c_void
-    };
+        // fall back to uncompressed references
+        LOW_MEMORY.store(false, Ordering::Relaxed);
+    }
+
+    let ptr = unsafe { libc::mmap(ptr::null_mut(), size, prot, flags, -1, 0) };
 
     if ptr == libc::MAP_FAILED {
-        panic!("reserving memory with mmap() failed");
+        None
+    } else {
+        Some(Address::from_ptr(ptr))
+    }
+}
+
+#[cfg(target_family = "unix")]
+fn mmap_low(size: usize, prot: libc::c_int, flags: libc::c_int) -> Option<Address> {
+    while let Some(start) = next_low_memory(size) {
+        let ptr = unsafe {
+            libc::mmap(
+                start.to_mut_ptr(),
+                size,
+                prot,
+                flags | MAP_FIXED_NOREPLACE,
+                -1,
+                0,
+            )
+        };
+
+        if ptr == libc::MAP_FAILED {
+            // address range already in use, try the next one
+            if std::io::Error::last_os_error().raw_os_error() == Some(libc::EEXIST) {
+                low_memory_used(start, size);
+                continue;
+            }
+
+            return None;
+        }
+
+        let ptr = Address::from_ptr(ptr);
+
+        if ptr.offset(size).to_usize() <= mem::COMPRESSED_REF_LIMIT {
+            low_memory_used(ptr, size);
+            return Some(ptr);
+        }
+
+        // address was only used as hint and memory placed above the limit
+        unsafe {
+            libc::munmap(ptr.to_mut_ptr(), size);
+        }
+
+        return None;
+    }
+
+    None
+}
+
+#[cfg(target_family = "windows")]
+fn virtual_alloc(size: usize, alloc_type: u32, prot: u32) -> Address {
+    use winapi::um::memoryapi::VirtualAlloc;
+
+    if LOW_MEMORY.load(Ordering::Relaxed) {
+        // VirtualAlloc fails when the address range is already in use
+        while let Some(start) = next_low_memory(size) {
+            let ptr = unsafe { VirtualAlloc(start.to_mut_ptr(), size, alloc_type, prot) };
+
+            if !ptr.is_null() {
+                let ptr = Address::from_ptr(ptr);
+                low_memory_used(ptr, size);
+                return ptr;
+            }
+
+            low_memory_used(start, size);
+        }
+
+        // fall back to uncompressed references
+        LOW_MEMORY.store(false, Ordering::Relaxed);
     }
 
+    let ptr = unsafe { VirtualAlloc(ptr::null_mut(), size, alloc_type, prot) };
     Address::from_ptr(ptr)
 }
 
+#[cfg(target_family = "unix")]
+fn reserve(size: usize) -> Address {
+    debug_assert!(mem::is_page_aligned(size));
+
+    let flags = libc::MAP_PRIVATE | libc::MAP_ANON | libc::MAP_NORESERVE;
+
+    match mmap(size, libc::PROT_NONE, flags) {
+        Some(ptr) => ptr,
+        None => panic!("reserving memory with mmap() failed"),
+    }
+}
+
 #[cfg(target_family = "windows")]
 fn reserve(size: usize) -> Address {
     debug_assert!(mem::is_page_aligned(size));
 
-    use winapi::um::memoryapi::VirtualAlloc;
     use winapi::um::winnt::{MEM_RESERVE, PAGE_NOACCESS};
 
-    let ptr = unsafe { VirtualAlloc(ptr::null_mut(), size, MEM_RESERVE, PAGE_NOACCESS) };
+    let ptr = virtual_alloc(size, MEM_RESERVE, PAGE_NOACCESS);
 
     if ptr.is_null() {
         panic!("VirtualAlloc failed");
     }
 
-    Address::from_ptr(ptr)
+    ptr
 }
 
 #[cfg(target_family = "unix")]
@@ -124,29 +243,16 @@ pub fn commit(size: usize, executable: bool) -> Address {
         prot |= libc::PROT_EXEC;
     }
 
-    let ptr = unsafe {
-        libc::mmap(
-            ptr::null_mut(),
-            size,
-            prot,
-            libc::MAP_PRIVATE | libc::MAP_ANON,
-            -1,
-            0,
-        )
-    };
-
-    if ptr == libc::MAP_FAILED {
-        panic!("committing memory with mmap() failed");
+    match mmap(size, prot, libc::MAP_PRIVATE | libc::MAP_ANON) {
+        Some(ptr) => ptr,
+        None => panic!("committing memory with mmap() failed"),
     }
-
-    Address::from_ptr(ptr)
 }
 
 #[cfg(target_family = "windows")]
 pub fn commit(size: usize, executable: bool) -> Address {
     debug_assert!(mem::is_page_aligned(size));
 
-    use winapi::um::memoryapi::VirtualAlloc;
     use winapi::um::winnt::{MEM_COMMIT, MEM_RESERVE, PAGE_EXECUTE_READWRITE, PAGE_READWRITE};
 
     let prot = if executable {
@@ -155,13 +261,13 @@ pub fn commit(size: usize, executable: bool) -> Address {
         PAGE_READWRITE
     };
 
-    let ptr = unsafe { VirtualAlloc(ptr::null_mut(), size, MEM_COMMIT | MEM_RESERVE, prot) };
+    let ptr = virtual_alloc(size, MEM_COMMIT | MEM_RESERVE, prot);
 
     if ptr.is_null() {
         panic!("VirtualAlloc failed");
     }
 
-    Address::from_ptr(ptr)
+    ptr
 }
 
 #[cfg(target_family = "unix")]
@@ -0,0 +1,40 @@
+#[cfg(unix)]
+use std::ffi::{CStr, CString};
+
+use crate::gc::Address;
+
+// libraries are never closed, symbols stay valid for the whole run
+#[cfg(unix)]
+pub fn open_library(name: &str) -> Result<Address, String> {
+    let name = CString::new(name).map_err(|_| "invalid library name".to_string())?;
+    let handle = unsafe { libc::dlopen(name.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+
+    if handle.is_null() {
+        let reason = unsafe { CStr::from_ptr(libc::dlerror()) };
+        return Err(reason.to_string_lossy().into_owned());
+    }
+
+    Ok(Address::from_ptr(handle))
+}
+
+#[cfg(unix)]
+pub fn find_symbol(library: Address, name: &str) -> Option<Address> {
+    let name = CString::new(name).ok()?;
+    let ptr = unsafe { libc::dlsym(library.to_mut_ptr(), name.as_ptr()) };
+
+    if ptr.is_null() {
+        None
+    } else {
+        Some(Address::from_ptr(ptr))
+    }
+}
+
+#[cfg(not(unix))]
+pub fn open_library(_: &str) -> Result<Address, String> {
+    Err("loading libraries is not supported on this platform".into())
+}
+
+#[cfg(not(unix))]
+pub fn find_symbol(_: Address, _: &str) -> Option<Address> {
+    None
+}
@@ -1,11 +1,11 @@
 use crate::error::msg::SemError;
 use crate::mem;
 use crate::sym::TypeSym::{
-    SymClass, SymClassTypeParam, SymEnum, SymFctTypeParam, SymStruct, SymTrait,
+    SymClass, SymClassTypeParam, SymEnum, SymFctTypeParam, SymStruct, SymTrait, SymTraitTypeParam,
 };
 use crate::ty::{BuiltinType, TypeList};
 use crate::typeck;
-use crate::vm::{ensure_tuple, ClassId, FileId, NodeMap, VM};
+use crate::vm::{ensure_tuple, ClassId, FileId, NodeMap, TraitId, VM};
 use dora_parser::ast::Type::{TypeBasic, TypeLambda, TypeSelf, TypeTuple};
 use dora_parser::ast::{
     Expr, ExprBlockType, Stmt, Type, TypeBasicType, TypeLambdaType, TypeTupleType,
@@ -16,6 +16,7 @@ mod clsdefck;
 mod constdefck;
 mod enumck;
 mod extensiondefck;
+pub(crate) mod externck;
 mod fctdefck;
 mod flowck;
 mod globaldef;
@@ -109,6 +110,10 @@ pub fn check<'ast>(vm: &mut VM<'ast>) {
     prelude::internal_functions(vm);
     prelude::known_methods(vm);
 
+    // load libraries and symbols of @extern functions
+    externck::check(vm);
+    return_on_error!(vm);
+
     // check types of expressions in functions
     typeck::check(vm);
     return_on_error!(vm);
@@ -185,30 +190,46 @@ fn internalck<'ast>(vm: &VM<'ast>) {
 fn init_global_addresses<'ast>(vm: &VM<'ast>) {
     let globals = vm.globals.lock();
     let mut size = 0;
+    let mut thread_local_size = 0;
     let mut offsets = Vec::with_capacity(globals.len());
 
     for glob in globals.iter() {
         let glob = glob.read();
 
-        let initialized = size;
-        size += BuiltinType::Bool.size(vm);
+        // thread-locals are laid out the same way but in a separate
+        // table that is allocated for each thread
+        let table_size = if glob.thread_local {
+            &mut thread_local_size
+        } else {
+            &mut size
+        };
+
+        let initialized = *table_size;
+        *table_size += BuiltinType::Bool.size(vm);
 
         let ty_size = glob.ty.size(vm);
         let ty_align = glob.ty.align(vm);
 
-        let value = mem::align_i32(size, ty_align);
+        let value = mem::align_i32(*table_size, ty_align);
         offsets.push((initialized, value));
-        size = value + ty_size;
+        *table_size = value + ty_size;
     }
 
     let ptr = vm.gc.alloc_perm(size as usize);
+    vm.threads
+        .set_thread_locals_size(thread_local_size as usize);
 
     for (ind, glob) in globals.iter().enumerate() {
         let mut glob = glob.write();
         let (initialized, value) = offsets[ind];
 
-        glob.address_init = ptr.offset(initialized as usize);
-        glob.address_value = ptr.offset(value as usize);
+        if glob.thread_local {
+            glob.thread_local_init = initialized;
+            glob.thread_local_value = value;
+        } else {
+            glob.address_init = ptr.offset(initialized as usize);
+            glob.address_value = ptr.offset(value as usize);
+        }
     }
 }
 
@@ -226,7 +247,10 @@ fn read_type_basic<'ast>(
     file: FileId,
     basic: &'ast TypeBasicType,
 ) -> Option<BuiltinType> {
-    let sym = vm.sym.lock().get_type(basic.name);
+    let sym = vm
+        .sym
+        .lock()
+        .lookup_type(basic.name, vm.is_stdlib_file(file));
 
     if sym.is_none() {
         let name = vm.interner.str(basic.name).to_string();
@@ -241,14 +265,7 @@ fn read_type_basic<'ast>(
     match sym {
         SymClass(cls_id) => read_type_class(vm, file, basic, cls_id),
 
-        SymTrait(trait_id) => {
-            if basic.params.len() > 0 {
-                let msg = SemError::NoTypeParamsExpected;
-                vm.diag.lock().report(file, basic.pos, msg);
-            }
-
-            Some(BuiltinType::Trait(trait_id))
-        }
+        SymTrait(trait_id) => read_type_trait(vm, file, basic, trait_id),
 
         SymStruct(struct_id) => {
             if basic.params.len() > 0 {
@@ -279,6 +296,15 @@ fn read_type_basic<'ast>(
             Some(BuiltinType::ClassTypeParam(cls_id, type_param_id))
         }
 
+        SymTraitTypeParam(trait_id, type_param_id) => {
+            if basic.params.len() > 0 {
+                let msg = SemError::NoTypeParamsExpected;
+                vm.diag.lock().report(file, basic.pos, msg);
+            }
+
+            Some(BuiltinType::TraitTypeParam(trait_id, type_param_id))
+        }
+
         SymFctTypeParam(fct_id, type_param_id) => {
             if basic.params.len() > 0 {
                 let msg = SemError::NoTypeParamsExpected;
@@ -343,10 +369,45 @@ fn read_type_class<'ast>(
     }
 
     let list = TypeList::with(type_params);
+
+    if !typeparamck::check_atomic_ref(vm, file, basic.pos, cls.id, &list) {
+        return None;
+    }
+
     let list_id = vm.lists.lock().insert(list);
     Some(BuiltinType::Class(cls.id, list_id))
 }
 
+fn read_type_trait<'ast>(
+    vm: &VM<'ast>,
+    file: FileId,
+    basic: &'ast TypeBasicType,
+    trait_id: TraitId,
+) -> Option<BuiltinType> {
+    let mut type_params = Vec::new();
+
+    for param in &basic.params {
+        let param = read_type(vm, file, param);
+
+        if let Some(param) = param {
+            type_params.push(param);
+        } else {
+            return None;
+        }
+    }
+
+    let xtrait = vm.traits[trait_id].read();
+
+    if xtrait.type_params.len() != type_params.len() {
+        let msg = SemError::WrongNumberTypeParams(xtrait.type_params.len(), type_params.len());
+        vm.diag.lock().report(file, basic.pos, msg);
+        return None;
+    }
+
+    let list_id = vm.lists.lock().insert(TypeList::with(type_params));
+    Some(BuiltinType::Trait(trait_id, list_id))
+}
+
 fn read_type_tuple<'ast>(
     vm: &VM<'ast>,
     file: FileId,
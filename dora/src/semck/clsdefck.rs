@@ -83,8 +83,13 @@ impl<'x, 'ast> ClsDefCheck<'x, 'ast> {
                     let ty = semck::read_type(self.vm, cls.file, bound);
 
                     match ty {
-                        Some(BuiltinType::Trait(trait_id)) => {
-                            if !cls.type_params[type_param_id].trait_bounds.insert(trait_id) {
+                        Some(BuiltinType::Trait(trait_id, list_id)) => {
+                            if self.vm.lists.lock().get(list_id).len() > 0 {
+                                // bounds on generic traits aren't supported yet
+                                let msg = SemError::Unimplemented;
+                                self.vm.diag.lock().report(cls.file, bound.pos(), msg);
+                            } else if !cls.type_params[type_param_id].trait_bounds.insert(trait_id)
+                            {
                                 let msg = SemError::DuplicateTraitBound;
                                 self.vm.diag.lock().report(cls.file, type_param.pos, msg);
                             }
@@ -117,7 +122,8 @@ impl<'x, 'ast> ClsDefCheck<'x, 'ast> {
 
     fn check_parent_class(&mut self, parent_class: &'ast ast::ParentClass) {
         let name = self.vm.interner.str(parent_class.name).to_string();
-        let sym = self.vm.sym.lock().get_type(parent_class.name);
+        let in_stdlib = self.vm.is_stdlib_file(self.file_id.into());
+        let sym = self.vm.sym.lock().lookup_type(parent_class.name, in_stdlib);
 
         match sym {
             Some(TypeSym::SymClass(cls_id)) => {
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use crate::error::msg::SemError;
+use crate::gc::Address;
+use crate::os::library;
+use crate::ty::BuiltinType;
+use crate::vm::{Fct, FctKind, VM};
+
+// resolves functions declared with @extern("library", "symbol"), the
+// library is loaded with dlopen() and the symbol looked up with dlsym().
+pub fn check<'ast>(vm: &VM<'ast>) {
+    let mut libraries: HashMap<String, Result<Address, String>> = HashMap::new();
+
+    for fct in vm.fcts.iter() {
+        let mut fct = fct.write();
+        let ast = fct.ast;
+
+        let external = match ast.external {
+            Some(ref external) => external,
+            None => continue,
+        };
+
+        if ast.block.is_some() {
+            report(vm, &fct, SemError::ExternWithBody);
+            continue;
+        }
+
+        if !fct.type_params.is_empty() {
+            report(vm, &fct, SemError::ExternGeneric);
+            continue;
+        }
+
+        let mut supported = true;
+
+        for &ty in &fct.param_types {
+            if !is_supported_param(vm, ty) {
+                report(vm, &fct, SemError::ExternTypeNotSupported(ty.name(vm)));
+                supported = false;
+            }
+        }
+
+        if !fct.return_type.is_unit() && !is_primitive(fct.return_type) {
+            let name = fct.return_type.name(vm);
+            report(vm, &fct, SemError::ExternTypeNotSupported(name));
+            supported = false;
+        }
+
+        if !supported {
+            continue;
+        }
+
+        let handle = libraries
+            .entry(external.library.clone())
+            .or_insert_with(|| library::open_library(&external.library));
+
+        let handle = match handle {
+            Ok(handle) => *handle,
+            Err(reason) => {
+                let msg = SemError::ExternLibraryNotFound(external.library.clone(), reason.clone());
+                report(vm, &fct, msg);
+                continue;
+            }
+        };
+
+        let symbol = match external.symbol {
+            Some(ref symbol) => symbol.clone(),
+            None => vm.interner.str(fct.name).to_string(),
+        };
+
+        if let Some(ptr) = library::find_symbol(handle, &symbol) {
+            fct.kind = FctKind::Native(ptr);
+        } else {
+            let msg = SemError::ExternSymbolNotFound(external.library.clone(), symbol);
+            report(vm, &fct, msg);
+        }
+    }
+}
+
+fn report(vm: &VM, fct: &Fct, msg: SemError) {
+    vm.diag.lock().report(fct.file, fct.pos, msg);
+}
+
+fn is_primitive(ty: BuiltinType) -> bool {
+    match ty {
+        BuiltinType::Bool
+        | BuiltinType::UInt8
+        | BuiltinType::Char
+        | BuiltinType::Int32
+        | BuiltinType::Int64
+        | BuiltinType::Float32
+        | BuiltinType::Float64 => true,
+        _ => false,
+    }
+}
+
+// strings are passed as NUL-terminated copies, byte arrays
+// as a pointer to their first element
+fn is_supported_param(vm: &VM, ty: BuiltinType) -> bool {
+    is_primitive(ty) || is_string(vm, ty) || is_byte_array(vm, ty)
+}
+
+pub fn is_string(vm: &VM, ty: BuiltinType) -> bool {
+    ty.cls_id(vm) == Some(vm.vips.string_class)
+}
+
+pub fn is_byte_array(vm: &VM, ty: BuiltinType) -> bool {
+    ty.cls_id(vm) == Some(vm.vips.array_class) && ty.type_params(vm)[0] == BuiltinType::UInt8
+}
+
+// tests load libc and libm by their Linux names
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use crate::error::msg::SemError;
+    use crate::semck::tests::{err, ok, pos};
+
+    #[test]
+    fn test_extern_fct() {
+        ok("@extern(\"libm.so.6\") fun cos(x: Float64) -> Float64;");
+        ok("@extern(\"libc.so.6\", \"strlen\") fun length(x: String) -> Int64;");
+        ok("@extern(\"libc.so.6\", \"memset\") fun fill(x: Array[UInt8], v: Int32, n: Int64);");
+    }
+
+    #[test]
+    fn test_extern_fct_errors() {
+        err(
+            "@extern(\"libm.so.6\") fun cos(x: Float64) -> Float64 { 1.0 }",
+            pos(1, 22),
+            SemError::ExternWithBody,
+        );
+        err(
+            "@extern(\"libm.so.6\") fun cos[T](x: T) -> Float64;",
+            pos(1, 22),
+            SemError::ExternGeneric,
+        );
+        err(
+            "@extern(\"libm.so.6\") fun cos(x: Array[Int32]);",
+            pos(1, 22),
+            SemError::ExternTypeNotSupported("Array[Int32]".into()),
+        );
+        err(
+            "@extern(\"libm.so.6\") fun cos() -> String;",
+            pos(1, 22),
+            SemError::ExternTypeNotSupported("String".into()),
+        );
+        err(
+            "@extern(\"libm.so.6\", \"unknown_symbol\") fun cos();",
+            pos(1, 40),
+            SemError::ExternSymbolNotFound("libm.so.6".into(), "unknown_symbol".into()),
+        );
+    }
+}
@@ -9,7 +9,7 @@ use dora_parser::ast::visit::*;
 use dora_parser::ast::*;
 
 pub fn check<'a, 'ast>(vm: &VM<'ast>) {
-    debug_assert!(vm.sym.lock().levels() == 1);
+    debug_assert!(vm.sym.lock().levels() == 2);
 
     for fct in vm.fcts.iter() {
         let mut fct = fct.write();
@@ -47,6 +47,13 @@ pub fn check<'a, 'ast>(vm: &VM<'ast>) {
                 let cls = vm.classes.idx(ximpl.cls_id(vm));
                 let cls = cls.read();
 
+                if ximpl.is_generic(vm) {
+                    for (type_param_id, param) in cls.type_params.iter().enumerate() {
+                        let sym = TypeSym::SymClassTypeParam(cls.id, type_param_id.into());
+                        vm.sym.lock().insert_type(param.name, sym);
+                    }
+                }
+
                 if fct.has_self() {
                     fct.param_types.push(cls.ty);
                 }
@@ -62,7 +69,14 @@ pub fn check<'a, 'ast>(vm: &VM<'ast>) {
 
             FctParent::Module(_) => {}
 
-            FctParent::Trait(_) => {
+            FctParent::Trait(trait_id) => {
+                let xtrait = vm.traits[trait_id].read();
+
+                for (type_param_id, param) in xtrait.type_params.iter().enumerate() {
+                    let sym = TypeSym::SymTraitTypeParam(trait_id, type_param_id.into());
+                    vm.sym.lock().insert_type(param.name, sym);
+                }
+
                 if fct.has_self() {
                     fct.param_types.push(BuiltinType::This);
                 }
@@ -89,8 +103,15 @@ pub fn check<'a, 'ast>(vm: &VM<'ast>) {
                         let ty = semck::read_type(vm, fct.file, bound);
 
                         match ty {
-                            Some(BuiltinType::Trait(trait_id)) => {
-                                if !fct.type_params[type_param_id].trait_bounds.insert(trait_id) {
+                            Some(BuiltinType::Trait(trait_id, list_id)) => {
+                                if vm.lists.lock().get(list_id).len() > 0 {
+                                    // bounds on generic traits aren't supported yet
+                                    let msg = SemError::Unimplemented;
+                                    vm.diag.lock().report(fct.file, bound.pos(), msg);
+                                } else if !fct.type_params[type_param_id]
+                                    .trait_bounds
+                                    .insert(trait_id)
+                                {
                                     let msg = SemError::DuplicateTraitBound;
                                     vm.diag.lock().report(fct.file, type_param.pos, msg);
                                 }
@@ -216,7 +237,7 @@ pub fn check<'a, 'ast>(vm: &VM<'ast>) {
         vm.sym.lock().pop_level();
     }
 
-    debug_assert!(vm.sym.lock().levels() == 1);
+    debug_assert!(vm.sym.lock().levels() == 2);
 }
 
 fn check_abstract<'ast>(vm: &VM<'ast>, fct: &Fct<'ast>) {
@@ -82,7 +82,13 @@ mod tests {
     fn flowck_break() {
         ok("fun a() { while true { break; } }");
         ok("fun a() { while true { if true { break; } } }");
+        ok("fun a(m: Mutex) { while true { synchronized m { break; } } }");
         err("fun a() { break; }", pos(1, 11), SemError::OutsideLoop);
+        err(
+            "fun a(m: Mutex) { synchronized m { break; } }",
+            pos(1, 36),
+            SemError::OutsideLoop,
+        );
         err(
             "fun a() { while true { } break; }",
             pos(1, 26),
@@ -50,6 +50,11 @@ pub fn check<'ast>(
     };
 
     gdef.visit_ast(ast);
+
+    // also needed when there are no files besides the stdlib
+    if gdef.vm.sym.lock().levels() == 1 {
+        gdef.vm.sym.lock().push_level();
+    }
 }
 
 struct GlobalDef<'x, 'ast: 'x> {
@@ -68,26 +73,38 @@ struct GlobalDef<'x, 'ast: 'x> {
 
 impl<'x, 'ast> Visitor<'ast> for GlobalDef<'x, 'ast> {
     fn visit_file(&mut self, f: &'ast File) {
+        // globals of the program are defined on their own level
+        if self.file_id as usize == self.vm.stdlib_files {
+            self.vm.sym.lock().push_level();
+        }
+
         walk_file(self, f);
         self.file_id += 1;
     }
 
     fn visit_trait(&mut self, t: &'ast Trait) {
         let id: TraitId = (self.vm.traits.len() as u32).into();
-        let xtrait = TraitData {
+        let mut xtrait = TraitData {
             id,
             file: self.file_id.into(),
             pos: t.pos,
             name: t.name,
+            type_params: Vec::new(),
             methods: Vec::new(),
         };
 
+        if let Some(ref type_params) = t.type_params {
+            for param in type_params {
+                xtrait.type_params.push(TypeParam::new(param.name));
+            }
+        }
+
         self.vm.traits.push(RwLock::new(xtrait));
 
         self.map_trait_defs.insert(t.id, id);
 
         let sym = SymTrait(id);
-        if let Some(sym) = self.vm.sym.lock().insert_type(t.name, sym) {
+        if let Some(sym) = self.insert_type(t.name, sym) {
             report_type_shadow(self.vm, t.name, self.file_id.into(), t.pos, sym);
         }
     }
@@ -106,6 +123,9 @@ impl<'x, 'ast> Visitor<'ast> for GlobalDef<'x, 'ast> {
                 initializer: None,
                 address_init: Address::null(),
                 address_value: Address::null(),
+                thread_local: g.thread_local,
+                thread_local_init: 0,
+                thread_local_value: 0,
             };
 
             globals.push(Arc::new(RwLock::new(global)));
@@ -116,7 +136,7 @@ impl<'x, 'ast> Visitor<'ast> for GlobalDef<'x, 'ast> {
         self.map_global_defs.insert(g.id, id);
 
         let sym = SymGlobal(id);
-        if let Some(sym) = self.vm.sym.lock().insert_term(g.name, sym) {
+        if let Some(sym) = self.insert_term(g.name, sym) {
             report_term_shadow(self.vm, g.name, self.file_id.into(), g.pos, sym);
         }
     }
@@ -129,6 +149,7 @@ impl<'x, 'ast> Visitor<'ast> for GlobalDef<'x, 'ast> {
                 file: self.file_id.into(),
                 pos: i.pos,
                 trait_id: None,
+                trait_ty: BuiltinType::Error,
                 class_ty: BuiltinType::Error,
                 methods: Vec::new(),
             };
@@ -182,8 +203,13 @@ impl<'x, 'ast> Visitor<'ast> for GlobalDef<'x, 'ast> {
 
         self.map_module_defs.insert(m.id, id);
 
+        if let Some(sym) = self.stdlib_term(m.name) {
+            report_term_shadow(self.vm, m.name, self.file_id.into(), m.pos, sym);
+            return;
+        }
+
         let mut sym_table = self.vm.sym.lock();
-        match sym_table.get_term(m.name) {
+        match sym_table.get_local_term(m.name) {
             None => {
                 sym_table.insert_term(m.name, SymModule(id));
             }
@@ -216,7 +242,7 @@ impl<'x, 'ast> Visitor<'ast> for GlobalDef<'x, 'ast> {
         self.map_const_defs.insert(c.id, id);
 
         let sym = SymConst(id);
-        if let Some(sym) = self.vm.sym.lock().insert_term(c.name, sym) {
+        if let Some(sym) = self.insert_term(c.name, sym) {
             report_term_shadow(self.vm, c.name, self.file_id.into(), c.pos, sym);
         }
     }
@@ -269,6 +295,7 @@ impl<'x, 'ast> Visitor<'ast> for GlobalDef<'x, 'ast> {
 
         self.map_cls_defs.insert(c.id, id);
 
+        // classes of the program are allowed to replace classes of the stdlib
         let sym = SymClass(id);
         if let Some(sym) = self.vm.sym.lock().insert_type(c.name, sym) {
             report_type_shadow(self.vm, c.name, self.file_id.into(), c.pos, sym);
@@ -276,7 +303,7 @@ impl<'x, 'ast> Visitor<'ast> for GlobalDef<'x, 'ast> {
         }
 
         let mut sym_table = self.vm.sym.lock();
-        match sym_table.get_term(c.name) {
+        match sym_table.get_local_term(c.name) {
             None => {
                 sym_table.insert_term(c.name, SymClassConstructor(id));
             }
@@ -308,13 +335,18 @@ impl<'x, 'ast> Visitor<'ast> for GlobalDef<'x, 'ast> {
         self.map_struct_defs.insert(s.id, id);
 
         let sym = SymStruct(id);
-        if let Some(sym) = self.vm.sym.lock().insert_type(s.name, sym) {
+        if let Some(sym) = self.insert_type(s.name, sym) {
             report_type_shadow(self.vm, s.name, self.file_id.into(), s.pos, sym);
             return;
         }
 
+        if let Some(sym) = self.stdlib_term(s.name) {
+            report_term_shadow(self.vm, s.name, self.file_id.into(), s.pos, sym);
+            return;
+        }
+
         let mut sym_table = self.vm.sym.lock();
-        match sym_table.get_term(s.name) {
+        match sym_table.get_local_term(s.name) {
             None => {
                 sym_table.insert_term(s.name, SymStructConstructor(id));
             }
@@ -388,12 +420,42 @@ impl<'x, 'ast> Visitor<'ast> for GlobalDef<'x, 'ast> {
         self.map_enum_defs.insert(e.id, id);
 
         let sym = SymEnum(id);
-        if let Some(sym) = self.vm.sym.lock().insert_type(e.name, sym) {
+        if let Some(sym) = self.insert_type(e.name, sym) {
             report_type_shadow(self.vm, e.name, self.file_id.into(), e.pos, sym);
         }
     }
 }
 
+impl<'x, 'ast> GlobalDef<'x, 'ast> {
+    // apart from classes, globals of the program can't reuse
+    // names of the stdlib.
+    fn stdlib_type(&self, name: Name) -> Option<TypeSym> {
+        if self.vm.is_stdlib_file(self.file_id.into()) {
+            return None;
+        }
+
+        self.vm.sym.lock().lookup_type(name, true)
+    }
+
+    fn stdlib_term(&self, name: Name) -> Option<TermSym> {
+        if self.vm.is_stdlib_file(self.file_id.into()) {
+            return None;
+        }
+
+        self.vm.sym.lock().lookup_term(name, true)
+    }
+
+    fn insert_type(&mut self, name: Name, sym: TypeSym) -> Option<TypeSym> {
+        self.stdlib_type(name)
+            .or_else(|| self.vm.sym.lock().insert_type(name, sym))
+    }
+
+    fn insert_term(&mut self, name: Name, sym: TermSym) -> Option<TermSym> {
+        self.stdlib_term(name)
+            .or_else(|| self.vm.sym.lock().insert_term(name, sym))
+    }
+}
+
 pub fn report_type_shadow(vm: &VM, name: Name, file: FileId, pos: Position, sym: TypeSym) {
     let name = vm.interner.str(name).to_string();
 
@@ -468,6 +530,13 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_class_shadows_stdlib_class() {
+        ok("class Channel(let value: Int32) fun f() -> Int32 { Channel(1).value }");
+        ok("class ThreadPool fun f() { ThreadPool(); Channel::unbounded[Int32](); }");
+        ok("class Future[T](let value: T) fun f() -> Int32 { Future[Int32](1).value }");
+    }
+
     #[test]
     fn test_struct() {
         ok("struct Foo {}");
@@ -1,8 +1,9 @@
 use std::collections::HashSet;
 
 use crate::error::msg::SemError;
+use crate::semck::specialize::replace_trait_type_param;
 use crate::ty::BuiltinType;
-use crate::vm::{FileId, VM};
+use crate::vm::{find_impl_in_class, FileId, VM};
 
 use dora_parser::lexer::position::Position;
 
@@ -13,6 +14,7 @@ pub fn check<'ast>(vm: &mut VM<'ast>) {
         let cls = vm.classes.idx(ximpl.cls_id(vm));
         let cls = cls.read();
         let cls = cls.ty;
+        let trait_type_params = ximpl.trait_ty.type_params(vm);
 
         let all: HashSet<_> = xtrait.methods.iter().cloned().collect();
         let mut defined = HashSet::new();
@@ -26,6 +28,7 @@ pub fn check<'ast>(vm: &mut VM<'ast>) {
                 method.is_static,
                 method.name,
                 Some(cls),
+                &trait_type_params,
                 method.params_without_self(),
             ) {
                 method.impl_for = Some(fid);
@@ -34,16 +37,15 @@ pub fn check<'ast>(vm: &mut VM<'ast>) {
                 let trait_method = vm.fcts.idx(fid);
                 let trait_method = trait_method.read();
 
-                let return_type_valid = method.return_type
-                    == if trait_method.return_type == BuiltinType::This {
-                        cls
-                    } else {
-                        trait_method.return_type
-                    };
+                let trait_return_type =
+                    replace_trait_type_param(vm, trait_method.return_type, &trait_type_params, cls);
+
+                let return_type_valid = method.return_type == trait_return_type
+                    || returns_trait_impl(vm, method.return_type, trait_return_type);
 
                 if !return_type_valid {
                     let impl_return_type = method.return_type.name(vm);
-                    let trait_return_type = trait_method.return_type.name(vm);
+                    let trait_return_type = trait_return_type.name(vm);
 
                     let msg = SemError::ReturnTypeMismatch(impl_return_type, trait_return_type);
                     vm.diag.lock().report(ximpl.file, method.pos, msg);
@@ -90,6 +92,28 @@ pub fn check<'ast>(vm: &mut VM<'ast>) {
     }
 }
 
+// methods returning a trait object may return any class implementing it,
+// e.g. makeIterator() -> Iterator[T] may return VecIter[T]
+fn returns_trait_impl(vm: &VM, return_type: BuiltinType, trait_ty: BuiltinType) -> bool {
+    let trait_id = match trait_ty {
+        BuiltinType::Trait(trait_id, _) => trait_id,
+        _ => return false,
+    };
+
+    if return_type.cls_id(vm).is_none() {
+        return false;
+    }
+
+    match find_impl_in_class(vm, return_type, trait_id) {
+        Some((class_ty, impl_id)) => {
+            let ximpl = vm.impls[impl_id].read();
+            ximpl.trait_ty_for(vm, class_ty) == trait_ty
+        }
+
+        None => false,
+    }
+}
+
 fn report(vm: &VM, file: FileId, pos: Position, msg: SemError) {
     vm.diag.lock().report(file, pos, msg);
 }
@@ -186,4 +210,56 @@ mod tests {
             SemError::ReturnTypeMismatch("Int32".into(), "Bool".into()),
         );
     }
+
+    #[test]
+    fn method_with_trait_type_params() {
+        ok("trait X[T] { fun m(value: T) -> T; }
+            class CX
+            impl X[String] for CX { fun m(value: String) -> String = value; }");
+
+        err(
+            "trait X[T] { fun m() -> T; }
+            class CX
+            impl X[String] for CX { fun m() -> Int32 = 0; }",
+            pos(3, 37),
+            SemError::ReturnTypeMismatch("Int32".into(), "String".into()),
+        );
+
+        errors(
+            "trait X[T] { fun m(value: T); }
+            class CX
+            impl X[String] for CX { fun m(value: Int32) {} }",
+            &[
+                (
+                    pos(3, 37),
+                    SemError::MethodNotInTrait("X".into(), "m".into(), vec!["Int32".into()]),
+                ),
+                (
+                    pos(3, 13),
+                    SemError::MethodMissingFromTrait("X".into(), "m".into(), vec!["T".into()]),
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn method_returning_trait_impl() {
+        ok("trait Iter[T] { fun next() -> T; }
+            trait Make[T] { fun make() -> Iter[T]; }
+            class A
+            class AIter
+            impl Iter[Int32] for AIter { fun next() -> Int32 = 0; }
+            impl Make[Int32] for A { fun make() -> AIter = AIter(); }");
+
+        err(
+            "trait Iter[T] { fun next() -> T; }
+            trait Make[T] { fun make() -> Iter[T]; }
+            class A
+            class AIter
+            impl Iter[Int32] for AIter { fun next() -> Int32 = 0; }
+            impl Make[Int64] for A { fun make() -> AIter = AIter(); }",
+            pos(6, 38),
+            SemError::ReturnTypeMismatch("AIter".into(), "Iter[Int64]".into()),
+        );
+    }
 }
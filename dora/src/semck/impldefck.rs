@@ -5,7 +5,7 @@ use crate::error::msg::SemError;
 use crate::semck;
 use crate::sym::TypeSym;
 use crate::ty::BuiltinType;
-use crate::vm::{Fct, FctId, FctKind, FctParent, FctSrc, FileId, ImplId, NodeMap, VM};
+use crate::vm::{ClassId, Fct, FctId, FctKind, FctParent, FctSrc, FileId, ImplId, NodeMap, VM};
 
 use dora_parser::ast::visit::{self, Visitor};
 use dora_parser::ast::{self, Ast};
@@ -44,30 +44,50 @@ impl<'x, 'ast> ImplCheck<'x, 'ast> {
 
         let mut ximpl = self.vm.impls[self.impl_id.unwrap()].write();
 
-        if i.type_params.is_some() {
-            // We don't support type parameters for impl-blocks yet.
-            report(self.vm, ximpl.file, i.pos, SemError::Unimplemented);
-            self.impl_id = None;
-            return;
+        self.vm.sym.lock().push_level();
+
+        if let Some(ref type_params) = i.type_params {
+            if let Some(cls_id) = self.impl_class_for_type_params(i, type_params) {
+                for (type_param_id, type_param) in type_params.iter().enumerate() {
+                    let sym = TypeSym::SymClassTypeParam(cls_id, type_param_id.into());
+                    self.vm.sym.lock().insert_type(type_param.name, sym);
+                }
+            } else {
+                // type params of impl-blocks are only supported as the type
+                // params of the class: impl[T] Foo[T] for Bar[T]
+                report(self.vm, ximpl.file, i.pos, SemError::Unimplemented);
+                self.vm.sym.lock().pop_level();
+                self.impl_id = None;
+                return;
+            }
         }
 
         if let Some(ref trait_type) = i.trait_type {
-            if let Some(trait_name) = trait_type.to_basic_without_type_params() {
-                if let Some(TypeSym::SymTrait(trait_id)) = self.vm.sym.lock().get_type(trait_name) {
+            if let Some(trait_name) = trait_type.to_basic().map(|basic| basic.name) {
+                let in_stdlib = self.vm.is_stdlib_file(ximpl.file);
+                let sym = self.vm.sym.lock().lookup_type(trait_name, in_stdlib);
+
+                if let Some(TypeSym::SymTrait(trait_id)) = sym {
                     ximpl.trait_id = Some(trait_id);
                 } else {
                     let name = self.vm.interner.str(trait_name).to_string();
                     report(self.vm, ximpl.file, i.pos, SemError::ExpectedTrait(name));
                 }
             } else {
-                // We don't support type parameters for traits yet.
                 report(self.vm, ximpl.file, i.pos, SemError::Unimplemented);
-                self.impl_id = None;
-                return;
+            }
+
+            if ximpl.trait_id.is_some() {
+                if let Some(trait_ty) = semck::read_type(self.vm, self.file_id.into(), trait_type) {
+                    ximpl.trait_ty = trait_ty;
+                } else {
+                    ximpl.trait_id = None;
+                }
             }
         } else {
             // We don't support extension blocks yet.
             report(self.vm, ximpl.file, i.pos, SemError::Unimplemented);
+            self.vm.sym.lock().pop_level();
             self.impl_id = None;
             return;
         }
@@ -85,6 +105,8 @@ impl<'x, 'ast> ImplCheck<'x, 'ast> {
             }
         }
 
+        self.vm.sym.lock().pop_level();
+
         if ximpl.trait_id.is_some() && !ximpl.class_ty.is_error() {
             let cls = self.vm.classes.idx(ximpl.cls_id(self.vm));
             let mut cls = cls.write();
@@ -94,6 +116,35 @@ impl<'x, 'ast> ImplCheck<'x, 'ast> {
 
         self.impl_id = None;
     }
+
+    fn impl_class_for_type_params(
+        &self,
+        i: &'ast ast::Impl,
+        type_params: &'ast [ast::TypeParam],
+    ) -> Option<ClassId> {
+        let basic = i.class_type.to_basic()?;
+        let in_stdlib = self.vm.is_stdlib_file(self.file_id.into());
+        let cls_id = self
+            .vm
+            .sym
+            .lock()
+            .lookup_type(basic.name, in_stdlib)?
+            .to_class()?;
+
+        if basic.params.len() != type_params.len() {
+            return None;
+        }
+
+        for (param, type_param) in basic.params.iter().zip(type_params) {
+            if param.to_basic_without_type_params() != Some(type_param.name)
+                || !type_param.bounds.is_empty()
+            {
+                return None;
+            }
+        }
+
+        Some(cls_id)
+    }
 }
 
 impl<'x, 'ast> Visitor<'ast> for ImplCheck<'x, 'ast> {
@@ -248,4 +299,32 @@ mod tests {
     fn impl_class_type_params() {
         ok("trait MyTrait {} class Foo[T] impl MyTrait for Foo[String] {}");
     }
+
+    #[test]
+    fn impl_generic_trait() {
+        ok("trait Foo[T] { fun get() -> T; }
+            class A
+            impl Foo[Int32] for A { fun get() -> Int32 = 0; }");
+        ok("trait Foo[T] { fun get() -> T; }
+            class A[T](let value: T)
+            impl[T] Foo[T] for A[T] { fun get() -> T = self.value; }");
+        ok("trait Foo[T] { fun get() -> T; }
+            class A[K, V](let key: K, let value: V)
+            impl[K, V] Foo[(K, V)] for A[K, V] { fun get() -> (K, V) = (self.key, self.value); }");
+    }
+
+    #[test]
+    fn impl_type_params_not_class_type_params() {
+        err(
+            "trait Foo {} class A[T] impl[T] Foo for A[Int32] {}",
+            pos(1, 25),
+            SemError::Unimplemented,
+        );
+
+        err(
+            "trait Foo {} class A[X, Y] impl[X, Y] Foo for A[Y, X] {}",
+            pos(1, 28),
+            SemError::Unimplemented,
+        );
+    }
 }
@@ -66,7 +66,8 @@ impl<'x, 'ast> ModuleCheck<'x, 'ast> {
 
     fn check_parent_class(&mut self, parent_class: &'ast ast::ParentClass) {
         let name = self.vm.interner.str(parent_class.name).to_string();
-        let sym = self.vm.sym.lock().get_type(parent_class.name);
+        let in_stdlib = self.vm.is_stdlib_file(self.file_id.into());
+        let sym = self.vm.sym.lock().lookup_type(parent_class.name, in_stdlib);
 
         match sym {
             Some(TypeSym::SymClass(cls_id)) => {
@@ -67,6 +67,23 @@ impl<'a, 'ast> NameCheck<'a, 'ast> {
             }
         }
 
+        if let FctParent::Impl(impl_id) = self.fct.parent {
+            let ximpl = self.vm.impls[impl_id].read();
+
+            if ximpl.is_generic(self.vm) {
+                let cls_id = ximpl.cls_id(self.vm);
+                let cls = self.vm.classes.idx(cls_id);
+                let cls = cls.read();
+
+                for (tpid, tp) in cls.type_params.iter().enumerate() {
+                    self.vm
+                        .sym
+                        .lock()
+                        .insert_type(tp.name, SymClassTypeParam(cls_id, tpid.into()));
+                }
+            }
+        }
+
         if let Some(ref type_params) = self.fct.ast.type_params {
             for (tpid, tp) in type_params.iter().enumerate() {
                 self.vm
@@ -187,8 +204,9 @@ impl<'a, 'ast> NameCheck<'a, 'ast> {
     }
 
     fn check_expr_ident(&mut self, ident: &'ast ExprIdentType) {
-        let term_sym = self.vm.sym.lock().get_term(ident.name);
-        let type_sym = self.vm.sym.lock().get_type(ident.name);
+        let in_stdlib = self.vm.is_stdlib_file(self.fct.file);
+        let term_sym = self.vm.sym.lock().lookup_term(ident.name, in_stdlib);
+        let type_sym = self.vm.sym.lock().lookup_type(ident.name, in_stdlib);
 
         match (term_sym, type_sym) {
             (Some(SymVar(id)), None) => {
@@ -306,7 +324,8 @@ impl<'a, 'ast> Visitor<'ast> for NameCheck<'a, 'ast> {
         };
 
         // params are only allowed to replace functions, vars cannot be replaced
-        let term_sym = self.vm.sym.lock().get_term(p.name);
+        let in_stdlib = self.vm.is_stdlib_file(self.fct.file);
+        let term_sym = self.vm.sym.lock().lookup_term(p.name, in_stdlib);
         match term_sym {
             Some(SymFct(_)) | None => {
                 let var_id = self.add_var(var_ctxt, p.pos);
@@ -416,6 +435,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn param_named_like_global() {
+        err(
+            "let path: Int32 = 1; fun f(path: String) {}",
+            pos(1, 28),
+            SemError::ShadowGlobal("path".into()),
+        );
+
+        // stdlib parameters (like in File and Format) don't see program globals
+        ok("let path: Int32 = 1; let count: Int32 = 1;
+            fun f() -> String { File::open(\"a\"); \"${path:>4}${count}\" }");
+    }
+
     #[test]
     fn multiple_params() {
         ok("fun f(a: Int32, b: Int32, c:String) {}");
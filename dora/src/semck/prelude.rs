@@ -1,7 +1,7 @@
 use parking_lot::RwLock;
 use std::sync::Arc;
 
-use crate::cpu::{has_lzcnt, has_popcnt, has_tzcnt};
+use crate::cpu::{has_lzcnt, has_popcnt, has_round, has_tzcnt};
 use crate::gc::Address;
 use crate::mem;
 use crate::object::Header;
@@ -31,6 +31,8 @@ pub fn internal_classes<'ast>(vm: &mut VM<'ast>) {
 
     vm.vips.cls.string_buffer = internal_class(vm, "StringBuffer", None);
     vm.vips.mods.string_buffer = internal_module(vm, "StringBuffer", None);
+    vm.vips.cls.mutex = internal_class(vm, "Mutex", None);
+    vm.vips.cls.atomic_ref = internal_class(vm, "AtomicRef", None);
 
     let cls = vm.classes.idx(vm.vips.string_class);
     let mut cls = cls.write();
@@ -51,6 +53,7 @@ pub fn internal_classes<'ast>(vm: &mut VM<'ast>) {
     vm.vips.stringable_trait = find_trait(vm, "Stringable");
     vm.vips.zero_trait = find_trait(vm, "Zero");
     *vm.vips.iterator_trait.lock() = Some(find_trait(vm, "Iterator"));
+    *vm.vips.iterable_trait.lock() = Some(find_trait(vm, "Iterable"));
 
     internal_free_classes(vm);
 }
@@ -60,6 +63,16 @@ pub fn known_methods<'ast>(vm: &mut VM<'ast>) {
     vm.vips.fct.string_buffer_append = find_class_method(vm, vm.vips.cls.string_buffer, "append");
     vm.vips.fct.string_buffer_to_string =
         find_class_method(vm, vm.vips.cls.string_buffer, "toString");
+    vm.vips.fct.mutex_lock = find_class_method(vm, vm.vips.cls.mutex, "lock");
+    vm.vips.fct.mutex_unlock = find_class_method(vm, vm.vips.cls.mutex, "unlock");
+
+    let format = find_module(vm, "Format");
+    vm.vips.fct.format_uint8 = find_module_method(vm, format, "appendUInt8");
+    vm.vips.fct.format_int32 = find_module_method(vm, format, "appendInt32");
+    vm.vips.fct.format_int64 = find_module_method(vm, format, "appendInt64");
+    vm.vips.fct.format_float32 = find_module_method(vm, format, "appendFloat32");
+    vm.vips.fct.format_float64 = find_module_method(vm, format, "appendFloat64");
+    vm.vips.fct.format_string = find_module_method(vm, format, "appendString");
 }
 
 fn internal_free_classes<'ast>(vm: &mut VM<'ast>) {
@@ -81,6 +94,7 @@ fn internal_free_classes<'ast>(vm: &mut VM<'ast>) {
             size: InstanceSize::Fixed(Header::size()),
             fields: Vec::new(),
             ref_fields: Vec::new(),
+            tuple_ref_fields: Vec::new(),
             vtable: None,
         })));
 
@@ -92,6 +106,7 @@ fn internal_free_classes<'ast>(vm: &mut VM<'ast>) {
             size: InstanceSize::FreeArray,
             fields: Vec::new(),
             ref_fields: Vec::new(),
+            tuple_ref_fields: Vec::new(),
             vtable: None,
         })));
 
@@ -118,7 +133,7 @@ fn internal_free_classes<'ast>(vm: &mut VM<'ast>) {
 
 fn internal_class<'ast>(vm: &mut VM<'ast>, name: &str, ty: Option<BuiltinType>) -> ClassId {
     let iname = vm.interner.intern(name);
-    let clsid = vm.sym.lock().get_class(iname);
+    let clsid = vm.sym.lock().get_stdlib_class(iname);
 
     if let Some(clsid) = clsid {
         let cls = vm.classes.idx(clsid);
@@ -140,7 +155,7 @@ fn internal_class<'ast>(vm: &mut VM<'ast>, name: &str, ty: Option<BuiltinType>)
 
 fn internal_module<'ast>(vm: &mut VM<'ast>, name: &str, ty: Option<BuiltinType>) -> ModuleId {
     let iname = vm.interner.intern(name);
-    let module_id = vm.sym.lock().get_module(iname);
+    let module_id = vm.sym.lock().get_stdlib_module(iname);
 
     if let Some(module_id) = module_id {
         let module = vm.modules.idx(module_id);
@@ -163,7 +178,7 @@ fn internal_module<'ast>(vm: &mut VM<'ast>, name: &str, ty: Option<BuiltinType>)
 fn find_trait<'ast>(vm: &mut VM<'ast>, name: &str) -> TraitId {
     let iname = vm.interner.intern(name);
 
-    let tid = vm.sym.lock().get_trait(iname);
+    let tid = vm.sym.lock().get_stdlib_trait(iname);
 
     if let Some(tid) = tid {
         tid
@@ -172,6 +187,30 @@ fn find_trait<'ast>(vm: &mut VM<'ast>, name: &str) -> TraitId {
     }
 }
 
+fn find_class<'ast>(vm: &VM<'ast>, name: &str) -> ClassId {
+    let iname = vm.interner.intern(name);
+
+    let clsid = vm.sym.lock().get_stdlib_class(iname);
+
+    if let Some(clsid) = clsid {
+        clsid
+    } else {
+        panic!("class {} not found!", name);
+    }
+}
+
+fn find_module<'ast>(vm: &VM<'ast>, name: &str) -> ModuleId {
+    let iname = vm.interner.intern(name);
+
+    let module_id = vm.sym.lock().get_stdlib_module(iname);
+
+    if let Some(module_id) = module_id {
+        module_id
+    } else {
+        panic!("module {} not found!", name);
+    }
+}
+
 pub fn internal_functions<'ast>(vm: &mut VM<'ast>) {
     native_fct(vm, "fatalError", stdlib::fatal_error as *const u8);
     native_fct(vm, "abort", stdlib::abort as *const u8);
@@ -179,6 +218,8 @@ pub fn internal_functions<'ast>(vm: &mut VM<'ast>) {
 
     native_fct(vm, "print", stdlib::print as *const u8);
     native_fct(vm, "println", stdlib::println as *const u8);
+    native_fct(vm, "eprint", stdlib::eprint as *const u8);
+    native_fct(vm, "eprintln", stdlib::eprintln as *const u8);
     intrinsic_fct(vm, "assert", Intrinsic::Assert);
     intrinsic_fct(vm, "debug", Intrinsic::Debug);
     native_fct(vm, "argc", stdlib::argc as *const u8);
@@ -191,6 +232,7 @@ pub fn internal_functions<'ast>(vm: &mut VM<'ast>) {
         stdlib::gc_minor_collect as *const u8,
     );
     native_fct(vm, "sleep", stdlib::sleep as *const u8);
+    native_fct(vm, "wallclock", stdlib::wallclock_nanos as *const u8);
     native_fct(vm, "encodedBytecode", stdlib::bytecode as *const u8);
 
     native_fct(vm, "call", stdlib::call as *const u8);
@@ -216,6 +258,50 @@ pub fn internal_functions<'ast>(vm: &mut VM<'ast>) {
 
     intrinsic_class_method(vm, clsid, "equals", Intrinsic::CharEq);
     intrinsic_class_method(vm, clsid, "compareTo", Intrinsic::CharCmp);
+    native_class_method(
+        vm,
+        clsid,
+        "isAlphabetic",
+        stdlib::char_is_alphabetic as *const u8,
+    );
+    native_class_method(vm, clsid, "isNumeric", stdlib::char_is_numeric as *const u8);
+    native_class_method(
+        vm,
+        clsid,
+        "isAlphanumeric",
+        stdlib::char_is_alphanumeric as *const u8,
+    );
+    native_class_method(
+        vm,
+        clsid,
+        "isWhitespace",
+        stdlib::char_is_whitespace as *const u8,
+    );
+    native_class_method(vm, clsid, "isControl", stdlib::char_is_control as *const u8);
+    native_class_method(
+        vm,
+        clsid,
+        "isUpperCase",
+        stdlib::char_is_uppercase as *const u8,
+    );
+    native_class_method(
+        vm,
+        clsid,
+        "isLowerCase",
+        stdlib::char_is_lowercase as *const u8,
+    );
+    native_class_method(
+        vm,
+        clsid,
+        "toUpperCase",
+        stdlib::char_to_uppercase as *const u8,
+    );
+    native_class_method(
+        vm,
+        clsid,
+        "toLowerCase",
+        stdlib::char_to_lowercase as *const u8,
+    );
 
     let clsid = vm.vips.int32_class;
     native_class_method(vm, clsid, "toString", stdlib::int32_to_string as *const u8);
@@ -297,6 +383,42 @@ pub fn internal_functions<'ast>(vm: &mut VM<'ast>) {
 
     let clsid = vm.vips.string_class;
     native_class_method(vm, clsid, "compareTo", stdlib::strcmp as *const u8);
+    native_class_method(
+        vm,
+        clsid,
+        "indexOfOrMinusOne",
+        stdlib::str_index_of as *const u8,
+    );
+    native_class_method(
+        vm,
+        clsid,
+        "lastIndexOfOrMinusOne",
+        stdlib::str_last_index_of as *const u8,
+    );
+    native_class_method(
+        vm,
+        clsid,
+        "startsWith",
+        stdlib::str_starts_with as *const u8,
+    );
+    native_class_method(vm, clsid, "endsWith", stdlib::str_ends_with as *const u8);
+    native_class_method(vm, clsid, "trim", stdlib::str_trim as *const u8);
+    native_class_method(vm, clsid, "trimStart", stdlib::str_trim_start as *const u8);
+    native_class_method(vm, clsid, "trimEnd", stdlib::str_trim_end as *const u8);
+    native_class_method(vm, clsid, "replace", stdlib::str_replace as *const u8);
+    native_class_method(vm, clsid, "repeat", stdlib::str_repeat as *const u8);
+    native_class_method(
+        vm,
+        clsid,
+        "toUpperCase",
+        stdlib::str_to_uppercase as *const u8,
+    );
+    native_class_method(
+        vm,
+        clsid,
+        "toLowerCase",
+        stdlib::str_to_lowercase as *const u8,
+    );
     native_class_method(
         vm,
         clsid,
@@ -321,6 +443,30 @@ pub fn internal_functions<'ast>(vm: &mut VM<'ast>) {
         "toInt64OrZero",
         stdlib::str_to_int64 as *const u8,
     );
+    native_class_method(
+        vm,
+        clsid,
+        "toFloat32Success",
+        stdlib::str_to_float32_success as *const u8,
+    );
+    native_class_method(
+        vm,
+        clsid,
+        "toFloat64Success",
+        stdlib::str_to_float64_success as *const u8,
+    );
+    native_class_method(
+        vm,
+        clsid,
+        "toFloat32OrZero",
+        stdlib::str_to_float32 as *const u8,
+    );
+    native_class_method(
+        vm,
+        clsid,
+        "toFloat64OrZero",
+        stdlib::str_to_float64 as *const u8,
+    );
     native_class_method(vm, clsid, "plus", stdlib::strcat as *const u8);
 
     intrinsic_class_method(vm, clsid, "size", Intrinsic::StrLen);
@@ -334,6 +480,24 @@ pub fn internal_functions<'ast>(vm: &mut VM<'ast>) {
         "toString",
         stdlib::float32_to_string as *const u8,
     );
+    native_class_method(
+        vm,
+        clsid,
+        "toStringFixed",
+        stdlib::float32_to_string_fixed as *const u8,
+    );
+    native_class_method(
+        vm,
+        clsid,
+        "toStringExponential",
+        stdlib::float32_to_string_exponential as *const u8,
+    );
+    native_class_method(
+        vm,
+        clsid,
+        "toStringHex",
+        stdlib::float32_to_string_hex as *const u8,
+    );
     intrinsic_class_method(vm, clsid, "toInt32", Intrinsic::Float32ToInt32);
     intrinsic_class_method(vm, clsid, "toInt64", Intrinsic::Float32ToInt64);
     intrinsic_class_method(vm, clsid, "toFloat64", Intrinsic::PromoteFloat32ToFloat64);
@@ -354,6 +518,31 @@ pub fn internal_functions<'ast>(vm: &mut VM<'ast>) {
     intrinsic_class_method(vm, clsid, "isNan", Intrinsic::Float32IsNan);
     intrinsic_class_method(vm, clsid, "sqrt", Intrinsic::Float32Sqrt);
 
+    native_class_method(vm, clsid, "trunc", stdlib::float32_trunc as *const u8);
+    native_class_method(vm, clsid, "ceil", stdlib::float32_ceil as *const u8);
+    native_class_method(vm, clsid, "floor", stdlib::float32_floor as *const u8);
+    native_class_method(
+        vm,
+        clsid,
+        "roundHalfEven",
+        stdlib::float32_round_half_even as *const u8,
+    );
+
+    native_class_method(vm, clsid, "sin", stdlib::float32_sin as *const u8);
+    native_class_method(vm, clsid, "cos", stdlib::float32_cos as *const u8);
+    native_class_method(vm, clsid, "tan", stdlib::float32_tan as *const u8);
+    native_class_method(vm, clsid, "asin", stdlib::float32_asin as *const u8);
+    native_class_method(vm, clsid, "acos", stdlib::float32_acos as *const u8);
+    native_class_method(vm, clsid, "atan", stdlib::float32_atan as *const u8);
+    native_class_method(vm, clsid, "exp", stdlib::float32_exp as *const u8);
+    native_class_method(vm, clsid, "log", stdlib::float32_log as *const u8);
+    native_class_method(vm, clsid, "log2", stdlib::float32_log2 as *const u8);
+    native_class_method(vm, clsid, "log10", stdlib::float32_log10 as *const u8);
+    native_class_method(vm, clsid, "cbrt", stdlib::float32_cbrt as *const u8);
+    native_class_method(vm, clsid, "atan2", stdlib::float32_atan2 as *const u8);
+    native_class_method(vm, clsid, "pow", stdlib::float32_pow as *const u8);
+    native_class_method(vm, clsid, "hypot", stdlib::float32_hypot as *const u8);
+
     let clsid = vm.vips.float64_class;
     native_class_method(
         vm,
@@ -361,6 +550,24 @@ pub fn internal_functions<'ast>(vm: &mut VM<'ast>) {
         "toString",
         stdlib::float64_to_string as *const u8,
     );
+    native_class_method(
+        vm,
+        clsid,
+        "toStringFixed",
+        stdlib::float64_to_string_fixed as *const u8,
+    );
+    native_class_method(
+        vm,
+        clsid,
+        "toStringExponential",
+        stdlib::float64_to_string_exponential as *const u8,
+    );
+    native_class_method(
+        vm,
+        clsid,
+        "toStringHex",
+        stdlib::float64_to_string_hex as *const u8,
+    );
     intrinsic_class_method(vm, clsid, "toInt32", Intrinsic::Float64ToInt32);
     intrinsic_class_method(vm, clsid, "toInt64", Intrinsic::Float64ToInt64);
     intrinsic_class_method(vm, clsid, "toFloat32", Intrinsic::DemoteFloat64ToFloat32);
@@ -381,6 +588,31 @@ pub fn internal_functions<'ast>(vm: &mut VM<'ast>) {
     intrinsic_class_method(vm, clsid, "isNan", Intrinsic::Float64IsNan);
     intrinsic_class_method(vm, clsid, "sqrt", Intrinsic::Float64Sqrt);
 
+    native_class_method(vm, clsid, "trunc", stdlib::float64_trunc as *const u8);
+    native_class_method(vm, clsid, "ceil", stdlib::float64_ceil as *const u8);
+    native_class_method(vm, clsid, "floor", stdlib::float64_floor as *const u8);
+    native_class_method(
+        vm,
+        clsid,
+        "roundHalfEven",
+        stdlib::float64_round_half_even as *const u8,
+    );
+
+    native_class_method(vm, clsid, "sin", stdlib::float64_sin as *const u8);
+    native_class_method(vm, clsid, "cos", stdlib::float64_cos as *const u8);
+    native_class_method(vm, clsid, "tan", stdlib::float64_tan as *const u8);
+    native_class_method(vm, clsid, "asin", stdlib::float64_asin as *const u8);
+    native_class_method(vm, clsid, "acos", stdlib::float64_acos as *const u8);
+    native_class_method(vm, clsid, "atan", stdlib::float64_atan as *const u8);
+    native_class_method(vm, clsid, "exp", stdlib::float64_exp as *const u8);
+    native_class_method(vm, clsid, "log", stdlib::float64_log as *const u8);
+    native_class_method(vm, clsid, "log2", stdlib::float64_log2 as *const u8);
+    native_class_method(vm, clsid, "log10", stdlib::float64_log10 as *const u8);
+    native_class_method(vm, clsid, "cbrt", stdlib::float64_cbrt as *const u8);
+    native_class_method(vm, clsid, "atan2", stdlib::float64_atan2 as *const u8);
+    native_class_method(vm, clsid, "pow", stdlib::float64_pow as *const u8);
+    native_class_method(vm, clsid, "hypot", stdlib::float64_hypot as *const u8);
+
     let module_id = vm.vips.string_module;
     native_module_method(
         vm,
@@ -419,10 +651,347 @@ pub fn internal_functions<'ast>(vm: &mut VM<'ast>) {
     );
 
     let iname = vm.interner.intern("Thread");
-    let clsid = vm.sym.lock().get_class(iname);
+    let clsid = vm.sym.lock().get_stdlib_class(iname);
 
     if let Some(clsid) = clsid {
         native_class_method(vm, clsid, "start", stdlib::spawn_thread as *const u8);
+        native_class_method(vm, clsid, "joinThread", stdlib::join_thread as *const u8);
+    }
+
+    let module_id = vm.sym.lock().get_stdlib_module(iname);
+
+    if let Some(module_id) = module_id {
+        native_module_method(
+            vm,
+            module_id,
+            "current",
+            stdlib::current_thread as *const u8,
+        );
+        native_module_method(
+            vm,
+            module_id,
+            "sleepNanos",
+            stdlib::sleep_nanos as *const u8,
+        );
+    }
+
+    let clsid = vm.vips.cls.mutex;
+    native_class_method(vm, clsid, "lock", stdlib::mutex_lock as *const u8);
+    native_class_method(vm, clsid, "tryLock", stdlib::mutex_try_lock as *const u8);
+    native_class_method(vm, clsid, "unlock", stdlib::mutex_unlock as *const u8);
+
+    let clsid = find_class(vm, "ConditionVariable");
+    native_class_method(vm, clsid, "wait", stdlib::condition_wait as *const u8);
+    native_class_method(
+        vm,
+        clsid,
+        "waitTimeout",
+        stdlib::condition_wait_timeout as *const u8,
+    );
+    native_class_method(
+        vm,
+        clsid,
+        "notifyOne",
+        stdlib::condition_notify_one as *const u8,
+    );
+    native_class_method(
+        vm,
+        clsid,
+        "notifyAll",
+        stdlib::condition_notify_all as *const u8,
+    );
+
+    let clsid = find_class(vm, "AtomicInt32");
+    intrinsic_class_method(vm, clsid, "load", Intrinsic::AtomicInt32Load);
+    intrinsic_class_method(vm, clsid, "store", Intrinsic::AtomicInt32Store);
+    intrinsic_class_method(vm, clsid, "exchange", Intrinsic::AtomicInt32Exchange);
+    intrinsic_class_method(
+        vm,
+        clsid,
+        "compareExchange",
+        Intrinsic::AtomicInt32CompareExchange,
+    );
+    intrinsic_class_method(vm, clsid, "fetchAdd", Intrinsic::AtomicInt32FetchAdd);
+
+    let clsid = find_class(vm, "AtomicInt64");
+    intrinsic_class_method(vm, clsid, "load", Intrinsic::AtomicInt64Load);
+    intrinsic_class_method(vm, clsid, "store", Intrinsic::AtomicInt64Store);
+    intrinsic_class_method(vm, clsid, "exchange", Intrinsic::AtomicInt64Exchange);
+    intrinsic_class_method(
+        vm,
+        clsid,
+        "compareExchange",
+        Intrinsic::AtomicInt64CompareExchange,
+    );
+    intrinsic_class_method(vm, clsid, "fetchAdd", Intrinsic::AtomicInt64FetchAdd);
+
+    let clsid = find_class(vm, "AtomicBool");
+    intrinsic_class_method(vm, clsid, "load", Intrinsic::AtomicBoolLoad);
+    intrinsic_class_method(vm, clsid, "store", Intrinsic::AtomicBoolStore);
+    intrinsic_class_method(vm, clsid, "exchange", Intrinsic::AtomicBoolExchange);
+    intrinsic_class_method(
+        vm,
+        clsid,
+        "compareExchange",
+        Intrinsic::AtomicBoolCompareExchange,
+    );
+
+    let clsid = vm.vips.cls.atomic_ref;
+    intrinsic_class_method(vm, clsid, "load", Intrinsic::AtomicRefLoad);
+    intrinsic_class_method(vm, clsid, "store", Intrinsic::AtomicRefStore);
+    intrinsic_class_method(vm, clsid, "exchange", Intrinsic::AtomicRefExchange);
+    intrinsic_class_method(
+        vm,
+        clsid,
+        "compareExchange",
+        Intrinsic::AtomicRefCompareExchange,
+    );
+
+    let module_id = find_module(vm, "FileSystem");
+    native_module_method(vm, module_id, "open", stdlib::file_open as *const u8);
+    native_module_method(vm, module_id, "read", stdlib::file_read as *const u8);
+    native_module_method(vm, module_id, "write", stdlib::file_write as *const u8);
+    native_module_method(
+        vm,
+        module_id,
+        "writeString",
+        stdlib::file_write as *const u8,
+    );
+    native_module_method(vm, module_id, "seek", stdlib::file_seek as *const u8);
+    native_module_method(vm, module_id, "close", stdlib::file_close as *const u8);
+    native_module_method(
+        vm,
+        module_id,
+        "metadata",
+        stdlib::file_metadata as *const u8,
+    );
+    native_module_method(vm, module_id, "remove", stdlib::file_remove as *const u8);
+    native_module_method(vm, module_id, "rename", stdlib::file_rename as *const u8);
+    native_module_method(
+        vm,
+        module_id,
+        "createDirectory",
+        stdlib::directory_create as *const u8,
+    );
+    native_module_method(
+        vm,
+        module_id,
+        "removeDirectory",
+        stdlib::directory_remove as *const u8,
+    );
+    native_module_method(
+        vm,
+        module_id,
+        "readDirectory",
+        stdlib::directory_read as *const u8,
+    );
+    native_module_method(
+        vm,
+        module_id,
+        "nextEntryOrNull",
+        stdlib::string_list_next as *const u8,
+    );
+    native_module_method(
+        vm,
+        module_id,
+        "closeDirectory",
+        stdlib::string_list_close as *const u8,
+    );
+    native_module_method(
+        vm,
+        module_id,
+        "errorMessage",
+        stdlib::io_error_message as *const u8,
+    );
+
+    let module_id = find_module(vm, "Stdin");
+    native_module_method(
+        vm,
+        module_id,
+        "readLineOrNull",
+        stdlib::stdin_read_line as *const u8,
+    );
+    native_module_method(vm, module_id, "readBytes", stdlib::stdin_read as *const u8);
+
+    let module_id = find_module(vm, "Env");
+    native_module_method(vm, module_id, "getOrNull", stdlib::env_get as *const u8);
+    native_module_method(vm, module_id, "set", stdlib::env_set as *const u8);
+    native_module_method(vm, module_id, "remove", stdlib::env_remove as *const u8);
+    native_module_method(vm, module_id, "list", stdlib::env_vars as *const u8);
+    native_module_method(
+        vm,
+        module_id,
+        "nextOrNull",
+        stdlib::string_list_next as *const u8,
+    );
+    native_module_method(
+        vm,
+        module_id,
+        "close",
+        stdlib::string_list_close as *const u8,
+    );
+    native_module_method(
+        vm,
+        module_id,
+        "currentDirectoryOrNull",
+        stdlib::current_directory as *const u8,
+    );
+    native_module_method(
+        vm,
+        module_id,
+        "changeDirectory",
+        stdlib::set_current_directory as *const u8,
+    );
+
+    let module_id = find_module(vm, "Process");
+    native_module_method(
+        vm,
+        module_id,
+        "commandNew",
+        stdlib::command_new as *const u8,
+    );
+    native_module_method(
+        vm,
+        module_id,
+        "commandArg",
+        stdlib::command_arg as *const u8,
+    );
+    native_module_method(
+        vm,
+        module_id,
+        "commandEnv",
+        stdlib::command_env as *const u8,
+    );
+    native_module_method(
+        vm,
+        module_id,
+        "commandDirectory",
+        stdlib::command_directory as *const u8,
+    );
+    native_module_method(
+        vm,
+        module_id,
+        "commandStdio",
+        stdlib::command_stdio as *const u8,
+    );
+    native_module_method(vm, module_id, "spawn", stdlib::command_spawn as *const u8);
+    native_module_method(vm, module_id, "id", stdlib::process_id as *const u8);
+    native_module_method(
+        vm,
+        module_id,
+        "writeStdin",
+        stdlib::process_write_stdin as *const u8,
+    );
+    native_module_method(
+        vm,
+        module_id,
+        "writeStdinString",
+        stdlib::process_write_stdin as *const u8,
+    );
+    native_module_method(
+        vm,
+        module_id,
+        "closeStdin",
+        stdlib::process_close_stdin as *const u8,
+    );
+    native_module_method(vm, module_id, "read", stdlib::process_read as *const u8);
+    native_module_method(vm, module_id, "wait", stdlib::process_wait as *const u8);
+    native_module_method(vm, module_id, "kill", stdlib::process_kill as *const u8);
+    native_module_method(vm, module_id, "drop", stdlib::process_drop as *const u8);
+    native_module_method(vm, module_id, "output", stdlib::process_output as *const u8);
+    native_module_method(
+        vm,
+        module_id,
+        "outputStatus",
+        stdlib::output_status as *const u8,
+    );
+    native_module_method(
+        vm,
+        module_id,
+        "outputSize",
+        stdlib::output_size as *const u8,
+    );
+    native_module_method(
+        vm,
+        module_id,
+        "outputCopy",
+        stdlib::output_copy as *const u8,
+    );
+    native_module_method(
+        vm,
+        module_id,
+        "outputDrop",
+        stdlib::output_drop as *const u8,
+    );
+
+    let module_id = find_module(vm, "Socket");
+    native_module_method(
+        vm,
+        module_id,
+        "parseAddressOrNull",
+        stdlib::net_parse_address as *const u8,
+    );
+    native_module_method(vm, module_id, "listen", stdlib::tcp_listen as *const u8);
+    native_module_method(vm, module_id, "accept", stdlib::tcp_accept as *const u8);
+    native_module_method(vm, module_id, "connect", stdlib::tcp_connect as *const u8);
+    native_module_method(vm, module_id, "bindDatagram", stdlib::udp_bind as *const u8);
+    native_module_method(vm, module_id, "read", stdlib::socket_read as *const u8);
+    native_module_method(vm, module_id, "write", stdlib::socket_write as *const u8);
+    native_module_method(
+        vm,
+        module_id,
+        "writeString",
+        stdlib::socket_write as *const u8,
+    );
+    native_module_method(vm, module_id, "sendTo", stdlib::udp_send_to as *const u8);
+    native_module_method(
+        vm,
+        module_id,
+        "receiveFromOrNull",
+        stdlib::udp_receive_from as *const u8,
+    );
+    native_module_method(
+        vm,
+        module_id,
+        "setTimeout",
+        stdlib::socket_set_timeout as *const u8,
+    );
+    native_module_method(
+        vm,
+        module_id,
+        "shutdown",
+        stdlib::socket_shutdown as *const u8,
+    );
+    native_module_method(
+        vm,
+        module_id,
+        "localAddressOrNull",
+        stdlib::socket_local_address as *const u8,
+    );
+    native_module_method(
+        vm,
+        module_id,
+        "peerAddressOrNull",
+        stdlib::socket_peer_address as *const u8,
+    );
+    native_module_method(vm, module_id, "close", stdlib::socket_close as *const u8);
+    native_module_method(
+        vm,
+        module_id,
+        "timedOut",
+        stdlib::net_timed_out as *const u8,
+    );
+
+    let module_id = find_module(vm, "Sort");
+    native_module_method(
+        vm,
+        module_id,
+        "sortPrimitive",
+        stdlib::sort_primitive as *const u8,
+    );
+
+    for (name, fctptr) in vm.host_fcts.clone() {
+        native_fct(vm, &name, fctptr.to_ptr());
     }
 }
 
@@ -568,7 +1137,7 @@ fn intrinsic_fct<'ast>(vm: &mut VM<'ast>, name: &str, intrinsic: Intrinsic) {
 
 fn internal_fct<'ast>(vm: &mut VM<'ast>, name: &str, kind: FctKind) {
     let name = vm.interner.intern(name);
-    let fctid = vm.sym.lock().get_fct(name);
+    let fctid = vm.sym.lock().get_stdlib_fct(name);
 
     if let Some(fctid) = fctid {
         let fct = vm.fcts.idx(fctid);
@@ -682,6 +1251,20 @@ pub(crate) fn install_conditional_intrinsics(vm: &mut VM) {
             Intrinsic::Int64CountOneBitsTrailing,
         );
     }
+
+    if has_round() {
+        let clsid = vm.vips.float32_class;
+        intrinsic_class_method(vm, clsid, "trunc", Intrinsic::Float32RoundToZero);
+        intrinsic_class_method(vm, clsid, "ceil", Intrinsic::Float32RoundUp);
+        intrinsic_class_method(vm, clsid, "floor", Intrinsic::Float32RoundDown);
+        intrinsic_class_method(vm, clsid, "roundHalfEven", Intrinsic::Float32RoundHalfEven);
+
+        let clsid = vm.vips.float64_class;
+        intrinsic_class_method(vm, clsid, "trunc", Intrinsic::Float64RoundToZero);
+        intrinsic_class_method(vm, clsid, "ceil", Intrinsic::Float64RoundUp);
+        intrinsic_class_method(vm, clsid, "floor", Intrinsic::Float64RoundDown);
+        intrinsic_class_method(vm, clsid, "roundHalfEven", Intrinsic::Float64RoundHalfEven);
+    }
 }
 
 #[cfg(test)]
@@ -64,6 +64,7 @@ pub fn returns_value(s: &Stmt) -> Result<(), Position> {
         StmtContinue(ref stmt) => Err(stmt.pos),
         StmtVar(ref stmt) => Err(stmt.pos),
         StmtExpr(ref stmt) => expr_returns_value(&stmt.expr),
+        StmtSync(ref stmt) => returns_value(&stmt.block),
     }
 }
 
@@ -201,6 +201,7 @@ fn create_specialized_class(vm: &VM, cls: &Class, type_params: &TypeList) -> Cla
             size: InstanceSize::Fixed(0),
             fields: Vec::new(),
             ref_fields: Vec::new(),
+            tuple_ref_fields: Vec::new(),
             vtable: None,
         })));
 
@@ -209,12 +210,14 @@ fn create_specialized_class(vm: &VM, cls: &Class, type_params: &TypeList) -> Cla
 
     let mut fields;
     let mut ref_fields;
+    let mut tuple_ref_fields;
     let size;
     let parent_id;
 
     if cls.is_array || cls.is_str {
         fields = Vec::new();
         ref_fields = Vec::new();
+        tuple_ref_fields = Vec::new();
 
         size = if cls.is_array {
             let element_ty = type_params[0];
@@ -229,7 +232,7 @@ fn create_specialized_class(vm: &VM, cls: &Class, type_params: &TypeList) -> Cla
 
                 if tuple.contains_references() {
                     for &offset in tuple.references() {
-                        ref_fields.push(offset);
+                        tuple_ref_fields.push(offset);
                     }
 
                     InstanceSize::TupleArray(tuple.size())
@@ -259,6 +262,7 @@ fn create_specialized_class(vm: &VM, cls: &Class, type_params: &TypeList) -> Cla
 
             fields = Vec::new();
             ref_fields = cls_def.ref_fields.clone();
+            tuple_ref_fields = cls_def.tuple_ref_fields.clone();
             csize = match cls_def.size {
                 InstanceSize::Fixed(size) => size,
                 _ => unreachable!(),
@@ -267,6 +271,7 @@ fn create_specialized_class(vm: &VM, cls: &Class, type_params: &TypeList) -> Cla
         } else {
             fields = Vec::with_capacity(cls.fields.len());
             ref_fields = Vec::new();
+            tuple_ref_fields = Vec::new();
             csize = Header::size();
             parent_id = None;
         };
@@ -275,8 +280,11 @@ fn create_specialized_class(vm: &VM, cls: &Class, type_params: &TypeList) -> Cla
             let ty = specialize_type(vm, f.ty, &type_params, &TypeList::empty());
             debug_assert!(!ty.contains_type_param(vm));
 
-            let field_size = ty.size(vm);
-            let field_align = ty.align(vm);
+            let (field_size, field_align) = if ty.reference_type() {
+                (vm.ref_width(), vm.ref_width())
+            } else {
+                (ty.size(vm), ty.align(vm))
+            };
 
             let offset = mem::align_i32(csize, field_align);
             fields.push(FieldDef { offset, ty });
@@ -288,7 +296,7 @@ fn create_specialized_class(vm: &VM, cls: &Class, type_params: &TypeList) -> Cla
                 let tuple = tuples.get_tuple(tuple_id);
 
                 for &ref_offset in tuple.references() {
-                    ref_fields.push(offset + ref_offset);
+                    tuple_ref_fields.push(offset + ref_offset);
                 }
             } else if ty.reference_type() {
                 ref_fields.push(offset);
@@ -306,12 +314,13 @@ fn create_specialized_class(vm: &VM, cls: &Class, type_params: &TypeList) -> Cla
     cls_def.size = size;
     cls_def.fields = fields;
     cls_def.ref_fields = ref_fields;
+    cls_def.tuple_ref_fields = tuple_ref_fields;
     cls_def.parent_id = parent_id;
 
     let (instance_size, element_size) = match size {
         InstanceSize::Fixed(instance_size) => (instance_size as usize, 0),
         InstanceSize::PrimitiveArray(element_size) => (0, element_size as usize),
-        InstanceSize::ObjArray => (0, mem::ptr_width_usize()),
+        InstanceSize::ObjArray => (0, vm.ref_width() as usize),
         InstanceSize::UnitArray => (Header::size() as usize + mem::ptr_width_usize(), 0),
         InstanceSize::FreeArray => (0, mem::ptr_width_usize()),
         InstanceSize::Str => (0, 1),
@@ -408,6 +417,20 @@ pub fn replace_type_param(
             BuiltinType::Class(cls_id, list_id)
         }
 
+        BuiltinType::Trait(trait_id, list_id) => {
+            let params = vm.lists.lock().get(list_id);
+
+            let params = TypeList::with(
+                params
+                    .iter()
+                    .map(|p| replace_type_param(vm, p, cls_tp, fct_tp, self_ty))
+                    .collect::<Vec<_>>(),
+            );
+
+            let list_id = vm.lists.lock().insert(params);
+            BuiltinType::Trait(trait_id, list_id)
+        }
+
         BuiltinType::This => self_ty.expect("no type for Self given"),
 
         BuiltinType::Lambda(_) => unimplemented!(),
@@ -437,6 +460,53 @@ pub fn replace_type_param(
     }
 }
 
+// replaces the type params of a trait in the signature of a trait method
+// with the type arguments of an implementation
+pub fn replace_trait_type_param(
+    vm: &VM,
+    ty: BuiltinType,
+    trait_tp: &TypeList,
+    self_ty: BuiltinType,
+) -> BuiltinType {
+    match ty {
+        BuiltinType::TraitTypeParam(_, tpid) => trait_tp[tpid.idx()],
+        BuiltinType::This => self_ty,
+
+        BuiltinType::Class(_, list_id) | BuiltinType::Trait(_, list_id) => {
+            let params = vm.lists.lock().get(list_id);
+
+            let params = TypeList::with(
+                params
+                    .iter()
+                    .map(|p| replace_trait_type_param(vm, p, trait_tp, self_ty))
+                    .collect::<Vec<_>>(),
+            );
+
+            let list_id = vm.lists.lock().insert(params);
+
+            match ty {
+                BuiltinType::Class(cls_id, _) => BuiltinType::Class(cls_id, list_id),
+                BuiltinType::Trait(trait_id, _) => BuiltinType::Trait(trait_id, list_id),
+                _ => unreachable!(),
+            }
+        }
+
+        BuiltinType::Tuple(tuple_id) => {
+            let subtypes = vm.tuples.lock().get(tuple_id);
+
+            let new_subtypes = subtypes
+                .iter()
+                .map(|&t| replace_trait_type_param(vm, t, trait_tp, self_ty))
+                .collect::<Vec<_>>();
+
+            let tuple_id = ensure_tuple(vm, new_subtypes);
+            BuiltinType::Tuple(tuple_id)
+        }
+
+        _ => ty,
+    }
+}
+
 pub fn specialize_for_call_type(call_type: &CallType, ty: BuiltinType, vm: &VM) -> BuiltinType {
     match *call_type {
         CallType::Fct(_, ref cls_type_params, ref fct_type_params) => {
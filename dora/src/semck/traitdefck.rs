@@ -1,5 +1,5 @@
 use parking_lot::RwLock;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::error::msg::SemError;
 use crate::ty::BuiltinType;
@@ -33,6 +33,30 @@ impl<'x, 'ast> TraitCheck<'x, 'ast> {
     fn check(&mut self) {
         self.visit_ast(self.ast);
     }
+
+    fn check_type_params(&mut self, type_params: &'ast [ast::TypeParam]) {
+        let mut names = HashSet::new();
+
+        for type_param in type_params {
+            if !names.insert(type_param.name) {
+                let name = self.vm.interner.str(type_param.name).to_string();
+                let msg = SemError::TypeParamNameNotUnique(name);
+                self.vm
+                    .diag
+                    .lock()
+                    .report(self.file_id.into(), type_param.pos, msg);
+            }
+
+            // bounds on type params of traits aren't supported yet
+            if !type_param.bounds.is_empty() {
+                self.vm.diag.lock().report(
+                    self.file_id.into(),
+                    type_param.pos,
+                    SemError::Unimplemented,
+                );
+            }
+        }
+    }
 }
 
 impl<'x, 'ast> Visitor<'ast> for TraitCheck<'x, 'ast> {
@@ -44,6 +68,10 @@ impl<'x, 'ast> Visitor<'ast> for TraitCheck<'x, 'ast> {
     fn visit_trait(&mut self, t: &'ast ast::Trait) {
         self.trait_id = Some(*self.map_trait_defs.get(t.id).unwrap());
 
+        if let Some(ref type_params) = t.type_params {
+            self.check_type_params(type_params);
+        }
+
         visit::walk_trait(self, t);
 
         self.trait_id = None;
@@ -153,4 +181,21 @@ mod tests {
             SemError::MethodExists("foo".into(), pos(2, 13)),
         );
     }
+
+    #[test]
+    fn trait_with_type_params() {
+        ok("trait Foo[T] { fun get() -> T; fun set(value: T); }");
+        ok("trait Foo[A, B] { fun get(a: A) -> (A, B); }");
+
+        err(
+            "trait Foo[T, T] {}",
+            pos(1, 14),
+            SemError::TypeParamNameNotUnique("T".into()),
+        );
+        err(
+            "trait Foo[T] {} fun f(x: Foo) {}",
+            pos(1, 26),
+            SemError::WrongNumberTypeParams(1, 0),
+        );
+    }
 }
@@ -4,7 +4,7 @@ use std::collections::hash_set::HashSet;
 
 use crate::error::msg::SemError;
 use crate::ty::{BuiltinType, TypeList};
-use crate::vm::{FileId, TraitId, TypeParam, VM};
+use crate::vm::{ClassId, FileId, TraitId, TypeParam, VM};
 
 pub fn check_type(vm: &VM, file: FileId, pos: Position, object_type: BuiltinType) -> bool {
     let tp_defs = {
@@ -43,6 +43,31 @@ pub fn check_params(
     checker.check(params)
 }
 
+// the atomic instructions used for AtomicRef[T] only work on
+// references, floats and tuples would need different code.
+pub fn check_atomic_ref(
+    vm: &VM,
+    file: FileId,
+    pos: Position,
+    cls_id: ClassId,
+    params: &TypeList,
+) -> bool {
+    if cls_id != vm.vips.cls.atomic_ref || params.len() != 1 {
+        return true;
+    }
+
+    let ty = params[0];
+
+    if ty.reference_type() || ty.is_type_param() || ty.is_error() {
+        return true;
+    }
+
+    let msg = SemError::ReferenceTypeExpected(ty.name(vm));
+    vm.diag.lock().report(file, pos, msg);
+
+    false
+}
+
 struct TypeParamCheck<'a, 'ast: 'a> {
     vm: &'a VM<'ast>,
     file: FileId,
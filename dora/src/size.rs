@@ -1,4 +1,4 @@
-use crate::mem;
+use crate::vm::VM;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum InstanceSize {
@@ -12,10 +12,10 @@ pub enum InstanceSize {
 }
 
 impl InstanceSize {
-    pub fn element_size(&self) -> Option<i32> {
+    pub fn element_size(&self, vm: &VM) -> Option<i32> {
         match self {
             InstanceSize::PrimitiveArray(esize) => Some(*esize),
-            InstanceSize::ObjArray => Some(mem::ptr_width()),
+            InstanceSize::ObjArray => Some(vm.ref_width()),
             InstanceSize::Str => Some(1),
             InstanceSize::Fixed(_) => None,
             InstanceSize::FreeArray => unreachable!(),
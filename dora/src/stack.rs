@@ -3,9 +3,9 @@ use std::ptr;
 use crate::compiler::fct::JitFctId;
 use crate::compiler::map::CodeDescriptor;
 use crate::handle::{root, Handle};
-use crate::object::{alloc, Array, Int32Array, Ref, Stacktrace, StacktraceElement, Str};
-use crate::threads::THREAD;
-use crate::vm::{get_vm, FctParent, VM};
+use crate::object::{self, alloc, Array, Int32Array, Ref, Stacktrace, StacktraceElement, Str};
+use crate::threads::{DoraThread, THREAD};
+use crate::vm::{get_vm, FctId, FctParent, VM};
 
 pub struct NativeStacktrace {
     elems: Vec<StackElem>,
@@ -43,6 +43,16 @@ impl NativeStacktrace {
     }
 
     pub fn dump_err(&self, vm: &VM) {
+        let thread = THREAD.with(|thread| thread.borrow().clone());
+
+        // keep output for main thread short
+        if thread.id() != 0 {
+            match thread_name(vm, &thread) {
+                Some(name) => eprintln!("in thread {} ({}):", thread.id(), name),
+                None => eprintln!("in thread {}:", thread.id()),
+            }
+        }
+
         let frames = self.elems.len();
         for (ind, elem) in self.elems.iter().enumerate() {
             let jit_fct = vm.jit_fcts.idx(elem.fct_id);
@@ -61,6 +71,23 @@ impl NativeStacktrace {
     }
 }
 
+fn thread_name(vm: &VM, thread: &DoraThread) -> Option<String> {
+    if thread.object().is_null() {
+        return None;
+    }
+
+    let cls_id = vm.cls_def_by_name("Thread");
+    let fid = vm.field_in_class(cls_id, "name");
+    let name: Ref<Str> =
+        object::read_ref(vm, thread.object().to_usize().into(), cls_id, fid).cast();
+
+    if name.address().is_null() {
+        None
+    } else {
+        Some(String::from_utf8_lossy(name.content()).into_owned())
+    }
+}
+
 struct StackElem {
     fct_id: JitFctId,
     lineno: i32,
@@ -197,7 +224,7 @@ pub extern "C" fn retrieve_stack_trace(obj: Handle<Stacktrace>) {
 
 pub extern "C" fn stack_element(obj: Handle<Stacktrace>, ind: i32) -> Ref<StacktraceElement> {
     let vm = get_vm();
-    let array = obj.backtrace;
+    let array = obj.backtrace();
 
     let ind = ind as usize * 2;
 
@@ -207,14 +234,13 @@ pub extern "C" fn stack_element(obj: Handle<Stacktrace>, ind: i32) -> Ref<Stackt
 
     let ste: Ref<StacktraceElement> = alloc(vm, cls_def_id).cast();
     let mut ste = root(ste);
-    ste.line = lineno;
+    ste.set_line(lineno);
 
-    let jit_fct_id = JitFctId::from(fct_id as usize);
-    let jit_fct = vm.jit_fcts.idx(jit_fct_id);
-    let fct = vm.fcts.idx(jit_fct.fct_id());
+    let fct = vm.fcts.idx(FctId::from(fct_id as usize));
     let fct = fct.read();
     let name = fct.full_name(vm);
-    ste.name = Str::from_buffer(vm, name.as_bytes());
+    let name = Str::from_buffer(vm, name.as_bytes());
+    ste.set_name(name);
 
     ste.direct()
 }
@@ -293,10 +319,12 @@ fn set_backtrace(vm: &VM, mut obj: Handle<Stacktrace>, via_retrieve: bool) {
     let mut array = root(array);
     let mut i = 0;
 
+    // store source function: code collector might free and reuse the JitFctId
     for elem in stacktrace.elems.iter().skip(skip) {
+        let fct_id = vm.jit_fcts.idx(elem.fct_id).fct_id();
         array.set_at(i, elem.lineno);
-        array.set_at(i + 1, elem.fct_id.idx() as i32);
+        array.set_at(i + 1, fct_id.to_usize() as i32);
         i += 2;
     }
-    obj.backtrace = array.direct();
+    obj.set_backtrace(array.direct());
 }
@@ -1,21 +1,35 @@
 use libc;
 
-use std::io::{self, Write};
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
 use std::mem;
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::process;
+use std::slice;
 use std::str;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::vec;
 
 use crate::boots;
 use crate::gc::{Address, GcReason};
-use crate::handle::{scope as handle_scope, Handle};
-use crate::object::{Obj, Ref, Str, UInt8Array};
+use crate::handle::{root, scope as handle_scope, Handle};
+use crate::monitors::MonitorError;
+use crate::object::{self, ArrayElement, Int64Array, Obj, Ref, Str, UInt8Array};
 use crate::stack::stacktrace_from_last_dtn;
 use crate::sym::TermSym::SymFct;
 use crate::threads::{DoraThread, STACK_SIZE, THREAD};
-use crate::ty::TypeList;
-use crate::vm::{get_vm, stack_pointer, Trap};
+use crate::ty::{BuiltinType, TypeList};
+use crate::vm::{find_methods_in_class, get_vm, stack_pointer, Trap, VM};
 
 pub extern "C" fn uint8_to_string(val: u8) -> Ref<Str> {
     handle_scope(|| {
@@ -26,9 +40,18 @@ pub extern "C" fn uint8_to_string(val: u8) -> Ref<Str> {
     })
 }
 
-pub extern "C" fn char_to_string(val: char) -> Ref<Str> {
+// Chars created with `Int32.toCharUnchecked()` might not be valid
+// scalar values, natives take them as u32 and check them first.
+fn char_from_u32(val: u32) -> Option<char> {
+    std::char::from_u32(val)
+}
+
+// invalid chars are shown as U+FFFD
+pub extern "C" fn char_to_string(val: u32) -> Ref<Str> {
     handle_scope(|| {
-        let buffer = val.to_string();
+        let buffer = char_from_u32(val)
+            .unwrap_or(std::char::REPLACEMENT_CHARACTER)
+            .to_string();
         let vm = get_vm();
 
         Str::from_buffer(vm, buffer.as_bytes())
@@ -71,6 +94,273 @@ pub extern "C" fn float64_to_string(val: f64) -> Ref<Str> {
     })
 }
 
+// A negative precision uses as many digits as needed to round-trip, like
+// `toString`.
+pub extern "C" fn float32_to_string_fixed(val: f32, precision: i32) -> Ref<Str> {
+    float_format(float_to_fixed_string(val, precision))
+}
+
+pub extern "C" fn float64_to_string_fixed(val: f64, precision: i32) -> Ref<Str> {
+    float_format(float_to_fixed_string(val, precision))
+}
+
+fn float_to_fixed_string<T: fmt::Display>(val: T, precision: i32) -> String {
+    if precision < 0 {
+        format!("{}", val)
+    } else {
+        format!("{:.*}", precision as usize, val)
+    }
+}
+
+// A negative precision uses the shortest mantissa that round-trips, e.g.
+// 1234.5 is "1.2345e3".
+pub extern "C" fn float32_to_string_exponential(val: f32, precision: i32) -> Ref<Str> {
+    float_format(float_to_exponential_string(val, precision))
+}
+
+pub extern "C" fn float64_to_string_exponential(val: f64, precision: i32) -> Ref<Str> {
+    float_format(float_to_exponential_string(val, precision))
+}
+
+fn float_to_exponential_string<T: fmt::LowerExp>(val: T, precision: i32) -> String {
+    if precision < 0 {
+        format!("{:e}", val)
+    } else {
+        format!("{:.*e}", precision as usize, val)
+    }
+}
+
+pub extern "C" fn float32_to_string_hex(val: f32) -> Ref<Str> {
+    let bits = val.to_bits() as u64;
+    // shift the 23 bit mantissa to a multiple of 4 bits
+    float_format(float_to_hex_string(
+        bits >> 31 != 0,
+        (bits >> 23) & 0xFF,
+        (bits & 0x7F_FF_FF) << 1,
+        6,
+        127,
+    ))
+}
+
+pub extern "C" fn float64_to_string_hex(val: f64) -> Ref<Str> {
+    let bits = val.to_bits();
+    float_format(float_to_hex_string(
+        bits >> 63 != 0,
+        (bits >> 52) & 0x7FF,
+        bits & 0xF_FF_FF_FF_FF_FF_FF,
+        13,
+        1023,
+    ))
+}
+
+// Formats like C's `%a`: 1.5 is "0x1.8p+0" and subnormals use a leading "0x0.".
+fn float_to_hex_string(
+    negative: bool,
+    exponent: u64,
+    mantissa: u64,
+    digits: usize,
+    bias: i64,
+) -> String {
+    let sign = if negative { "-" } else { "" };
+    let max_exponent = (2 * bias + 1) as u64;
+
+    if exponent == max_exponent {
+        return if mantissa != 0 {
+            "NaN".into()
+        } else {
+            format!("{}inf", sign)
+        };
+    }
+
+    if exponent == 0 && mantissa == 0 {
+        return format!("{}0x0p+0", sign);
+    }
+
+    let (leading, exponent) = if exponent == 0 {
+        (0, 1 - bias)
+    } else {
+        (1, exponent as i64 - bias)
+    };
+
+    let fraction = format!("{:0width$x}", mantissa, width = digits);
+    let fraction = fraction.trim_end_matches('0');
+    let exponent_sign = if exponent < 0 { "-" } else { "+" };
+
+    if fraction.is_empty() {
+        format!("{}0x{}p{}{}", sign, leading, exponent_sign, exponent.abs())
+    } else {
+        format!(
+            "{}0x{}.{}p{}{}",
+            sign,
+            leading,
+            fraction,
+            exponent_sign,
+            exponent.abs()
+        )
+    }
+}
+
+// Fallbacks for the rounding intrinsics on CPUs without SSE4.1.
+pub extern "C" fn float32_floor(val: f32) -> f32 {
+    val.floor()
+}
+
+pub extern "C" fn float32_ceil(val: f32) -> f32 {
+    val.ceil()
+}
+
+pub extern "C" fn float32_trunc(val: f32) -> f32 {
+    val.trunc()
+}
+
+pub extern "C" fn float32_round_half_even(val: f32) -> f32 {
+    if (val - val.trunc()).abs() == 0.5 {
+        2.0 * (val / 2.0).round()
+    } else {
+        val.round()
+    }
+}
+
+pub extern "C" fn float32_sin(val: f32) -> f32 {
+    val.sin()
+}
+
+pub extern "C" fn float32_cos(val: f32) -> f32 {
+    val.cos()
+}
+
+pub extern "C" fn float32_tan(val: f32) -> f32 {
+    val.tan()
+}
+
+pub extern "C" fn float32_asin(val: f32) -> f32 {
+    val.asin()
+}
+
+pub extern "C" fn float32_acos(val: f32) -> f32 {
+    val.acos()
+}
+
+pub extern "C" fn float32_atan(val: f32) -> f32 {
+    val.atan()
+}
+
+pub extern "C" fn float32_exp(val: f32) -> f32 {
+    val.exp()
+}
+
+pub extern "C" fn float32_log(val: f32) -> f32 {
+    val.ln()
+}
+
+pub extern "C" fn float32_log2(val: f32) -> f32 {
+    val.log2()
+}
+
+pub extern "C" fn float32_log10(val: f32) -> f32 {
+    val.log10()
+}
+
+pub extern "C" fn float32_cbrt(val: f32) -> f32 {
+    val.cbrt()
+}
+
+pub extern "C" fn float32_atan2(lhs: f32, rhs: f32) -> f32 {
+    lhs.atan2(rhs)
+}
+
+pub extern "C" fn float32_pow(lhs: f32, rhs: f32) -> f32 {
+    lhs.powf(rhs)
+}
+
+pub extern "C" fn float32_hypot(lhs: f32, rhs: f32) -> f32 {
+    lhs.hypot(rhs)
+}
+
+pub extern "C" fn float64_floor(val: f64) -> f64 {
+    val.floor()
+}
+
+pub extern "C" fn float64_ceil(val: f64) -> f64 {
+    val.ceil()
+}
+
+pub extern "C" fn float64_trunc(val: f64) -> f64 {
+    val.trunc()
+}
+
+pub extern "C" fn float64_round_half_even(val: f64) -> f64 {
+    if (val - val.trunc()).abs() == 0.5 {
+        2.0 * (val / 2.0).round()
+    } else {
+        val.round()
+    }
+}
+
+pub extern "C" fn float64_sin(val: f64) -> f64 {
+    val.sin()
+}
+
+pub extern "C" fn float64_cos(val: f64) -> f64 {
+    val.cos()
+}
+
+pub extern "C" fn float64_tan(val: f64) -> f64 {
+    val.tan()
+}
+
+pub extern "C" fn float64_asin(val: f64) -> f64 {
+    val.asin()
+}
+
+pub extern "C" fn float64_acos(val: f64) -> f64 {
+    val.acos()
+}
+
+pub extern "C" fn float64_atan(val: f64) -> f64 {
+    val.atan()
+}
+
+pub extern "C" fn float64_exp(val: f64) -> f64 {
+    val.exp()
+}
+
+pub extern "C" fn float64_log(val: f64) -> f64 {
+    val.ln()
+}
+
+pub extern "C" fn float64_log2(val: f64) -> f64 {
+    val.log2()
+}
+
+pub extern "C" fn float64_log10(val: f64) -> f64 {
+    val.log10()
+}
+
+pub extern "C" fn float64_cbrt(val: f64) -> f64 {
+    val.cbrt()
+}
+
+pub extern "C" fn float64_atan2(lhs: f64, rhs: f64) -> f64 {
+    lhs.atan2(rhs)
+}
+
+pub extern "C" fn float64_pow(lhs: f64, rhs: f64) -> f64 {
+    lhs.powf(rhs)
+}
+
+pub extern "C" fn float64_hypot(lhs: f64, rhs: f64) -> f64 {
+    lhs.hypot(rhs)
+}
+
+fn float_format(buffer: String) -> Ref<Str> {
+    handle_scope(|| {
+        let vm = get_vm();
+
+        Str::from_buffer(vm, buffer.as_bytes())
+    })
+}
+
 pub extern "C" fn print(val: Handle<Str>) {
     io::stdout().write(val.content()).unwrap();
 }
@@ -111,7 +401,21 @@ pub extern "C" fn println(val: Handle<Str>) {
 
 pub extern "C" fn sleep(seconds: i32) {
     assert!(seconds >= 0);
-    thread::sleep(Duration::from_secs(seconds as u64));
+    blocking(|| thread::sleep(Duration::from_secs(seconds as u64)));
+}
+
+pub extern "C" fn sleep_nanos(nanos: i64) {
+    if nanos > 0 {
+        blocking(|| thread::sleep(Duration::from_nanos(nanos as u64)));
+    }
+}
+
+// nanoseconds since the Unix epoch, negative for earlier system times
+pub extern "C" fn wallclock_nanos() -> i64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_nanos() as i64,
+        Err(err) => -(err.duration().as_nanos() as i64),
+    }
 }
 
 pub extern "C" fn call(fct: Handle<Str>) {
@@ -157,12 +461,8 @@ pub extern "C" fn bytecode(name: Handle<Str>) -> Ref<Obj> {
 }
 
 pub extern "C" fn strcmp(lhs: Handle<Str>, rhs: Handle<Str>) -> i32 {
-    unsafe {
-        libc::strcmp(
-            lhs.data() as *const libc::c_char,
-            rhs.data() as *const libc::c_char,
-        )
-    }
+    // strings are not NUL-terminated, so compare the contents by length
+    lhs.content().cmp(rhs.content()) as i32
 }
 
 pub extern "C" fn strcat(lhs: Handle<Str>, rhs: Handle<Str>) -> Ref<Str> {
@@ -228,113 +528,1133 @@ pub extern "C" fn argv(ind: i32) -> Ref<Str> {
     panic!("argument does not exist");
 }
 
-pub extern "C" fn str_to_int32_success(val: Handle<Str>) -> bool {
-    let slice = val.content();
-    let val = str::from_utf8(slice).unwrap();
+pub extern "C" fn eprint(val: Handle<Str>) {
+    io::stderr().write_all(val.content()).unwrap();
+}
 
-    val.parse::<i32>().is_ok()
+pub extern "C" fn eprintln(val: Handle<Str>) {
+    let stderr = io::stderr();
+    let mut handle = stderr.lock();
+    handle.write_all(val.content()).unwrap();
+    handle.write_all(b"\n").unwrap();
 }
 
-pub extern "C" fn str_to_int32(val: Handle<Str>) -> i32 {
-    let slice = val.content();
-    let val = str::from_utf8(slice).unwrap();
+// runs a blocking operation with the current thread parked, so that it
+// doesn't hold up collections. `f` must not access the heap.
+fn blocking<F: FnOnce() -> R, R>(f: F) -> R {
+    let vm = get_vm();
+    let thread = THREAD.with(|thread| thread.borrow().clone());
 
-    val.parse::<i32>().unwrap_or(0)
+    thread.park(vm);
+    let result = f();
+    thread.unpark(vm);
+
+    result
 }
 
-pub extern "C" fn str_to_int64_success(val: Handle<Str>) -> bool {
-    let slice = val.content();
-    let val = str::from_utf8(slice).unwrap();
+// reads into a temporary buffer while parked, the array might be
+// moved by a collection in the meantime
+fn blocking_read<F>(buffer: Handle<UInt8Array>, offset: i64, len: i64, f: F) -> i64
+where
+    F: FnOnce(&mut [u8]) -> io::Result<usize>,
+{
+    if !io_range_valid(&buffer, offset, len) {
+        return -(libc::EINVAL as i64);
+    }
 
-    val.parse::<i64>().is_ok()
+    let mut data = vec![0; len as usize];
+
+    match blocking(|| f(&mut data)) {
+        Ok(size) => {
+            io_buffer(buffer, offset, size as i64).copy_from_slice(&data[..size]);
+            size as i64
+        }
+
+        Err(err) => -(io_error_code(err) as i64),
+    }
 }
 
-pub extern "C" fn str_to_int64(val: Handle<Str>) -> i64 {
-    let slice = val.content();
-    let val = str::from_utf8(slice).unwrap();
+pub extern "C" fn stdin_read_line() -> Ref<Str> {
+    let mut line = Vec::new();
+    let result = blocking(|| io::stdin().lock().read_until(b'\n', &mut line));
 
-    val.parse::<i64>().unwrap_or(0)
+    match result {
+        Ok(0) | Err(_) => Ref::null(),
+        Ok(_) => {
+            if line.ends_with(b"\n") {
+                line.pop();
+
+                if line.ends_with(b"\r") {
+                    line.pop();
+                }
+            }
+
+            let line = String::from_utf8_lossy(&line);
+            Str::from_buffer(get_vm(), line.as_bytes())
+        }
+    }
 }
 
-pub extern "C" fn trap(trap_id: u32) {
-    let vm = get_vm();
-    let trap = Trap::from(trap_id).expect("invalid trap id!");
+pub extern "C" fn stdin_read(buffer: Handle<UInt8Array>, offset: i64, len: i64) -> i64 {
+    blocking_read(buffer, offset, len, |data| io::stdin().lock().read(data))
+}
 
-    let msg = match trap {
-        Trap::DIV0 => "division by 0",
-        Trap::ASSERT => "assert failed",
-        Trap::INDEX_OUT_OF_BOUNDS => "array index out of bounds",
-        Trap::NIL => "nil check failed",
-        Trap::CAST => "cast failed",
-        Trap::OOM => "out of memory",
-        Trap::STACK_OVERFLOW => "stack overflow",
-    };
+pub extern "C" fn env_get(name: Handle<Str>) -> Ref<Str> {
+    let name = String::from_utf8_lossy(name.content()).into_owned();
 
-    eprintln!("{}", msg);
-    let stacktrace = stacktrace_from_last_dtn(vm);
-    stacktrace.dump_err(vm);
-    unsafe {
-        libc::_exit(100 + trap_id as i32);
+    match env::var_os(name) {
+        Some(value) => Str::from_buffer(get_vm(), value.to_string_lossy().as_bytes()),
+        None => Ref::null(),
     }
 }
 
-pub extern "C" fn spawn_thread(obj: Handle<Obj>) {
-    use crate::compiler;
-    use crate::stack::DoraToNativeInfo;
+pub extern "C" fn env_set(name: Handle<Str>, value: Handle<Str>) {
+    let name = String::from_utf8_lossy(name.content()).into_owned();
+    let value = String::from_utf8_lossy(value.content()).into_owned();
+    env::set_var(name, value);
+}
 
-    let vm = get_vm();
-    let thread = DoraThread::new(vm);
+pub extern "C" fn env_remove(name: Handle<Str>) {
+    let name = String::from_utf8_lossy(name.content()).into_owned();
+    env::remove_var(name);
+}
 
-    vm.threads.attach_thread(thread.clone());
-    let obj = obj.direct();
+// names and values of all variables, read with `string_list_next`
+pub extern "C" fn env_vars() -> i64 {
+    let mut vars: Vec<(String, String)> = env::vars_os()
+        .map(|(name, value)| {
+            (
+                name.to_string_lossy().into_owned(),
+                value.to_string_lossy().into_owned(),
+            )
+        })
+        .collect();
+    vars.sort();
+
+    let list: Vec<String> = vars
+        .into_iter()
+        .flat_map(|(name, value)| vec![name, value])
+        .collect();
+
+    STRING_LISTS.insert(Mutex::new(list.into_iter()))
+}
 
-    thread::spawn(move || {
-        THREAD.with(|tld_thread| {
-            *tld_thread.borrow_mut() = thread;
-        });
+pub extern "C" fn current_directory() -> Ref<Str> {
+    match env::current_dir() {
+        Ok(path) => Str::from_buffer(get_vm(), path.to_string_lossy().as_bytes()),
+        Err(_) => Ref::null(),
+    }
+}
 
-        let stack_top = stack_pointer();
-        let stack_limit = stack_top.sub(STACK_SIZE);
+pub extern "C" fn set_current_directory(path: Handle<Str>) -> i32 {
+    io_status(env::set_current_dir(io_path(path)))
+}
 
-        THREAD.with(|thread| {
-            thread.borrow().tld.set_stack_limit(stack_limit);
-        });
+// Child processes are set up with a `process::Command` and then passed as
+// id of a `ChildProcess`. The pipes are kept apart from the child, so
+// that reading stdout doesn't block writing stdin in another thread.
+struct ChildProcess {
+    child: Mutex<process::Child>,
+    stdin: Mutex<Option<process::ChildStdin>>,
+    stdout: Mutex<Option<process::ChildStdout>>,
+    stderr: Mutex<Option<process::ChildStderr>>,
+}
 
-        let main = {
-            let cls_id = obj.header().vtbl().class().cls_id;
-            let cls_id = cls_id.expect("no corresponding class");
-            let cls = vm.classes.idx(cls_id);
-            let cls = cls.read();
-            let name = vm.interner.intern("run");
-            cls.find_method(vm, name, false)
-                .expect("run() method not found")
-        };
+lazy_static! {
+    static ref COMMANDS: HandleTable<Mutex<process::Command>> = HandleTable::new();
+    static ref PROCESSES: HandleTable<ChildProcess> = HandleTable::new();
+    static ref OUTPUTS: HandleTable<process::Output> = HandleTable::new();
+}
 
-        let tld = THREAD.with(|thread| {
-            let thread = thread.borrow();
-            let ptr = &thread.tld;
+// unknown commands are ignored here, spawning them fails
+fn process_command<F: FnOnce(&mut process::Command)>(handle: i64, f: F) {
+    if let Some(command) = COMMANDS.get(handle) {
+        f(&mut command.lock());
+    }
+}
 
-            Address::from_ptr(ptr as *const _)
-        });
+fn process_stdio(mode: i32) -> process::Stdio {
+    match mode {
+        0 => process::Stdio::inherit(),
+        1 => process::Stdio::piped(),
+        _ => process::Stdio::null(),
+    }
+}
 
-        let fct_ptr = {
-            let mut dtn = DoraToNativeInfo::new();
-            let type_params = TypeList::empty();
+pub extern "C" fn command_new(program: Handle<Str>) -> i64 {
+    let program = String::from_utf8_lossy(program.content()).into_owned();
+    COMMANDS.insert(Mutex::new(process::Command::new(program)))
+}
 
-            THREAD.with(|thread| {
-                thread.borrow().use_dtn(&mut dtn, || {
-                    compiler::generate(vm, main, &type_params, &type_params)
-                })
-            })
-        };
+pub extern "C" fn command_arg(handle: i64, arg: Handle<Str>) {
+    let arg = String::from_utf8_lossy(arg.content()).into_owned();
+    process_command(handle, |command| {
+        command.arg(arg);
+    });
+}
 
-        // execute the thread object's run-method
-        let dora_stub_address = vm.dora_stub();
-        let fct: extern "C" fn(Address, Address, Ref<Obj>) =
-            unsafe { mem::transmute(dora_stub_address) };
-        fct(tld, fct_ptr, obj);
+pub extern "C" fn command_env(handle: i64, name: Handle<Str>, value: Handle<Str>) {
+    let name = String::from_utf8_lossy(name.content()).into_owned();
+    let value = String::from_utf8_lossy(value.content()).into_owned();
+    process_command(handle, |command| {
+        command.env(name, value);
+    });
+}
 
-        // remove thread from list of all threads
-        vm.threads.detach_current_thread();
+pub extern "C" fn command_directory(handle: i64, path: Handle<Str>) {
+    let path = io_path(path);
+    process_command(handle, |command| {
+        command.current_dir(path);
+    });
+}
+
+pub extern "C" fn command_stdio(handle: i64, stdin: i32, stdout: i32, stderr: i32) {
+    process_command(handle, |command| {
+        command
+            .stdin(process_stdio(stdin))
+            .stdout(process_stdio(stdout))
+            .stderr(process_stdio(stderr));
+    });
+}
+
+// consumes the command
+pub extern "C" fn command_spawn(handle: i64) -> i64 {
+    let command = match COMMANDS.remove(handle) {
+        Some(command) => command,
+        None => return -(libc::EBADF as i64),
+    };
+
+    let result = command.lock().spawn();
+
+    match result {
+        Ok(mut child) => PROCESSES.insert(ChildProcess {
+            stdin: Mutex::new(child.stdin.take()),
+            stdout: Mutex::new(child.stdout.take()),
+            stderr: Mutex::new(child.stderr.take()),
+            child: Mutex::new(child),
+        }),
+        Err(err) => -(io_error_code(err) as i64),
+    }
+}
+
+pub extern "C" fn process_id(handle: i64) -> i32 {
+    match PROCESSES.get(handle) {
+        Some(process) => process.child.lock().id() as i32,
+        None => -libc::EBADF,
+    }
+}
+
+pub extern "C" fn process_write_stdin(
+    handle: i64,
+    buffer: Handle<UInt8Array>,
+    offset: i64,
+    len: i64,
+) -> i64 {
+    if !io_range_valid(&buffer, offset, len) {
+        return -(libc::EINVAL as i64);
+    }
+
+    let process = match PROCESSES.get(handle) {
+        Some(process) => process,
+        None => return -(libc::EBADF as i64),
+    };
+
+    let data = io_buffer(buffer, offset, len).to_vec();
+
+    let result = blocking(|| match process.stdin.lock().as_mut() {
+        Some(stdin) => stdin.write(&data),
+        None => Err(io::Error::from_raw_os_error(libc::EBADF)),
     });
+
+    io_size(result.map(|size| size as u64))
+}
+
+pub extern "C" fn process_close_stdin(handle: i64) {
+    if let Some(process) = PROCESSES.get(handle) {
+        process.stdin.lock().take();
+    }
+}
+
+// reads from stdout if `stream` is 1, otherwise from stderr
+pub extern "C" fn process_read(
+    handle: i64,
+    stream: i32,
+    buffer: Handle<UInt8Array>,
+    offset: i64,
+    len: i64,
+) -> i64 {
+    let process = match PROCESSES.get(handle) {
+        Some(process) => process,
+        None => return -(libc::EBADF as i64),
+    };
+
+    blocking_read(buffer, offset, len, |data| {
+        let pipe = if stream == 1 {
+            process.stdout.lock().as_mut().map(|pipe| pipe.read(data))
+        } else {
+            process.stderr.lock().as_mut().map(|pipe| pipe.read(data))
+        };
+
+        pipe.unwrap_or_else(|| Err(io::Error::from_raw_os_error(libc::EBADF)))
+    })
+}
+
+// returns the exit code, or 128 plus the signal number for
+// processes killed by a signal
+pub extern "C" fn process_wait(handle: i64) -> i64 {
+    let process = match PROCESSES.get(handle) {
+        Some(process) => process,
+        None => return -(libc::EBADF as i64),
+    };
+
+    process.stdin.lock().take();
+
+    // polls such that the process can be killed in the meantime
+    let result = blocking(|| loop {
+        match process.child.lock().try_wait() {
+            Ok(Some(status)) => return Ok(status),
+            Ok(None) => {}
+            Err(err) => return Err(err),
+        }
+
+        thread::sleep(Duration::from_millis(1));
+    });
+
+    match result {
+        Ok(status) => process_exit_code(status) as i64,
+        Err(err) => -(io_error_code(err) as i64),
+    }
+}
+
+#[cfg(unix)]
+fn process_exit_code(status: process::ExitStatus) -> i32 {
+    use std::os::unix::process::ExitStatusExt;
+
+    match status.code() {
+        Some(code) => code,
+        None => 128 + status.signal().unwrap_or(0),
+    }
+}
+
+#[cfg(not(unix))]
+fn process_exit_code(status: process::ExitStatus) -> i32 {
+    status.code().unwrap_or(1)
+}
+
+pub extern "C" fn process_kill(handle: i64) -> i32 {
+    match PROCESSES.get(handle) {
+        Some(process) => io_status(process.child.lock().kill()),
+        None => -libc::EBADF,
+    }
+}
+
+pub extern "C" fn process_drop(handle: i64) {
+    PROCESSES.remove(handle);
+}
+
+// waits for the process and collects everything it writes to stdout
+// and stderr, consumes the process and returns the id of a `process::Output`
+pub extern "C" fn process_output(handle: i64) -> i64 {
+    let process = match PROCESSES.remove(handle).map(Arc::try_unwrap) {
+        Some(Ok(process)) => process,
+        // still used by another thread
+        Some(Err(_)) | None => return -(libc::EBADF as i64),
+    };
+
+    let mut child = process.child.into_inner();
+    child.stdin = process.stdin.into_inner();
+    child.stdout = process.stdout.into_inner();
+    child.stderr = process.stderr.into_inner();
+
+    match blocking(|| child.wait_with_output()) {
+        Ok(output) => OUTPUTS.insert(output),
+        Err(err) => -(io_error_code(err) as i64),
+    }
+}
+
+fn process_output_stream(output: &process::Output, stream: i32) -> &[u8] {
+    if stream == 1 {
+        &output.stdout
+    } else {
+        &output.stderr
+    }
+}
+
+pub extern "C" fn output_status(handle: i64) -> i32 {
+    match OUTPUTS.get(handle) {
+        Some(output) => process_exit_code(output.status),
+        None => -libc::EBADF,
+    }
+}
+
+pub extern "C" fn output_size(handle: i64, stream: i32) -> i64 {
+    match OUTPUTS.get(handle) {
+        Some(output) => process_output_stream(&output, stream).len() as i64,
+        None => -(libc::EBADF as i64),
+    }
+}
+
+// copies as much as fits into the buffer
+pub extern "C" fn output_copy(handle: i64, stream: i32, buffer: Handle<UInt8Array>) {
+    if let Some(output) = OUTPUTS.get(handle) {
+        let data = process_output_stream(&output, stream);
+        let len = data.len().min(buffer.len());
+        io_buffer(buffer, 0, len as i64).copy_from_slice(&data[..len]);
+    }
+}
+
+pub extern "C" fn output_drop(handle: i64) {
+    OUTPUTS.remove(handle);
+}
+
+pub extern "C" fn str_to_int32_success(val: Handle<Str>) -> bool {
+    let slice = val.content();
+    let val = str::from_utf8(slice).unwrap();
+
+    val.parse::<i32>().is_ok()
+}
+
+pub extern "C" fn str_to_int32(val: Handle<Str>) -> i32 {
+    let slice = val.content();
+    let val = str::from_utf8(slice).unwrap();
+
+    val.parse::<i32>().unwrap_or(0)
+}
+
+pub extern "C" fn str_to_int64_success(val: Handle<Str>) -> bool {
+    let slice = val.content();
+    let val = str::from_utf8(slice).unwrap();
+
+    val.parse::<i64>().is_ok()
+}
+
+pub extern "C" fn str_to_int64(val: Handle<Str>) -> i64 {
+    let slice = val.content();
+    let val = str::from_utf8(slice).unwrap();
+
+    val.parse::<i64>().unwrap_or(0)
+}
+
+pub extern "C" fn str_to_float32_success(val: Handle<Str>) -> bool {
+    let slice = val.content();
+    let val = str::from_utf8(slice).unwrap();
+
+    val.parse::<f32>().is_ok()
+}
+
+pub extern "C" fn str_to_float32(val: Handle<Str>) -> f32 {
+    let slice = val.content();
+    let val = str::from_utf8(slice).unwrap();
+
+    val.parse::<f32>().unwrap_or(0.0)
+}
+
+pub extern "C" fn str_to_float64_success(val: Handle<Str>) -> bool {
+    let slice = val.content();
+    let val = str::from_utf8(slice).unwrap();
+
+    val.parse::<f64>().is_ok()
+}
+
+pub extern "C" fn str_to_float64(val: Handle<Str>) -> f64 {
+    let slice = val.content();
+    let val = str::from_utf8(slice).unwrap();
+
+    val.parse::<f64>().unwrap_or(0.0)
+}
+
+pub extern "C" fn str_index_of(val: Handle<Str>, needle: Handle<Str>, from: i64) -> i64 {
+    let haystack = val.content();
+    let from = from as usize;
+
+    if from > haystack.len() {
+        return -1;
+    }
+
+    match find_bytes(&haystack[from..], needle.content()) {
+        Some(idx) => (from + idx) as i64,
+        None => -1,
+    }
+}
+
+pub extern "C" fn str_last_index_of(val: Handle<Str>, needle: Handle<Str>) -> i64 {
+    let haystack = val.content();
+    let needle = needle.content();
+
+    if needle.len() > haystack.len() {
+        return -1;
+    }
+
+    let last = haystack.len() - needle.len();
+
+    (0..=last)
+        .rev()
+        .find(|&idx| &haystack[idx..idx + needle.len()] == needle)
+        .map_or(-1, |idx| idx as i64)
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.is_empty() {
+        return Some(0);
+    }
+
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+pub extern "C" fn str_starts_with(val: Handle<Str>, prefix: Handle<Str>) -> bool {
+    val.content().starts_with(prefix.content())
+}
+
+pub extern "C" fn str_ends_with(val: Handle<Str>, suffix: Handle<Str>) -> bool {
+    val.content().ends_with(suffix.content())
+}
+
+pub extern "C" fn str_trim(val: Handle<Str>) -> Ref<Str> {
+    str_trim_with(val, str::trim)
+}
+
+pub extern "C" fn str_trim_start(val: Handle<Str>) -> Ref<Str> {
+    str_trim_with(val, str::trim_start)
+}
+
+pub extern "C" fn str_trim_end(val: Handle<Str>) -> Ref<Str> {
+    str_trim_with(val, str::trim_end)
+}
+
+fn str_trim_with(val: Handle<Str>, trim: fn(&str) -> &str) -> Ref<Str> {
+    handle_scope(|| {
+        let vm = get_vm();
+        let content = str::from_utf8(val.content()).unwrap();
+        let trimmed = trim(content);
+        let offset = trimmed.as_ptr() as usize - content.as_ptr() as usize;
+
+        // copies the trimmed range straight out of `val`
+        Str::from_str(vm, val, offset, trimmed.len())
+    })
+}
+
+pub extern "C" fn str_replace(val: Handle<Str>, from: Handle<Str>, to: Handle<Str>) -> Ref<Str> {
+    handle_scope(|| {
+        let vm = get_vm();
+        let content = str::from_utf8(val.content()).unwrap();
+        let from = str::from_utf8(from.content()).unwrap();
+        let to = str::from_utf8(to.content()).unwrap();
+
+        let result = if from.is_empty() {
+            content.to_owned()
+        } else {
+            content.replace(from, to)
+        };
+
+        Str::from_buffer(vm, result.as_bytes())
+    })
+}
+
+pub extern "C" fn str_repeat(val: Handle<Str>, count: i64) -> Ref<Str> {
+    let count = count.max(0) as usize;
+
+    match val.len().checked_mul(count) {
+        Some(len) if len <= isize::MAX as usize => {}
+        _ => trap(Trap::OOM.int()),
+    }
+
+    handle_scope(|| Str::repeat(get_vm(), val, count))
+}
+
+pub extern "C" fn str_to_uppercase(val: Handle<Str>) -> Ref<Str> {
+    handle_scope(|| {
+        let vm = get_vm();
+        let content = str::from_utf8(val.content()).unwrap();
+
+        Str::from_buffer(vm, content.to_uppercase().as_bytes())
+    })
+}
+
+pub extern "C" fn str_to_lowercase(val: Handle<Str>) -> Ref<Str> {
+    handle_scope(|| {
+        let vm = get_vm();
+        let content = str::from_utf8(val.content()).unwrap();
+
+        Str::from_buffer(vm, content.to_lowercase().as_bytes())
+    })
+}
+
+pub extern "C" fn char_is_alphabetic(val: u32) -> bool {
+    char_from_u32(val).map_or(false, |val| val.is_alphabetic())
+}
+
+pub extern "C" fn char_is_numeric(val: u32) -> bool {
+    char_from_u32(val).map_or(false, |val| val.is_numeric())
+}
+
+pub extern "C" fn char_is_alphanumeric(val: u32) -> bool {
+    char_from_u32(val).map_or(false, |val| val.is_alphanumeric())
+}
+
+pub extern "C" fn char_is_whitespace(val: u32) -> bool {
+    char_from_u32(val).map_or(false, |val| val.is_whitespace())
+}
+
+pub extern "C" fn char_is_control(val: u32) -> bool {
+    char_from_u32(val).map_or(false, |val| val.is_control())
+}
+
+pub extern "C" fn char_is_uppercase(val: u32) -> bool {
+    char_from_u32(val).map_or(false, |val| val.is_uppercase())
+}
+
+pub extern "C" fn char_is_lowercase(val: u32) -> bool {
+    char_from_u32(val).map_or(false, |val| val.is_lowercase())
+}
+
+// Characters without a single-character mapping (like 'ß') and invalid
+// chars are returned unchanged.
+pub extern "C" fn char_to_uppercase(val: u32) -> u32 {
+    char_from_u32(val)
+        .and_then(|ch| single_char(ch.to_uppercase()))
+        .map_or(val, |ch| ch as u32)
+}
+
+pub extern "C" fn char_to_lowercase(val: u32) -> u32 {
+    char_from_u32(val)
+        .and_then(|ch| single_char(ch.to_lowercase()))
+        .map_or(val, |ch| ch as u32)
+}
+
+fn single_char(mut chars: impl Iterator<Item = char>) -> Option<char> {
+    let first = chars.next()?;
+
+    if chars.next().is_none() {
+        Some(first)
+    } else {
+        None
+    }
+}
+
+pub extern "C" fn trap(trap_id: u32) {
+    let vm = get_vm();
+    let trap = Trap::from(trap_id).expect("invalid trap id!");
+
+    let msg = match trap {
+        Trap::DIV0 => "division by 0",
+        Trap::ASSERT => "assert failed",
+        Trap::INDEX_OUT_OF_BOUNDS => "array index out of bounds",
+        Trap::NIL => "nil check failed",
+        Trap::CAST => "cast failed",
+        Trap::OOM => "out of memory",
+        Trap::STACK_OVERFLOW => "stack overflow",
+    };
+
+    eprintln!("{}", msg);
+    let stacktrace = stacktrace_from_last_dtn(vm);
+    stacktrace.dump_err(vm);
+    unsafe {
+        libc::_exit(100 + trap_id as i32);
+    }
+}
+
+pub extern "C" fn spawn_thread(obj: Handle<Obj>) {
+    use crate::compiler;
+    use crate::stack::DoraToNativeInfo;
+
+    let vm = get_vm();
+    let thread = DoraThread::new(vm);
+
+    let cls_id = vm.cls_def_by_name("Thread");
+    let fid = vm.field_in_class(cls_id, "id");
+    object::write_int64(vm, obj.direct(), cls_id, fid, thread.id() as i64);
+
+    // thread object is a root as soon as the thread is attached
+    thread.set_object(obj.direct().address());
+    vm.threads.attach_thread(thread.clone());
+
+    thread::spawn(move || {
+        THREAD.with(|tld_thread| {
+            *tld_thread.borrow_mut() = thread;
+        });
+
+        let stack_top = stack_pointer();
+        let stack_limit = stack_top.sub(STACK_SIZE);
+
+        THREAD.with(|thread| {
+            thread.borrow().tld.set_stack_limit(stack_limit);
+        });
+
+        let (main, cls_type_params) = {
+            let obj = THREAD.with(|thread| thread.borrow().object());
+            let obj: Ref<Obj> = obj.to_usize().into();
+            let cls_def = obj.header().vtbl().class();
+            let cls_id = cls_def.cls_id.expect("no corresponding class");
+            let list_id = vm.lists.lock().insert(cls_def.type_params.clone());
+            let name = vm.interner.intern("run");
+
+            // run() might be inherited from a generic superclass
+            let candidates =
+                find_methods_in_class(vm, BuiltinType::Class(cls_id, list_id), name, false);
+            let &(class_type, fct_id) = candidates.first().expect("run() method not found");
+            (fct_id, class_type.type_params(vm))
+        };
+
+        let tld = THREAD.with(|thread| {
+            let thread = thread.borrow();
+            let ptr = &thread.tld;
+
+            Address::from_ptr(ptr as *const _)
+        });
+
+        let fct_ptr = {
+            let mut dtn = DoraToNativeInfo::new();
+            let fct_type_params = TypeList::empty();
+
+            THREAD.with(|thread| {
+                thread.borrow().use_dtn(&mut dtn, || {
+                    compiler::generate(vm, main, &cls_type_params, &fct_type_params)
+                })
+            })
+        };
+
+        // object might have been moved during compilation
+        let obj = THREAD.with(|thread| thread.borrow().object());
+        let obj: Ref<Obj> = obj.to_usize().into();
+
+        // execute the thread object's run-method
+        let dora_stub_address = vm.dora_stub();
+        let fct: extern "C" fn(Address, Address, Ref<Obj>) =
+            unsafe { mem::transmute(dora_stub_address) };
+        fct(tld, fct_ptr, obj);
+
+        // remove thread from list of all threads
+        THREAD.with(|thread| vm.monitors.detach_thread(&thread.borrow()));
+        vm.threads.detach_current_thread();
+    });
+}
+
+pub extern "C" fn join_thread(obj: Handle<Obj>) {
+    let vm = get_vm();
+    let cls_id = vm.cls_def_by_name("Thread");
+    let fid = vm.field_in_class(cls_id, "id");
+    let id = object::read_int64(vm, obj.direct(), cls_id, fid);
+
+    // thread wasn't started yet
+    if id < 0 {
+        return;
+    }
+
+    vm.threads.join(vm, id as usize);
+}
+
+pub extern "C" fn mutex_lock(mutex: Handle<Obj>) {
+    let vm = get_vm();
+
+    vm.monitors.lock(vm, mutex.direct());
+}
+
+pub extern "C" fn mutex_try_lock(mutex: Handle<Obj>) -> bool {
+    let vm = get_vm();
+
+    vm.monitors.try_lock(vm, mutex.direct())
+}
+
+pub extern "C" fn mutex_unlock(mutex: Handle<Obj>) {
+    let vm = get_vm();
+
+    if let Err(err) = vm.monitors.unlock(vm, mutex.direct()) {
+        monitor_error(vm, err);
+    }
+}
+
+pub extern "C" fn condition_wait(cond: Handle<Obj>, mutex: Handle<Obj>) {
+    let vm = get_vm();
+
+    if let Err(err) = vm.monitors.wait(vm, cond.direct(), mutex.direct(), None) {
+        monitor_error(vm, err);
+    }
+}
+
+pub extern "C" fn condition_wait_timeout(
+    cond: Handle<Obj>,
+    mutex: Handle<Obj>,
+    millis: i64,
+) -> bool {
+    let vm = get_vm();
+    let timeout = Duration::from_millis(millis.max(0) as u64);
+
+    match vm
+        .monitors
+        .wait(vm, cond.direct(), mutex.direct(), Some(timeout))
+    {
+        Ok(notified) => notified,
+        Err(err) => monitor_error(vm, err),
+    }
+}
+
+pub extern "C" fn condition_notify_one(cond: Handle<Obj>) {
+    let vm = get_vm();
+    vm.monitors.notify(vm, cond.direct(), false);
+}
+
+pub extern "C" fn condition_notify_all(cond: Handle<Obj>) {
+    let vm = get_vm();
+    vm.monitors.notify(vm, cond.direct(), true);
+}
+
+fn monitor_error(vm: &VM, err: MonitorError) -> ! {
+    match err {
+        MonitorError::NotOwner => {
+            eprintln!("fatal error: mutex not locked by current thread");
+        }
+    }
+
+    let stacktrace = stacktrace_from_last_dtn(vm);
+    stacktrace.dump_err(vm);
+
+    process::exit(1);
+}
+
+pub extern "C" fn current_thread() -> Ref<Obj> {
+    handle_scope(|| {
+        let vm = get_vm();
+        let thread = THREAD.with(|thread| thread.borrow().clone());
+
+        if thread.object().is_null() {
+            // only the main thread starts without Thread object
+            let cls_id = vm.cls_def_by_name("Thread");
+            let obj = root(object::alloc(vm, cls_id));
+            let name = Str::from_buffer(vm, b"main");
+
+            let fid = vm.field_in_class(cls_id, "name");
+            object::write_ref(vm, obj.direct(), cls_id, fid, name.cast());
+            let fid = vm.field_in_class(cls_id, "id");
+            object::write_int64(vm, obj.direct(), cls_id, fid, thread.id() as i64);
+
+            thread.set_object(obj.direct().address());
+        }
+
+        thread.object().to_usize().into()
+    })
+}
+
+// Files, processes and other resources are owned by tables on the Rust
+// side, Dora code only gets their ids. Unknown ids, e.g. of already closed
+// files, are rejected instead of being dereferenced.
+struct HandleTable<T> {
+    entries: Mutex<HashMap<i64, Arc<T>>>,
+    next_id: AtomicI64,
+}
+
+impl<T> HandleTable<T> {
+    fn new() -> HandleTable<T> {
+        HandleTable {
+            entries: Mutex::new(HashMap::new()),
+            // ids are positive, negative results are error codes
+            next_id: AtomicI64::new(1),
+        }
+    }
+
+    fn insert(&self, value: T) -> i64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.entries.lock().insert(id, Arc::new(value));
+        id
+    }
+
+    // the table isn't locked while the value is used, blocking calls
+    // don't hold up other threads
+    fn get(&self, id: i64) -> Option<Arc<T>> {
+        self.entries.lock().get(&id).cloned()
+    }
+
+    fn remove(&self, id: i64) -> Option<Arc<T>> {
+        self.entries.lock().remove(&id)
+    }
+}
+
+lazy_static! {
+    static ref FILES: HandleTable<fs::File> = HandleTable::new();
+    static ref STRING_LISTS: HandleTable<Mutex<vec::IntoIter<String>>> = HandleTable::new();
+}
+
+// Natives of the file system API: files and directory listings are passed
+// to Dora as ids in a handle table, failures as negated OS error codes.
+
+// error code of `File::invalidData()`
+const IO_INVALID_DATA: i32 = -1;
+
+fn io_error_code(err: io::Error) -> i32 {
+    err.raw_os_error().unwrap_or_else(|| match err.kind() {
+        io::ErrorKind::InvalidInput => libc::EINVAL,
+        _ => libc::EIO,
+    })
+}
+
+fn io_status(result: io::Result<()>) -> i32 {
+    match result {
+        Ok(()) => 0,
+        Err(err) => -io_error_code(err),
+    }
+}
+
+fn io_size(result: io::Result<u64>) -> i64 {
+    match result {
+        Ok(size) => size as i64,
+        Err(err) => -(io_error_code(err) as i64),
+    }
+}
+
+fn io_path(path: Handle<Str>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(path.content()).into_owned())
+}
+
+// natives can also be called directly, not only through the
+// wrappers that check the range
+fn io_range_valid(buffer: &Handle<UInt8Array>, offset: i64, len: i64) -> bool {
+    offset >= 0 && len >= 0 && offset <= buffer.len() as i64 - len
+}
+
+fn io_buffer<'a>(mut buffer: Handle<UInt8Array>, offset: i64, len: i64) -> &'a mut [u8] {
+    // the thread doesn't reach a safepoint in natives, the
+    // array can't be moved while it is accessed
+    unsafe { slice::from_raw_parts_mut(buffer.data_mut().offset(offset as isize), len as usize) }
+}
+
+pub extern "C" fn file_open(path: Handle<Str>, mode: i32) -> i64 {
+    let mut options = fs::OpenOptions::new();
+    options
+        .read(mode & 1 != 0)
+        .write(mode & 2 != 0)
+        .append(mode & 4 != 0)
+        .create(mode & 8 != 0)
+        .truncate(mode & 16 != 0);
+
+    match options.open(io_path(path)) {
+        Ok(file) => FILES.insert(file),
+        Err(err) => -(io_error_code(err) as i64),
+    }
+}
+
+pub extern "C" fn file_read(handle: i64, buffer: Handle<UInt8Array>, offset: i64, len: i64) -> i64 {
+    let file = match FILES.get(handle) {
+        Some(file) => file,
+        None => return -(libc::EBADF as i64),
+    };
+
+    blocking_read(buffer, offset, len, |data| (&*file).read(data))
+}
+
+// also used for strings, which have the same layout as byte arrays
+pub extern "C" fn file_write(
+    handle: i64,
+    buffer: Handle<UInt8Array>,
+    offset: i64,
+    len: i64,
+) -> i64 {
+    if !io_range_valid(&buffer, offset, len) {
+        return -(libc::EINVAL as i64);
+    }
+
+    let file = match FILES.get(handle) {
+        Some(file) => file,
+        None => return -(libc::EBADF as i64),
+    };
+
+    let data = io_buffer(buffer, offset, len).to_vec();
+    io_size(blocking(|| (&*file).write(&data)).map(|size| size as u64))
+}
+
+pub extern "C" fn file_seek(handle: i64, offset: i64, origin: i32) -> i64 {
+    let position = match origin {
+        0 if offset < 0 => return -(libc::EINVAL as i64),
+        0 => SeekFrom::Start(offset as u64),
+        1 => SeekFrom::Current(offset),
+        _ => SeekFrom::End(offset),
+    };
+
+    match FILES.get(handle) {
+        Some(file) => io_size((&*file).seek(position)),
+        None => -(libc::EBADF as i64),
+    }
+}
+
+pub extern "C" fn file_close(handle: i64) -> i32 {
+    match FILES.remove(handle) {
+        Some(_) => 0,
+        None => -libc::EBADF,
+    }
+}
+
+pub extern "C" fn file_metadata(path: Handle<Str>, mut data: Handle<Int64Array>) -> i32 {
+    let metadata = match fs::metadata(io_path(path)) {
+        Ok(metadata) => metadata,
+        Err(err) => return -io_error_code(err),
+    };
+
+    let kind = if metadata.is_file() {
+        0
+    } else if metadata.is_dir() {
+        1
+    } else {
+        2
+    };
+
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or(0);
+
+    data.set_at(0, kind);
+    data.set_at(1, metadata.len() as i64);
+    data.set_at(2, modified);
+    data.set_at(3, metadata.permissions().readonly() as i64);
+
+    0
+}
+
+pub extern "C" fn file_remove(path: Handle<Str>) -> i32 {
+    io_status(fs::remove_file(io_path(path)))
+}
+
+pub extern "C" fn file_rename(from: Handle<Str>, to: Handle<Str>) -> i32 {
+    io_status(fs::rename(io_path(from), io_path(to)))
+}
+
+pub extern "C" fn directory_create(path: Handle<Str>, recursive: bool) -> i32 {
+    if recursive {
+        io_status(fs::create_dir_all(io_path(path)))
+    } else {
+        io_status(fs::create_dir(io_path(path)))
+    }
+}
+
+pub extern "C" fn directory_remove(path: Handle<Str>, recursive: bool) -> i32 {
+    if recursive {
+        io_status(fs::remove_dir_all(io_path(path)))
+    } else {
+        io_status(fs::remove_dir(io_path(path)))
+    }
+}
+
+pub extern "C" fn directory_read(path: Handle<Str>) -> i64 {
+    let entries = match fs::read_dir(io_path(path)) {
+        Ok(entries) => entries,
+        Err(err) => return -(io_error_code(err) as i64),
+    };
+
+    let mut names = Vec::new();
+
+    for entry in entries {
+        match entry {
+            Ok(entry) => names.push(entry.file_name().to_string_lossy().into_owned()),
+            Err(err) => return -(io_error_code(err) as i64),
+        }
+    }
+
+    names.sort();
+
+    STRING_LISTS.insert(Mutex::new(names.into_iter()))
+}
+
+// lists of strings are passed as id of an iterator, unknown
+// lists don't have any elements
+pub extern "C" fn string_list_next(handle: i64) -> Ref<Str> {
+    let next = STRING_LISTS
+        .get(handle)
+        .and_then(|entries| entries.lock().next());
+
+    match next {
+        Some(name) => Str::from_buffer(get_vm(), name.as_bytes()),
+        None => Ref::null(),
+    }
+}
+
+pub extern "C" fn string_list_close(handle: i64) {
+    STRING_LISTS.remove(handle);
+}
+
+pub extern "C" fn io_error_message(code: i32) -> Ref<Str> {
+    let message = if code == IO_INVALID_DATA {
+        "stream did not contain valid UTF-8".into()
+    } else {
+        io::Error::from_raw_os_error(code).to_string()
+    };
+
+    // drop the " (os error N)" suffix
+    let message = message.split(" (os error").next().unwrap();
+
+    Str::from_buffer(get_vm(), message.as_bytes())
+}
+
+// Sockets are only supported on Linux and macOS, other systems fail all
+// calls. Addresses are parsed the same everywhere.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+mod net;
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+mod net_unsupported;
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub use self::net::*;
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub use self::net_unsupported::*;
+
+fn net_parse(address: Handle<Str>) -> Option<SocketAddr> {
+    str::from_utf8(address.content()).ok()?.parse().ok()
+}
+
+// returns the canonical ip and writes the port into `port`
+pub extern "C" fn net_parse_address(value: Handle<Str>, mut port: Handle<Int64Array>) -> Ref<Str> {
+    match net_parse(value) {
+        Some(address) => {
+            port.set_at(0, address.port() as i64);
+            Str::from_buffer(get_vm(), address.ip().to_string().as_bytes())
+        }
+
+        None => Ref::null(),
+    }
+}
+
+pub extern "C" fn sort_primitive(array: Handle<Obj>, from: i64, to: i64) -> bool {
+    if array.direct().raw().is_null() {
+        trap(Trap::NIL.int());
+    }
+
+    let element_type = array.header().vtbl().class().type_params[0];
+    let (from, to) = (from as usize, to as usize);
+
+    match element_type {
+        BuiltinType::Bool => sort_slice(array.cast::<object::BoolArray>(), from, to, |v| *v),
+        BuiltinType::UInt8 => sort_slice(array.cast::<object::UInt8Array>(), from, to, |v| *v),
+        BuiltinType::Char => sort_slice(array.cast::<object::CharArray>(), from, to, |v| *v),
+        BuiltinType::Int32 => sort_slice(array.cast::<object::Int32Array>(), from, to, |v| *v),
+        BuiltinType::Int64 => sort_slice(array.cast::<object::Int64Array>(), from, to, |v| *v),
+        BuiltinType::Float32 => sort_slice(array.cast::<object::Float32Array>(), from, to, |v| {
+            // same total order as Float32.sortsAs
+            let bits = v.to_bits() as i32;
+            bits ^ (((bits >> 31) as u32) >> 1) as i32
+        }),
+        BuiltinType::Float64 => sort_slice(array.cast::<object::Float64Array>(), from, to, |v| {
+            // same total order as Float64.sortsAs
+            let bits = v.to_bits() as i64;
+            bits ^ (((bits >> 63) as u64) >> 1) as i64
+        }),
+        _ => return false,
+    }
+
+    true
+}
+
+fn sort_slice<T: Copy + ArrayElement, K: Ord>(
+    mut array: Handle<object::Array<T>>,
+    from: usize,
+    to: usize,
+    key: impl FnMut(&T) -> K,
+) {
+    // negative values were converted to large ones
+    if from > to || to > array.len() {
+        trap(Trap::INDEX_OUT_OF_BOUNDS.int());
+    }
+
+    let data = unsafe { slice::from_raw_parts_mut(array.data_mut(), array.len()) };
+    data[from..to].sort_unstable_by_key(key);
 }
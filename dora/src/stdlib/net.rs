@@ -0,0 +1,513 @@
+// Sockets are plain file descriptors, failing calls return the negated
+// error code. Timeouts of blocking calls are reported as `ETIMEDOUT`.
+
+use libc;
+
+use std::io;
+use std::mem;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6};
+use std::ptr;
+
+use crate::handle::Handle;
+use crate::object::{Int64Array, Ref, Str, UInt8Array};
+use crate::stdlib::{blocking, blocking_read, io_buffer, io_range_valid, net_parse};
+use crate::vm::get_vm;
+
+// Linux sets close-on-exec while creating the socket and suppresses
+// SIGPIPE for each call, macOS needs fcntl and the SO_NOSIGPIPE option.
+#[cfg(target_os = "linux")]
+const NET_SOCKET_FLAGS: libc::c_int = libc::SOCK_CLOEXEC;
+#[cfg(target_os = "macos")]
+const NET_SOCKET_FLAGS: libc::c_int = 0;
+
+#[cfg(target_os = "linux")]
+const NET_SEND_FLAGS: libc::c_int = libc::MSG_NOSIGNAL;
+#[cfg(target_os = "macos")]
+const NET_SEND_FLAGS: libc::c_int = 0;
+
+#[cfg(target_os = "linux")]
+fn net_configure(_fd: libc::c_int) {}
+
+#[cfg(target_os = "macos")]
+fn net_configure(fd: libc::c_int) {
+    let enable: libc::c_int = 1;
+
+    unsafe {
+        libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+        libc::setsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_NOSIGPIPE,
+            &enable as *const _ as *const libc::c_void,
+            mem::size_of::<libc::c_int>() as libc::socklen_t,
+        );
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn net_accept(fd: libc::c_int) -> libc::c_int {
+    unsafe { libc::accept4(fd, ptr::null_mut(), ptr::null_mut(), libc::SOCK_CLOEXEC) }
+}
+
+#[cfg(target_os = "macos")]
+fn net_accept(fd: libc::c_int) -> libc::c_int {
+    let result = unsafe { libc::accept(fd, ptr::null_mut(), ptr::null_mut()) };
+
+    if result >= 0 {
+        net_configure(result);
+    }
+
+    result
+}
+
+fn net_error() -> i64 {
+    let code = io::Error::last_os_error()
+        .raw_os_error()
+        .unwrap_or(libc::EIO);
+
+    if code == libc::EAGAIN || code == libc::EWOULDBLOCK {
+        -(libc::ETIMEDOUT as i64)
+    } else {
+        -(code as i64)
+    }
+}
+
+// errno needs to be read right after the call, unparking the
+// thread might overwrite it
+fn net_result(result: isize) -> i64 {
+    if result < 0 {
+        net_error()
+    } else {
+        result as i64
+    }
+}
+
+fn net_sockaddr(address: &SocketAddr) -> (libc::sockaddr_storage, libc::socklen_t) {
+    let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+
+    let len = match address {
+        SocketAddr::V4(address) => {
+            let sin = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in) };
+            sin.sin_family = libc::AF_INET as libc::sa_family_t;
+            sin.sin_port = address.port().to_be();
+            sin.sin_addr = libc::in_addr {
+                s_addr: u32::from_ne_bytes(address.ip().octets()),
+            };
+            mem::size_of::<libc::sockaddr_in>()
+        }
+
+        SocketAddr::V6(address) => {
+            let sin6 = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in6) };
+            sin6.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+            sin6.sin6_port = address.port().to_be();
+            sin6.sin6_addr.s6_addr = address.ip().octets();
+            sin6.sin6_flowinfo = address.flowinfo();
+            sin6.sin6_scope_id = address.scope_id();
+            mem::size_of::<libc::sockaddr_in6>()
+        }
+    };
+
+    (storage, len as libc::socklen_t)
+}
+
+fn net_socket_addr(storage: &libc::sockaddr_storage) -> Option<SocketAddr> {
+    match storage.ss_family as i32 {
+        libc::AF_INET => {
+            let sin = unsafe { &*(storage as *const _ as *const libc::sockaddr_in) };
+            let ip = Ipv4Addr::from(sin.sin_addr.s_addr.to_ne_bytes());
+            Some(SocketAddr::new(ip.into(), u16::from_be(sin.sin_port)))
+        }
+
+        libc::AF_INET6 => {
+            let sin6 = unsafe { &*(storage as *const _ as *const libc::sockaddr_in6) };
+            let ip = Ipv6Addr::from(sin6.sin6_addr.s6_addr);
+            let port = u16::from_be(sin6.sin6_port);
+            let address = SocketAddrV6::new(ip, port, sin6.sin6_flowinfo, sin6.sin6_scope_id);
+            Some(address.into())
+        }
+
+        _ => None,
+    }
+}
+
+fn net_address_str(address: Option<SocketAddr>) -> Ref<Str> {
+    match address {
+        Some(address) => Str::from_buffer(get_vm(), address.to_string().as_bytes()),
+        None => Ref::null(),
+    }
+}
+
+fn net_socket(address: &SocketAddr, kind: libc::c_int) -> i64 {
+    let family = match address {
+        SocketAddr::V4(_) => libc::AF_INET,
+        SocketAddr::V6(_) => libc::AF_INET6,
+    };
+
+    let fd = unsafe { libc::socket(family, kind | NET_SOCKET_FLAGS, 0) };
+
+    if fd < 0 {
+        return net_error();
+    }
+
+    net_configure(fd);
+    fd as i64
+}
+
+fn net_bind(fd: i64, address: &SocketAddr) -> i64 {
+    let (storage, len) = net_sockaddr(address);
+    let result = unsafe {
+        libc::bind(
+            fd as i32,
+            &storage as *const _ as *const libc::sockaddr,
+            len,
+        )
+    };
+
+    if result < 0 {
+        let err = net_error();
+        unsafe {
+            libc::close(fd as i32);
+        }
+        err
+    } else {
+        fd
+    }
+}
+
+pub extern "C" fn tcp_listen(address: Handle<Str>, backlog: i32) -> i64 {
+    let address = match net_parse(address) {
+        Some(address) => address,
+        None => return -(libc::EINVAL as i64),
+    };
+
+    let fd = net_socket(&address, libc::SOCK_STREAM);
+    if fd < 0 {
+        return fd;
+    }
+
+    let reuse: libc::c_int = 1;
+    unsafe {
+        libc::setsockopt(
+            fd as i32,
+            libc::SOL_SOCKET,
+            libc::SO_REUSEADDR,
+            &reuse as *const _ as *const libc::c_void,
+            mem::size_of::<libc::c_int>() as libc::socklen_t,
+        );
+    }
+
+    let fd = net_bind(fd, &address);
+    if fd < 0 {
+        return fd;
+    }
+
+    if unsafe { libc::listen(fd as i32, backlog) } < 0 {
+        let err = net_error();
+        socket_close(fd);
+        return err;
+    }
+
+    fd
+}
+
+pub extern "C" fn tcp_accept(fd: i64) -> i64 {
+    blocking(|| net_result(net_accept(fd as i32) as isize))
+}
+
+// connects with the given timeout in milliseconds, waits as long as
+// the OS allows if it isn't positive
+pub extern "C" fn tcp_connect(address: Handle<Str>, timeout: i64) -> i64 {
+    let address = match net_parse(address) {
+        Some(address) => address,
+        None => return -(libc::EINVAL as i64),
+    };
+
+    let fd = net_socket(&address, libc::SOCK_STREAM);
+    if fd < 0 {
+        return fd;
+    }
+
+    let (storage, len) = net_sockaddr(&address);
+    let storage = &storage as *const _ as *const libc::sockaddr;
+
+    let result = blocking(|| {
+        if timeout > 0 {
+            tcp_connect_timeout(fd as i32, storage, len, timeout)
+        } else if unsafe { libc::connect(fd as i32, storage, len) } < 0 {
+            net_error()
+        } else {
+            0
+        }
+    });
+
+    if result < 0 {
+        socket_close(fd);
+        result
+    } else {
+        fd
+    }
+}
+
+// connects the non-blocking socket and polls until the connection is
+// established or the timeout expires
+fn tcp_connect_timeout(
+    fd: i32,
+    address: *const libc::sockaddr,
+    len: libc::socklen_t,
+    timeout: i64,
+) -> i64 {
+    unsafe {
+        let flags = libc::fcntl(fd, libc::F_GETFL);
+        libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
+
+        if libc::connect(fd, address, len) < 0 {
+            if io::Error::last_os_error().raw_os_error() != Some(libc::EINPROGRESS) {
+                return net_error();
+            }
+
+            let mut pollfd = libc::pollfd {
+                fd,
+                events: libc::POLLOUT,
+                revents: 0,
+            };
+
+            match libc::poll(&mut pollfd, 1, timeout as i32) {
+                0 => return -(libc::ETIMEDOUT as i64),
+                result if result < 0 => return net_error(),
+                _ => {}
+            }
+
+            let mut error: libc::c_int = 0;
+            let mut error_len = mem::size_of::<libc::c_int>() as libc::socklen_t;
+            libc::getsockopt(
+                fd,
+                libc::SOL_SOCKET,
+                libc::SO_ERROR,
+                &mut error as *mut _ as *mut libc::c_void,
+                &mut error_len,
+            );
+
+            if error != 0 {
+                return -(error as i64);
+            }
+        }
+
+        libc::fcntl(fd, libc::F_SETFL, flags);
+    }
+
+    0
+}
+
+pub extern "C" fn udp_bind(address: Handle<Str>) -> i64 {
+    let address = match net_parse(address) {
+        Some(address) => address,
+        None => return -(libc::EINVAL as i64),
+    };
+
+    let fd = net_socket(&address, libc::SOCK_DGRAM);
+    if fd < 0 {
+        return fd;
+    }
+
+    net_bind(fd, &address)
+}
+
+pub extern "C" fn socket_read(fd: i64, buffer: Handle<UInt8Array>, offset: i64, len: i64) -> i64 {
+    blocking_read(buffer, offset, len, |data| {
+        let result = unsafe {
+            libc::recv(
+                fd as i32,
+                data.as_mut_ptr() as *mut libc::c_void,
+                data.len(),
+                0,
+            )
+        };
+
+        if result < 0 {
+            Err(io::Error::from_raw_os_error(-net_error() as i32))
+        } else {
+            Ok(result as usize)
+        }
+    })
+}
+
+pub extern "C" fn socket_write(fd: i64, buffer: Handle<UInt8Array>, offset: i64, len: i64) -> i64 {
+    if !io_range_valid(&buffer, offset, len) {
+        return -(libc::EINVAL as i64);
+    }
+
+    let data = io_buffer(buffer, offset, len).to_vec();
+
+    blocking(|| {
+        net_result(unsafe {
+            libc::send(
+                fd as i32,
+                data.as_ptr() as *const libc::c_void,
+                data.len(),
+                NET_SEND_FLAGS,
+            )
+        })
+    })
+}
+
+pub extern "C" fn udp_send_to(
+    fd: i64,
+    buffer: Handle<UInt8Array>,
+    offset: i64,
+    len: i64,
+    address: Handle<Str>,
+) -> i64 {
+    let address = match net_parse(address) {
+        Some(address) => address,
+        None => return -(libc::EINVAL as i64),
+    };
+
+    if !io_range_valid(&buffer, offset, len) {
+        return -(libc::EINVAL as i64);
+    }
+
+    let (storage, address_len) = net_sockaddr(&address);
+    let data = io_buffer(buffer, offset, len).to_vec();
+
+    blocking(|| {
+        net_result(unsafe {
+            libc::sendto(
+                fd as i32,
+                data.as_ptr() as *const libc::c_void,
+                data.len(),
+                NET_SEND_FLAGS,
+                &storage as *const _ as *const libc::sockaddr,
+                address_len,
+            )
+        })
+    })
+}
+
+// writes the size of the datagram or the negated error code into
+// `size` and returns the address of the sender
+pub extern "C" fn udp_receive_from(
+    fd: i64,
+    buffer: Handle<UInt8Array>,
+    offset: i64,
+    len: i64,
+    mut size: Handle<Int64Array>,
+) -> Ref<Str> {
+    if !io_range_valid(&buffer, offset, len) {
+        size.set_at(0, -(libc::EINVAL as i64));
+        return Ref::null();
+    }
+
+    let mut data = vec![0u8; len as usize];
+    let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+    let mut address_len = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+
+    let result = blocking(|| {
+        net_result(unsafe {
+            libc::recvfrom(
+                fd as i32,
+                data.as_mut_ptr() as *mut libc::c_void,
+                data.len(),
+                0,
+                &mut storage as *mut _ as *mut libc::sockaddr,
+                &mut address_len,
+            )
+        })
+    });
+
+    if result < 0 {
+        size.set_at(0, result);
+        return Ref::null();
+    }
+
+    let received = result as usize;
+    io_buffer(buffer, offset, received as i64).copy_from_slice(&data[..received]);
+    size.set_at(0, received as i64);
+
+    net_address_str(net_socket_addr(&storage))
+}
+
+// sets the timeout in milliseconds for reading if `write` is false,
+// otherwise for writing; disables it if not positive
+pub extern "C" fn socket_set_timeout(fd: i64, write: bool, timeout: i64) -> i32 {
+    let timeout = timeout.max(0);
+    let timeval = libc::timeval {
+        tv_sec: (timeout / 1000) as libc::time_t,
+        tv_usec: ((timeout % 1000) * 1000) as libc::suseconds_t,
+    };
+
+    let option = if write {
+        libc::SO_SNDTIMEO
+    } else {
+        libc::SO_RCVTIMEO
+    };
+
+    let result = unsafe {
+        libc::setsockopt(
+            fd as i32,
+            libc::SOL_SOCKET,
+            option,
+            &timeval as *const _ as *const libc::c_void,
+            mem::size_of::<libc::timeval>() as libc::socklen_t,
+        )
+    };
+
+    if result < 0 {
+        net_error() as i32
+    } else {
+        0
+    }
+}
+
+// shuts down reading if `how` is 0, writing if 1 and both if 2
+pub extern "C" fn socket_shutdown(fd: i64, how: i32) -> i32 {
+    let how = match how {
+        0 => libc::SHUT_RD,
+        1 => libc::SHUT_WR,
+        _ => libc::SHUT_RDWR,
+    };
+
+    if unsafe { libc::shutdown(fd as i32, how) } < 0 {
+        net_error() as i32
+    } else {
+        0
+    }
+}
+
+pub extern "C" fn socket_local_address(fd: i64) -> Ref<Str> {
+    socket_address(fd, libc::getsockname)
+}
+
+pub extern "C" fn socket_peer_address(fd: i64) -> Ref<Str> {
+    socket_address(fd, libc::getpeername)
+}
+
+fn socket_address(
+    fd: i64,
+    f: unsafe extern "C" fn(libc::c_int, *mut libc::sockaddr, *mut libc::socklen_t) -> libc::c_int,
+) -> Ref<Str> {
+    let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+
+    if unsafe {
+        f(
+            fd as i32,
+            &mut storage as *mut _ as *mut libc::sockaddr,
+            &mut len,
+        )
+    } < 0
+    {
+        return Ref::null();
+    }
+
+    net_address_str(net_socket_addr(&storage))
+}
+
+pub extern "C" fn socket_close(fd: i64) {
+    unsafe {
+        libc::close(fd as i32);
+    }
+}
+
+pub extern "C" fn net_timed_out() -> i32 {
+    libc::ETIMEDOUT
+}
@@ -0,0 +1,86 @@
+// Sockets aren't supported on this system yet, all calls fail with
+// `ENOSYS`.
+
+use libc;
+
+use crate::handle::Handle;
+use crate::object::{Int64Array, Ref, Str, UInt8Array};
+
+const UNSUPPORTED: i64 = -(libc::ENOSYS as i64);
+
+pub extern "C" fn tcp_listen(_address: Handle<Str>, _backlog: i32) -> i64 {
+    UNSUPPORTED
+}
+
+pub extern "C" fn tcp_accept(_fd: i64) -> i64 {
+    UNSUPPORTED
+}
+
+pub extern "C" fn tcp_connect(_address: Handle<Str>, _timeout: i64) -> i64 {
+    UNSUPPORTED
+}
+
+pub extern "C" fn udp_bind(_address: Handle<Str>) -> i64 {
+    UNSUPPORTED
+}
+
+pub extern "C" fn socket_read(
+    _fd: i64,
+    _buffer: Handle<UInt8Array>,
+    _offset: i64,
+    _len: i64,
+) -> i64 {
+    UNSUPPORTED
+}
+
+pub extern "C" fn socket_write(
+    _fd: i64,
+    _buffer: Handle<UInt8Array>,
+    _offset: i64,
+    _len: i64,
+) -> i64 {
+    UNSUPPORTED
+}
+
+pub extern "C" fn udp_send_to(
+    _fd: i64,
+    _buffer: Handle<UInt8Array>,
+    _offset: i64,
+    _len: i64,
+    _address: Handle<Str>,
+) -> i64 {
+    UNSUPPORTED
+}
+
+pub extern "C" fn udp_receive_from(
+    _fd: i64,
+    _buffer: Handle<UInt8Array>,
+    _offset: i64,
+    _len: i64,
+    mut size: Handle<Int64Array>,
+) -> Ref<Str> {
+    size.set_at(0, UNSUPPORTED);
+    Ref::null()
+}
+
+pub extern "C" fn socket_set_timeout(_fd: i64, _write: bool, _timeout: i64) -> i32 {
+    UNSUPPORTED as i32
+}
+
+pub extern "C" fn socket_shutdown(_fd: i64, _how: i32) -> i32 {
+    UNSUPPORTED as i32
+}
+
+pub extern "C" fn socket_local_address(_fd: i64) -> Ref<Str> {
+    Ref::null()
+}
+
+pub extern "C" fn socket_peer_address(_fd: i64) -> Ref<Str> {
+    Ref::null()
+}
+
+pub extern "C" fn socket_close(_fd: i64) {}
+
+pub extern "C" fn net_timed_out() -> i32 {
+    libc::ETIMEDOUT
+}
@@ -11,6 +11,11 @@ use crate::vm::module::ModuleId;
 use crate::vm::{ClassId, ConstId, EnumId, FctId, FieldId, GlobalId, StructId, TraitId, VarId};
 use dora_parser::interner::Name;
 
+// the first level contains the globals of the stdlib, the second one
+// the globals of the program. Code in the stdlib doesn't see the
+// program's globals, so programs can reuse names of the stdlib.
+const PROGRAM_LEVEL: usize = 1;
+
 #[derive(Debug)]
 pub struct SymTable {
     levels: Vec<SymLevel>,
@@ -38,7 +43,15 @@ impl SymTable {
     }
 
     pub fn get_type(&self, name: Name) -> Option<TypeSym> {
-        for level in self.levels.iter().rev() {
+        self.lookup_type(name, false)
+    }
+
+    pub fn get_term(&self, name: Name) -> Option<TermSym> {
+        self.lookup_term(name, false)
+    }
+
+    pub fn lookup_type(&self, name: Name, in_stdlib: bool) -> Option<TypeSym> {
+        for level in self.visible_levels(in_stdlib) {
             if let Some(val) = level.get_type(name) {
                 return Some(val.clone());
             }
@@ -47,8 +60,8 @@ impl SymTable {
         None
     }
 
-    pub fn get_term(&self, name: Name) -> Option<TermSym> {
-        for level in self.levels.iter().rev() {
+    pub fn lookup_term(&self, name: Name, in_stdlib: bool) -> Option<TermSym> {
+        for level in self.visible_levels(in_stdlib) {
             if let Some(val) = level.get_term(name) {
                 return Some(val.clone());
             }
@@ -57,6 +70,20 @@ impl SymTable {
         None
     }
 
+    // only looks into the innermost level
+    pub fn get_local_term(&self, name: Name) -> Option<TermSym> {
+        self.levels.last().unwrap().get_term(name).cloned()
+    }
+
+    fn visible_levels(&self, in_stdlib: bool) -> impl Iterator<Item = &SymLevel> {
+        self.levels
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(move |&(idx, _)| !in_stdlib || idx != PROGRAM_LEVEL)
+            .map(|(_, level)| level)
+    }
+
     pub fn get_class(&self, name: Name) -> Option<ClassId> {
         self.get_type(name).and_then(|n| n.to_class())
     }
@@ -93,6 +120,22 @@ impl SymTable {
         self.get_term(name).and_then(|n| n.to_var())
     }
 
+    pub fn get_stdlib_class(&self, name: Name) -> Option<ClassId> {
+        self.lookup_type(name, true).and_then(|n| n.to_class())
+    }
+
+    pub fn get_stdlib_trait(&self, name: Name) -> Option<TraitId> {
+        self.lookup_type(name, true).and_then(|n| n.to_trait())
+    }
+
+    pub fn get_stdlib_module(&self, name: Name) -> Option<ModuleId> {
+        self.lookup_term(name, true).and_then(|n| n.to_module())
+    }
+
+    pub fn get_stdlib_fct(&self, name: Name) -> Option<FctId> {
+        self.lookup_term(name, true).and_then(|n| n.to_fct())
+    }
+
     pub fn insert_type(&mut self, name: Name, sym: TypeSym) -> Option<TypeSym> {
         self.levels.last_mut().unwrap().insert_type(name, sym)
     }
@@ -148,6 +191,7 @@ pub enum TypeSym {
     SymStruct(StructId),
     SymTrait(TraitId),
     SymClassTypeParam(ClassId, TypeListId),
+    SymTraitTypeParam(TraitId, TypeListId),
     SymFctTypeParam(FctId, TypeListId),
     SymEnum(EnumId),
 }
@@ -212,6 +256,7 @@ impl TypeSym {
     pub fn is_type_param(&self) -> bool {
         match *self {
             SymClassTypeParam(_, _) => true,
+            SymTraitTypeParam(_, _) => true,
             SymFctTypeParam(_, _) => true,
             _ => false,
         }
@@ -1,4 +1,5 @@
 use parking_lot::{Condvar, Mutex};
+use std::alloc::{self, Layout};
 use std::cell::RefCell;
 use std::convert::From;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
@@ -6,7 +7,7 @@ use std::sync::Arc;
 
 use crate::gc::{tlab, Address, Region, K};
 use crate::handle::HandleMemory;
-use crate::safepoint;
+use crate::mem;
 use crate::stack::DoraToNativeInfo;
 use crate::vm::{get_vm, VM};
 
@@ -24,6 +25,9 @@ pub struct Threads {
     pub safepoint: Mutex<(usize, usize)>,
 
     pub barrier: Barrier,
+
+    // size of each thread's table of @threadlocal globals
+    pub thread_locals_size: AtomicUsize,
 }
 
 impl Threads {
@@ -34,21 +38,35 @@ impl Threads {
             next_id: AtomicUsize::new(1),
             safepoint: Mutex::new((0, 1)),
             barrier: Barrier::new(),
+            thread_locals_size: AtomicUsize::new(0),
         }
     }
 
     pub fn attach_current_thread(&self) {
         THREAD.with(|thread| {
+            let thread = thread.borrow();
+            thread.tld.allocate_thread_locals(self.thread_locals_size());
+
             let mut threads = self.threads.lock();
-            threads.push(thread.borrow().clone());
+            threads.push(thread.clone());
         });
     }
 
     pub fn attach_thread(&self, thread: Arc<DoraThread>) {
+        thread.tld.allocate_thread_locals(self.thread_locals_size());
+
         let mut threads = self.threads.lock();
         threads.push(thread);
     }
 
+    pub fn thread_locals_size(&self) -> usize {
+        self.thread_locals_size.load(Ordering::Relaxed)
+    }
+
+    pub fn set_thread_locals_size(&self, size: usize) {
+        self.thread_locals_size.store(size, Ordering::Relaxed);
+    }
+
     pub fn next_id(&self) -> usize {
         self.next_id.fetch_add(1, Ordering::SeqCst)
     }
@@ -89,10 +107,34 @@ impl Threads {
             thread.borrow().park(vm);
             let mut threads = self.threads.lock();
             threads.retain(|elem| !Arc::ptr_eq(elem, &*thread.borrow()));
+
+            // the collector can't see this thread anymore, its
+            // thread-locals can be freed.
+            thread
+                .borrow()
+                .tld
+                .free_thread_locals(self.thread_locals_size());
             self.cond_join.notify_all();
         });
     }
 
+    /// blocks until thread with given id has finished, the current
+    /// thread is parked while waiting and doesn't block collections.
+    pub fn join(&self, vm: &VM, id: usize) {
+        let thread = THREAD.with(|thread| thread.borrow().clone());
+        thread.park(vm);
+
+        {
+            let mut threads = self.threads.lock();
+
+            while threads.iter().any(|thread| thread.id() == id) {
+                self.cond_join.wait(&mut threads);
+            }
+        }
+
+        thread.unpark(vm);
+    }
+
     pub fn join_all(&self) {
         let mut threads = self.threads.lock();
 
@@ -115,6 +157,9 @@ impl Threads {
 
 pub struct DoraThread {
     pub id: AtomicUsize,
+    // Thread object in the heap, the main thread creates
+    // its object lazily on first call to Thread::current()
+    object: AtomicUsize,
     pub handles: HandleMemory,
     pub tld: ThreadLocalData,
     pub saved_pc: AtomicUsize,
@@ -137,6 +182,7 @@ impl DoraThread {
     fn with_id(id: usize) -> Arc<DoraThread> {
         Arc::new(DoraThread {
             id: AtomicUsize::new(id),
+            object: AtomicUsize::new(0),
             handles: HandleMemory::new(),
             tld: ThreadLocalData::new(),
             saved_pc: AtomicUsize::new(0),
@@ -149,6 +195,18 @@ impl DoraThread {
         self.id.load(Ordering::Relaxed)
     }
 
+    pub fn object(&self) -> Address {
+        self.object.load(Ordering::Relaxed).into()
+    }
+
+    pub fn set_object(&self, object: Address) {
+        self.object.store(object.to_usize(), Ordering::Relaxed);
+    }
+
+    pub fn object_address(&self) -> Address {
+        Address::from_ptr(&self.object as *const _)
+    }
+
     pub fn dtn(&self) -> *const DoraToNativeInfo {
         self.tld.dtn.load(Ordering::Relaxed) as *const _
     }
@@ -193,8 +251,12 @@ impl DoraThread {
     }
 
     pub fn unpark(&self, vm: &VM) {
-        if vm.threads.safepoint_id() != 0 {
-            safepoint::block(vm, self);
+        let safepoint_id = vm.threads.safepoint_id();
+
+        if safepoint_id != 0 {
+            // parked threads are already considered to be in the safepoint,
+            // stay parked until the world is resumed
+            vm.threads.barrier.wait(safepoint_id);
         }
 
         self.state.unpark(vm);
@@ -325,6 +387,7 @@ pub struct ThreadLocalData {
     guard_stack_limit: AtomicUsize,
     real_stack_limit: AtomicUsize,
     dtn: AtomicUsize,
+    thread_locals: AtomicUsize,
 }
 
 impl ThreadLocalData {
@@ -336,6 +399,7 @@ impl ThreadLocalData {
             guard_stack_limit: AtomicUsize::new(0),
             real_stack_limit: AtomicUsize::new(0),
             dtn: AtomicUsize::new(0),
+            thread_locals: AtomicUsize::new(0),
         }
     }
 
@@ -395,6 +459,38 @@ impl ThreadLocalData {
         offset_of!(ThreadLocalData, dtn) as i32
     }
 
+    pub fn thread_locals(&self) -> Address {
+        Address::from(self.thread_locals.load(Ordering::Relaxed))
+    }
+
+    pub fn thread_locals_offset() -> i32 {
+        offset_of!(ThreadLocalData, thread_locals) as i32
+    }
+
+    // zeroed table, all thread-locals start uninitialized
+    fn allocate_thread_locals(&self, size: usize) {
+        if size == 0 {
+            return;
+        }
+
+        assert!(self.thread_locals().is_null());
+        let layout = Layout::from_size_align(size, mem::ptr_width_usize()).unwrap();
+        let ptr = unsafe { alloc::alloc_zeroed(layout) };
+        assert!(!ptr.is_null());
+        self.thread_locals.store(ptr as usize, Ordering::Relaxed);
+    }
+
+    fn free_thread_locals(&self, size: usize) {
+        let ptr = self.thread_locals.swap(0, Ordering::Relaxed);
+
+        if ptr == 0 {
+            return;
+        }
+
+        let layout = Layout::from_size_align(size, mem::ptr_width_usize()).unwrap();
+        unsafe { alloc::dealloc(ptr as *mut u8, layout) };
+    }
+
     pub fn arm_stack_guard(&self) {
         self.guard_stack_limit.store(!0, Ordering::Release);
     }
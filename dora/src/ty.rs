@@ -49,7 +49,7 @@ pub enum BuiltinType {
     Tuple(TupleId),
 
     // some trait object
-    Trait(TraitId),
+    Trait(TraitId, TypeListId),
 
     // some module
     Module(ModuleId),
@@ -57,6 +57,7 @@ pub enum BuiltinType {
     // some type variable
     FctTypeParam(FctId, TypeListId),
     ClassTypeParam(ClassId, TypeListId),
+    TraitTypeParam(TraitId, TypeListId),
 
     // some lambda
     Lambda(LambdaId),
@@ -125,6 +126,7 @@ impl BuiltinType {
     pub fn is_type_param(&self) -> bool {
         match self {
             &BuiltinType::ClassTypeParam(_, _) => true,
+            &BuiltinType::TraitTypeParam(_, _) => true,
             &BuiltinType::FctTypeParam(_, _) => true,
             _ => false,
         }
@@ -192,7 +194,8 @@ impl BuiltinType {
         match self {
             &BuiltinType::Class(_, list_id)
             | &BuiltinType::Enum(_, list_id)
-            | &BuiltinType::Struct(_, list_id) => vm.lists.lock().get(list_id),
+            | &BuiltinType::Struct(_, list_id)
+            | &BuiltinType::Trait(_, list_id) => vm.lists.lock().get(list_id),
             _ => TypeList::empty(),
         }
     }
@@ -200,9 +203,10 @@ impl BuiltinType {
     pub fn contains_type_param(&self, vm: &VM) -> bool {
         match self {
             &BuiltinType::ClassTypeParam(_, _) => true,
+            &BuiltinType::TraitTypeParam(_, _) => true,
             &BuiltinType::FctTypeParam(_, _) => true,
 
-            &BuiltinType::Class(_, list_id) => {
+            &BuiltinType::Class(_, list_id) | &BuiltinType::Trait(_, list_id) => {
                 let params = vm.lists.lock().get(list_id);
                 params.iter().any(|t| t.contains_type_param(vm))
             }
@@ -217,7 +221,7 @@ impl BuiltinType {
         match *self {
             BuiltinType::Ptr => true,
             BuiltinType::Class(_, _) => true,
-            BuiltinType::Trait(_) => true,
+            BuiltinType::Trait(_, _) => true,
             _ => false,
         }
     }
@@ -302,9 +306,23 @@ impl BuiltinType {
                     format!("{}[{}]", name, params)
                 }
             }
-            BuiltinType::Trait(tid) => {
+            BuiltinType::Trait(tid, list_id) => {
                 let xtrait = vm.traits[tid].read();
-                vm.interner.str(xtrait.name).to_string()
+                let name = vm.interner.str(xtrait.name).to_string();
+
+                let params = vm.lists.lock().get(list_id);
+
+                if params.len() == 0 {
+                    name
+                } else {
+                    let params = params
+                        .iter()
+                        .map(|ty| ty.name(vm))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    format!("{}[{}]", name, params)
+                }
             }
             BuiltinType::Enum(id, list_id) => {
                 let xenum = vm.enums[id].read();
@@ -335,6 +353,13 @@ impl BuiltinType {
                 vm.interner.str(cls.type_params[id.idx()].name).to_string()
             }
 
+            BuiltinType::TraitTypeParam(tid, id) => {
+                let xtrait = vm.traits[tid].read();
+                vm.interner
+                    .str(xtrait.type_params[id.idx()].name)
+                    .to_string()
+            }
+
             BuiltinType::FctTypeParam(fid, id) => {
                 let fct = vm.fcts.idx(fid);
                 let fct = fct.read();
@@ -421,11 +446,12 @@ impl BuiltinType {
 
                 _ => false,
             },
-            BuiltinType::Trait(_) => unimplemented!(),
+            BuiltinType::Trait(_, _) => unimplemented!(),
             BuiltinType::Module(_) => *self == other,
             BuiltinType::Enum(_, _) => *self == other,
 
             BuiltinType::ClassTypeParam(_, _) => *self == other,
+            BuiltinType::TraitTypeParam(_, _) => *self == other,
             BuiltinType::FctTypeParam(_, _) => *self == other,
 
             BuiltinType::Lambda(_) => {
@@ -472,10 +498,10 @@ impl BuiltinType {
 
                 struc.size
             }
-            BuiltinType::Trait(_) => mem::ptr_width(),
-            BuiltinType::ClassTypeParam(_, _) | BuiltinType::FctTypeParam(_, _) => {
-                panic!("no size for type variable.")
-            }
+            BuiltinType::Trait(_, _) => mem::ptr_width(),
+            BuiltinType::ClassTypeParam(_, _)
+            | BuiltinType::TraitTypeParam(_, _)
+            | BuiltinType::FctTypeParam(_, _) => panic!("no size for type variable."),
             BuiltinType::Tuple(tuple_id) => vm.tuples.lock().get_tuple(tuple_id).size(),
         }
     }
@@ -507,10 +533,10 @@ impl BuiltinType {
 
                 struc.align
             }
-            BuiltinType::Trait(_) => mem::ptr_width(),
-            BuiltinType::ClassTypeParam(_, _) | BuiltinType::FctTypeParam(_, _) => {
-                panic!("no alignment for type variable.")
-            }
+            BuiltinType::Trait(_, _) => mem::ptr_width(),
+            BuiltinType::ClassTypeParam(_, _)
+            | BuiltinType::TraitTypeParam(_, _)
+            | BuiltinType::FctTypeParam(_, _) => panic!("no alignment for type variable."),
             BuiltinType::Tuple(tuple_id) => vm.tuples.lock().get_tuple(tuple_id).align(),
         }
     }
@@ -535,10 +561,10 @@ impl BuiltinType {
             | BuiltinType::Lambda(_)
             | BuiltinType::Ptr => MachineMode::Ptr,
             BuiltinType::Struct(_, _) => panic!("no machine mode for struct."),
-            BuiltinType::Trait(_) => MachineMode::Ptr,
-            BuiltinType::ClassTypeParam(_, _) | BuiltinType::FctTypeParam(_, _) => {
-                panic!("no machine mode for type variable.")
-            }
+            BuiltinType::Trait(_, _) => MachineMode::Ptr,
+            BuiltinType::ClassTypeParam(_, _)
+            | BuiltinType::TraitTypeParam(_, _)
+            | BuiltinType::FctTypeParam(_, _) => panic!("no machine mode for type variable."),
             BuiltinType::Tuple(_) => unimplemented!(),
         }
     }
@@ -560,11 +586,13 @@ impl BuiltinType {
             | BuiltinType::Float64
             | BuiltinType::Enum(_, _)
             | BuiltinType::Module(_)
-            | BuiltinType::Trait(_)
             | BuiltinType::Lambda(_)
             | BuiltinType::ClassTypeParam(_, _)
+            | BuiltinType::TraitTypeParam(_, _)
             | BuiltinType::FctTypeParam(_, _) => true,
-            BuiltinType::Class(_, list_id) | BuiltinType::Struct(_, list_id) => {
+            BuiltinType::Class(_, list_id)
+            | BuiltinType::Struct(_, list_id)
+            | BuiltinType::Trait(_, list_id) => {
                 let params = vm.lists.lock().get(list_id);
 
                 for param in params.iter() {
@@ -603,9 +631,8 @@ impl BuiltinType {
             | BuiltinType::Enum(_, _)
             | BuiltinType::Module(_)
             | BuiltinType::Ptr
-            | BuiltinType::Trait(_)
             | BuiltinType::Nil => true,
-            BuiltinType::Class(_, list_id) => {
+            BuiltinType::Class(_, list_id) | BuiltinType::Trait(_, list_id) => {
                 let params = vm.lists.lock().get(list_id);
 
                 for param in params.iter() {
@@ -618,7 +645,9 @@ impl BuiltinType {
             }
             BuiltinType::Tuple(tuple_id) => vm.tuples.lock().get_tuple(tuple_id).is_concrete_type(),
             BuiltinType::Lambda(_) | BuiltinType::Struct(_, _) => unimplemented!(),
-            BuiltinType::ClassTypeParam(_, _) | BuiltinType::FctTypeParam(_, _) => false,
+            BuiltinType::ClassTypeParam(_, _)
+            | BuiltinType::TraitTypeParam(_, _)
+            | BuiltinType::FctTypeParam(_, _) => false,
         }
     }
 }
@@ -4,15 +4,16 @@ use std::sync::Arc;
 use std::{f32, f64};
 
 use crate::error::msg::SemError;
-use crate::semck::specialize::replace_type_param;
+use crate::semck::specialize::{replace_type_param, specialize_type};
 use crate::semck::typeparamck;
 use crate::semck::{always_returns, expr_always_returns};
 use crate::sym::TypeSym::SymClass;
 use crate::ty::{BuiltinType, TypeList, TypeParamId};
 use crate::typeck::lookup::MethodLookup;
 use crate::vm::{
-    self, ensure_tuple, find_field_in_class, find_methods_in_class, CallType, ClassId, ConvInfo,
-    EnumId, Fct, FctId, FctParent, FctSrc, FileId, ForTypeInfo, IdentType, Intrinsic, VM,
+    self, ensure_tuple, find_field_in_class, find_impl_in_class, find_methods_in_class, CallType,
+    ClassId, ConvInfo, EnumId, Fct, FctId, FctParent, FctSrc, FileId, ForTypeInfo, IdentType,
+    Intrinsic, VM,
 };
 
 use dora_parser::ast::visit::Visitor;
@@ -137,79 +138,17 @@ impl<'a, 'ast> TypeCheck<'a, 'ast> {
             }
         }
 
-        let name = self.vm.interner.intern("makeIterator");
-
-        let mut lookup = MethodLookup::new(self.vm, self.file)
-            .method(object_type)
-            .pos(s.pos)
-            .name(name)
-            .args(&[]);
-
-        if lookup.find() {
-            let make_iterator_id = lookup.found_fct_id().unwrap();
-            let make_iterator_ret = lookup.found_ret().unwrap();
-            let iterator_trait_id = self.vm.vips.iterator();
-
-            if make_iterator_ret.implements_trait(self.vm, iterator_trait_id) {
-                // find fct next() & hasNext() in iterator-trait
-                let has_next_name = self.vm.interner.intern("hasNext");
-                let next_name = self.vm.interner.intern("next");
-                let trai = self.vm.traits[iterator_trait_id].read();
-                let next_id = trai
-                    .find_method_with_replace(self.vm, false, next_name, None, &[])
-                    .expect("next() not found");
-                let has_next_id = trai
-                    .find_method_with_replace(self.vm, false, has_next_name, None, &[])
-                    .expect("hasNext() not found");
-
-                // find impl for ret that implements Iterator
-                let cls_id = make_iterator_ret.cls_id(self.vm).unwrap();
-                let cls = self.vm.classes.idx(cls_id);
-                let cls = cls.read();
-                let impl_id = cls
-                    .find_impl_for_trait(self.vm, iterator_trait_id)
-                    .expect("impl not found for Iterator");
-
-                // find method in impl that implements next()
-                let ximpl = self.vm.impls[impl_id].read();
-                let impl_next_id = ximpl
-                    .find_implements(self.vm, next_id)
-                    .expect("next() impl not found");
-
-                // find method in impl that implements hasNext();
-                let impl_has_next_id = ximpl
-                    .find_implements(self.vm, has_next_id)
-                    .expect("hasNext() impl not found");
-
-                // get return type of next() in impl
-                let fct = self.vm.fcts.idx(impl_next_id);
-                let fct = fct.read();
-                let ret = fct.return_type;
-
-                // set variable type to return type of next
-                let var_id = *self.src.map_vars.get(s.id).unwrap();
-                self.src.vars[var_id].ty = ret;
-
-                // store fct ids for `for-in` loop
-                self.src.map_fors.insert(
-                    s.id,
-                    ForTypeInfo {
-                        make_iterator: make_iterator_id,
-                        has_next: impl_has_next_id,
-                        next: impl_next_id,
-                        iterator_type: make_iterator_ret,
-                    },
-                );
-            } else {
-                let ret = make_iterator_ret.name(self.vm);
-                let msg = SemError::MakeIteratorReturnType(ret);
-                self.vm.diag.lock().report(self.file, s.expr.pos(), msg);
+        if let Some((for_type_info, element_type)) = self.find_iterator(object_type) {
+            let var_id = *self.src.map_vars.get(s.id).unwrap();
+            self.src.vars[var_id].ty = element_type;
 
-                // set invalid error type
-                let var_id = *self.src.map_vars.get(s.id).unwrap();
-                self.src.vars[var_id].ty = BuiltinType::Error;
-            }
+            // store fct ids for `for-in` loop
+            self.src.map_fors.insert(s.id, for_type_info);
         } else {
+            let ty = object_type.name(self.vm);
+            let msg = SemError::TypeNotIterable(ty);
+            self.vm.diag.lock().report(self.file, s.expr.pos(), msg);
+
             // set invalid error type
             let var_id = *self.src.map_vars.get(s.id).unwrap();
             self.src.vars[var_id].ty = BuiltinType::Error;
@@ -218,6 +157,83 @@ impl<'a, 'ast> TypeCheck<'a, 'ast> {
         self.visit_stmt(&s.block);
     }
 
+    // for loops iterate over objects implementing Iterator[T] directly,
+    // objects implementing Iterable[T] are iterated with makeIterator()
+    fn find_iterator(&self, object_type: BuiltinType) -> Option<(ForTypeInfo, BuiltinType)> {
+        object_type.cls_id(self.vm)?;
+
+        if let Some(result) = self.find_iterator_methods(object_type) {
+            return Some(result);
+        }
+
+        let iterable_trait_id = self.vm.vips.iterable();
+        let (iterable_type, impl_id) = find_impl_in_class(self.vm, object_type, iterable_trait_id)?;
+
+        let make_iterator_name = self.vm.interner.intern("makeIterator");
+        let make_iterator_id = self.vm.traits[iterable_trait_id]
+            .read()
+            .find_method(self.vm, make_iterator_name, false)
+            .expect("makeIterator() not found");
+
+        let impl_make_iterator_id = self.vm.impls[impl_id]
+            .read()
+            .find_implements(self.vm, make_iterator_id)
+            .expect("makeIterator() impl not found");
+
+        let ret = self.vm.fcts.idx(impl_make_iterator_id).read().return_type;
+        let type_params = iterable_type.type_params(self.vm);
+        let iterator_type = specialize_type(self.vm, ret, &type_params, &TypeList::empty());
+
+        let (mut for_type_info, element_type) = self.find_iterator_methods(iterator_type)?;
+        for_type_info.make_iterator = Some((impl_make_iterator_id, iterable_type));
+
+        Some((for_type_info, element_type))
+    }
+
+    fn find_iterator_methods(
+        &self,
+        iterator_type: BuiltinType,
+    ) -> Option<(ForTypeInfo, BuiltinType)> {
+        iterator_type.cls_id(self.vm)?;
+
+        let iterator_trait_id = self.vm.vips.iterator();
+        let (iterator_type, impl_id) =
+            find_impl_in_class(self.vm, iterator_type, iterator_trait_id)?;
+
+        // find fct next() & hasNext() in iterator-trait
+        let has_next_name = self.vm.interner.intern("hasNext");
+        let next_name = self.vm.interner.intern("next");
+        let trai = self.vm.traits[iterator_trait_id].read();
+        let next_id = trai
+            .find_method(self.vm, next_name, false)
+            .expect("next() not found");
+        let has_next_id = trai
+            .find_method(self.vm, has_next_name, false)
+            .expect("hasNext() not found");
+
+        // find methods in impl that implement next() & hasNext()
+        let ximpl = self.vm.impls[impl_id].read();
+        let impl_next_id = ximpl
+            .find_implements(self.vm, next_id)
+            .expect("next() impl not found");
+        let impl_has_next_id = ximpl
+            .find_implements(self.vm, has_next_id)
+            .expect("hasNext() impl not found");
+
+        // the element type is the type argument of Iterator[T]
+        let trait_ty = ximpl.trait_ty_for(self.vm, iterator_type);
+        let element_type = trait_ty.type_params(self.vm)[0];
+
+        let for_type_info = ForTypeInfo {
+            make_iterator: None,
+            has_next: impl_has_next_id,
+            next: impl_next_id,
+            iterator_type,
+        };
+
+        Some((for_type_info, element_type))
+    }
+
     fn check_stmt_while(&mut self, s: &'ast StmtWhileType) {
         let expr_type = self.check_expr(&s.cond, BuiltinType::Any);
 
@@ -230,6 +246,18 @@ impl<'a, 'ast> TypeCheck<'a, 'ast> {
         self.visit_stmt(&s.block);
     }
 
+    fn check_stmt_sync(&mut self, s: &'ast StmtSyncType) {
+        let expr_type = self.check_expr(&s.mutex, BuiltinType::Any);
+
+        if !expr_type.is_error() && expr_type.cls_id(self.vm) != Some(self.vm.vips.cls.mutex) {
+            let expr_type = expr_type.name(self.vm);
+            let msg = SemError::SynchronizedType(expr_type);
+            self.vm.diag.lock().report(self.file, s.pos, msg);
+        }
+
+        self.visit_stmt(&s.block);
+    }
+
     fn check_stmt_return(&mut self, s: &'ast StmtReturnType) {
         let expr_type = s
             .expr
@@ -1336,7 +1364,7 @@ impl<'a, 'ast> TypeCheck<'a, 'ast> {
             let fct_id = lookup.found_fct_id().unwrap();
             let return_type = lookup.found_ret().unwrap();
 
-            let call_type = if let BuiltinType::Trait(trait_id) = object_type {
+            let call_type = if let BuiltinType::Trait(trait_id, _) = object_type {
                 CallType::Trait(trait_id, fct_id)
             } else {
                 let method_type = lookup.found_class_type().unwrap();
@@ -1434,7 +1462,9 @@ impl<'a, 'ast> TypeCheck<'a, 'ast> {
         for &trait_id in &tp.trait_bounds {
             let trai = self.vm.traits[trait_id].read();
 
-            if let Some(fid) = trai.find_method_with_replace(self.vm, false, name, None, args) {
+            if let Some(fid) =
+                trai.find_method_with_replace(self.vm, false, name, None, &TypeList::empty(), args)
+            {
                 found_fcts.push(fid);
             }
         }
@@ -1508,7 +1538,9 @@ impl<'a, 'ast> TypeCheck<'a, 'ast> {
             return BuiltinType::Error;
         }
 
-        match self.vm.sym.lock().get_type(class) {
+        let in_stdlib = self.vm.is_stdlib_file(self.file);
+
+        match self.vm.sym.lock().lookup_type(class, in_stdlib) {
             Some(SymClass(cls_id)) => {
                 let mut lookup = MethodLookup::new(self.vm, self.file)
                     .pos(e.pos)
@@ -2103,7 +2135,15 @@ impl<'a, 'ast> TypeCheck<'a, 'ast> {
                     _ => part_expr.implements_trait(self.vm, stringable_trait),
                 };
 
-                if implements_stringable || part_expr.is_error() {
+                if part_expr.is_error() {
+                    continue;
+                }
+
+                if implements_stringable {
+                    if let Some(ref spec) = e.specs[idx] {
+                        self.check_format_spec(spec, part_expr);
+                    }
+
                     continue;
                 }
 
@@ -2123,6 +2163,31 @@ impl<'a, 'ast> TypeCheck<'a, 'ast> {
         str_ty
     }
 
+    fn check_format_spec(&mut self, spec: &FormatSpec, ty: BuiltinType) {
+        let is_int = match ty {
+            BuiltinType::UInt8 | BuiltinType::Int32 | BuiltinType::Int64 => true,
+            _ => false,
+        };
+
+        let valid = if spec.is_radix() || spec.alternate {
+            is_int
+        } else if spec.is_exponential() || spec.precision.is_some() {
+            ty.is_float()
+        } else if spec.sign || spec.zero {
+            is_int || ty.is_float()
+        } else {
+            true
+        };
+
+        if !valid {
+            let ty = ty.name(self.vm);
+            self.vm
+                .diag
+                .lock()
+                .report(self.file, spec.pos, SemError::InvalidFormatSpec(ty));
+        }
+    }
+
     fn check_expr(&mut self, e: &'ast Expr, expected_ty: BuiltinType) -> BuiltinType {
         match *e {
             ExprLitChar(ref expr) => self.check_expr_lit_char(expr, expected_ty),
@@ -2161,6 +2226,7 @@ impl<'a, 'ast> Visitor<'ast> for TypeCheck<'a, 'ast> {
             StmtVar(ref stmt) => self.check_stmt_var(stmt),
             StmtWhile(ref stmt) => self.check_stmt_while(stmt),
             StmtFor(ref stmt) => self.check_stmt_for(stmt),
+            StmtSync(ref stmt) => self.check_stmt_sync(stmt),
             StmtReturn(ref stmt) => self.check_stmt_return(stmt),
 
             // for the rest of the statements, no special handling is necessary
@@ -2264,7 +2330,7 @@ fn arg_allows(
                 self_ty,
             )
         }
-        BuiltinType::Trait(_) => panic!("trait should not occur in fct definition."),
+        BuiltinType::Trait(_, _) => panic!("trait should not occur in fct definition."),
 
         BuiltinType::ClassTypeParam(cls_id, tpid) => {
             if def == arg {
@@ -2286,6 +2352,8 @@ fn arg_allows(
                 None,
             )
         }
+        BuiltinType::TraitTypeParam(_, _) => def == arg,
+
         BuiltinType::FctTypeParam(fct_id, tpid) => {
             if def == arg {
                 return true;
@@ -2333,7 +2401,38 @@ fn arg_allows(
                 return arg.subclass_from(vm, def);
             }
 
-            if cls_id != other_cls_id || params.len() != other_params.len() {
+            if cls_id != other_cls_id {
+                // check superclass of argument with its type params filled in
+                let other_cls = vm.classes.idx(other_cls_id);
+                let other_cls = other_cls.read();
+
+                return match other_cls.parent_class {
+                    Some(parent_class) => {
+                        let parent_class = replace_type_param(
+                            vm,
+                            parent_class,
+                            &other_params,
+                            &TypeList::empty(),
+                            None,
+                        );
+
+                        arg_allows(
+                            vm,
+                            def,
+                            parent_class,
+                            global_cls_id,
+                            global_fct_id,
+                            cls_tps,
+                            fct_tps,
+                            self_ty,
+                        )
+                    }
+
+                    None => false,
+                };
+            }
+
+            if params.len() != other_params.len() {
                 return false;
             }
 
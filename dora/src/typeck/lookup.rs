@@ -76,7 +76,7 @@ impl<'a, 'ast> MethodLookup<'a, 'ast> {
             Some(LookupKind::Method(obj))
         } else if let Some(_) = obj.module_id() {
             Some(LookupKind::Method(obj))
-        } else if let BuiltinType::Trait(trait_id) = obj {
+        } else if let BuiltinType::Trait(trait_id, _) = obj {
             Some(LookupKind::Trait(trait_id))
         } else if obj.is_nil() {
             Some(LookupKind::Method(obj))
@@ -239,7 +239,8 @@ impl<'a, 'ast> MethodLookup<'a, 'ast> {
         let cls_tps: TypeList = if let Some(cls_tps) = self.cls_tps {
             cls_tps.clone()
         } else if let LookupKind::Method(obj) = kind {
-            obj.type_params(self.vm)
+            // method might be defined in a superclass with different type params
+            self.found_class_type.unwrap_or(obj).type_params(self.vm)
         } else {
             TypeList::empty()
         };
@@ -384,6 +385,13 @@ impl<'a, 'ast> MethodLookup<'a, 'ast> {
         };
 
         self.check_tps(&cls_tps, tps)
+            && typeparamck::check_atomic_ref(
+                self.vm,
+                self.file,
+                self.pos.expect("no pos"),
+                cls_id,
+                tps,
+            )
     }
 
     fn check_fct_tps(&self, tps: &TypeList) -> bool {
@@ -301,6 +301,17 @@ fn type_while() {
     );
 }
 
+#[test]
+fn type_synchronized() {
+    ok("fun x(m: Mutex) { synchronized m { } }");
+    ok("fun x(m: Mutex) -> Int32 { synchronized m { return 1; } }");
+    err(
+        "fun x() { synchronized 2 { } }",
+        pos(1, 11),
+        SemError::SynchronizedType("Int32".into()),
+    );
+}
+
 #[test]
 fn type_if() {
     ok("fun x() { if true { } }");
@@ -1292,11 +1303,11 @@ fn test_generic_argument_with_trait_bound() {
 }
 
 #[test]
-fn test_for_supports_make_iterator() {
+fn test_for_supports_iterable() {
     err(
         "fun f() { for i in 1 {} }",
-        pos(1, 11),
-        SemError::UnknownMethod("Int32".into(), "makeIterator".into(), Vec::new()),
+        pos(1, 20),
+        SemError::TypeNotIterable("Int32".into()),
     );
 
     err(
@@ -1304,17 +1315,59 @@ fn test_for_supports_make_iterator() {
             class Foo() { fun makeIterator() -> Bool { return true; } }
             fun f() { for i in Foo() {} }",
         pos(3, 35),
-        SemError::MakeIteratorReturnType("Bool".into()),
+        SemError::TypeNotIterable("Foo".into()),
     );
 
-    ok(
-        "class Foo { fun makeIterator() -> FooIter { return FooIter(); } }
+    ok("class Foo
+            impl Iterable[Int32] for Foo { fun makeIterator() -> FooIter { return FooIter(); } }
             class FooIter
-            impl Iterator for FooIter {
+            impl Iterator[Int32] for FooIter {
                 fun hasNext() -> Bool { return false; }
                 fun next() -> Int32 { return 0; }
             }
-            fun f() -> Int32 { for i in Foo() { return i; } return 0; }",
+            fun f() -> Int32 { for i in Foo() { return i; } return 0; }");
+
+    ok("class FooIter
+            impl Iterator[Int32] for FooIter {
+                fun hasNext() -> Bool { return false; }
+                fun next() -> Int32 { return 0; }
+            }
+            fun f() -> Int32 { for i in FooIter() { return i; } return 0; }");
+}
+
+#[test]
+fn test_for_element_type() {
+    ok("fun f(x: Vec[String]) -> String {
+        var result = \"\";
+        for i in x {
+            result = result + i;
+        }
+        result
+    }");
+
+    ok("fun f[T](x: Vec[T]) -> Option[T] {
+        for i in x {
+            return Option::some[T](i);
+        }
+        Option::none[T]()
+    }");
+
+    ok("fun f(x: HashMap[Int32, String]) -> Int32 {
+        var result = 0;
+        for entry in x {
+            result = result + entry.0;
+        }
+        result
+    }");
+
+    err(
+        "fun f(x: Vec[String]) {
+            for i in x {
+                let y: Int32 = i;
+            }
+        }",
+        pos(3, 17),
+        SemError::AssignType("y".into(), "Int32".into(), "String".into()),
     );
 }
 
@@ -1543,6 +1596,29 @@ fn test_template() {
     ok("fun f[T: Stringable](x: T) -> String { return \"${x}\"; }");
 }
 
+#[test]
+fn test_template_format_spec() {
+    ok("fun f(x: Int32, y: Int64, z: UInt8) -> String = \"${x:x} ${y:#b} ${z:+08}\";");
+    ok("fun f(x: Float32, y: Float64) -> String = \"${x:.3} ${y:+e} ${y:010.2E}\";");
+    ok("fun f(x: String, y: Bool) -> String = \"${x:*^10} ${y:<5}\";");
+    ok("fun f[T: Stringable](x: T) -> String = \"${x:>8}\";");
+    err(
+        "fun f(x: Float64) -> String = \"${x:x}\";",
+        pos(1, 36),
+        SemError::InvalidFormatSpec("Float64".into()),
+    );
+    err(
+        "fun f(x: Int32) -> String = \"${x:.2}\";",
+        pos(1, 34),
+        SemError::InvalidFormatSpec("Int32".into()),
+    );
+    err(
+        "fun f(x: String) -> String = \"${x:+5}\";",
+        pos(1, 35),
+        SemError::InvalidFormatSpec("String".into()),
+    );
+}
+
 #[test]
 fn test_trait_object_as_argument() {
     ok("trait Foo { fun bar() -> Int32; }
@@ -1840,6 +1916,39 @@ fn test_inheritance_with_generics() {
     ");
 }
 
+#[test]
+fn test_generic_subclass_as_argument() {
+    ok("
+        @open class Foo
+        class Bar[A]: Foo
+        fun f(x: Foo) {}
+        fun g[A](x: Bar[A]) { f(x); }
+        fun h(x: Bar[Int32]) { f(x); }
+    ");
+
+    ok("
+        @open class Foo[A]
+        class Bar[B]: Foo[B]
+        fun f(x: Foo[Int32]) {}
+        fun g(x: Bar[Int32]) { f(x); }
+    ");
+
+    err(
+        "
+        @open class Foo[A]
+        class Bar[B]: Foo[B]
+        fun f(x: Foo[Int32]) {}
+        fun g(x: Bar[Int64]) { f(x); }
+    ",
+        pos(5, 33),
+        SemError::ParamTypesIncompatible(
+            "f".into(),
+            vec!["Foo[Int32]".into()],
+            vec!["Bar[Int64]".into()],
+        ),
+    );
+}
+
 #[test]
 fn test_fields_with_generics() {
     ok("
@@ -1935,8 +2044,8 @@ fn test_type_without_make_iterator() {
             }
         }
     ",
-        pos(4, 13),
-        SemError::UnknownMethod("Foo".into(), "makeIterator".into(), vec![]),
+        pos(4, 22),
+        SemError::TypeNotIterable("Foo".into()),
     );
 }
 
@@ -1944,17 +2053,13 @@ fn test_type_without_make_iterator() {
 fn test_type_make_iterator_not_implementing_iterator() {
     err(
         "
-        class Foo {
+        class Foo
+        impl Iterable[Int32] for Foo {
             fun makeIterator() -> Int32 { 0 }
         }
-        fun bar(x: Foo) {
-            for i in x {
-                let x: Foo = i;
-            }
-        }
     ",
-        pos(6, 22),
-        SemError::MakeIteratorReturnType("Int32".into()),
+        pos(4, 13),
+        SemError::ReturnTypeMismatch("Int32".into(), "Iterator[Int32]".into()),
     );
 }
 
@@ -2128,3 +2233,20 @@ fn check_no_type_params_with_generic_type() {
         SemError::WrongNumberTypeParams(1, 0),
     );
 }
+
+#[test]
+fn check_atomic_ref_with_value_type() {
+    ok("fun f() -> AtomicRef[String] { AtomicRef[String](\"a\") }");
+
+    err(
+        "fun f() { AtomicRef[Float64](1.0); }",
+        pos(1, 29),
+        SemError::ReferenceTypeExpected("Float64".into()),
+    );
+
+    err(
+        "fun f(x: AtomicRef[(Int32, Int32)]) {}",
+        pos(1, 10),
+        SemError::ReferenceTypeExpected("(Int32, Int32)".into()),
+    );
+}
@@ -1,17 +1,19 @@
 use parking_lot::{Mutex, RwLock};
 use std::mem;
 use std::ptr;
+use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 
 use crate::compiler;
 use crate::compiler::compile_stub;
 use crate::compiler::dora_stub;
-use crate::compiler::fct::JitFct;
+use crate::compiler::fct::{JitFct, JitFctId};
 use crate::compiler::map::{CodeDescriptor, CodeMap};
 use crate::compiler::native_stub::{self, NativeFct, NativeFctDescriptor, NativeStubs};
 use crate::driver::cmd::Args;
 use crate::error::diag::Diagnostic;
 use crate::gc::{Address, Gc};
+use crate::monitors::Monitors;
 use crate::object::{Ref, Testing};
 use crate::safepoint;
 use crate::stack::DoraToNativeInfo;
@@ -29,8 +31,8 @@ use dora_parser::lexer::File;
 use dora_parser::parser::NodeIdGenerator;
 
 pub use self::class::{
-    find_field_in_class, find_method_in_class, find_methods_in_class, Class, ClassDef, ClassDefId,
-    ClassId, TypeParam,
+    find_field_in_class, find_impl_in_class, find_method_in_class, find_methods_in_class, Class,
+    ClassDef, ClassDefId, ClassId, TypeParam,
 };
 pub use self::cnst::{ConstData, ConstId, ConstValue};
 pub use self::enums::{
@@ -78,6 +80,12 @@ pub fn set_vm(vm: &VM) {
     }
 }
 
+pub fn clear_vm() {
+    unsafe {
+        VM_GLOBAL = ptr::null();
+    }
+}
+
 #[inline(never)]
 pub fn stack_pointer() -> Address {
     let local: i32 = 0;
@@ -90,6 +98,7 @@ pub struct VM<'ast> {
     pub ast: &'ast ast::Ast,
     pub id_generator: NodeIdGenerator,
     pub files: Vec<File>,
+    pub stdlib_files: usize,
     pub diag: Mutex<Diagnostic>,
     pub sym: Mutex<SymTable>,
     pub vips: KnownElements,
@@ -104,6 +113,7 @@ pub struct VM<'ast> {
     pub module_defs: GrowableVec<RwLock<ModuleDef>>, // stores all module definitions
     pub fcts: GrowableVec<RwLock<Fct<'ast>>>,  // stores all function source definitions
     pub jit_fcts: GrowableVec<JitFct>,         // stores all function implementations
+    pub free_jit_fcts: Mutex<Vec<JitFctId>>,   // slots in jit_fcts freed by code collector
     pub fct_defs: GrowableVec<RwLock<FctDef>>, // stores all function definitions
     pub enums: Vec<RwLock<EnumData>>,          // store all enum source definitions
     pub enum_defs: GrowableVec<RwLock<EnumDef>>, // stores all enum definitions
@@ -120,6 +130,9 @@ pub struct VM<'ast> {
     pub trap_stub: Mutex<Address>,
     pub guard_check_stub: Mutex<Address>,
     pub threads: Threads,
+    pub monitors: Monitors,
+    pub active_compilations: AtomicUsize,
+    pub host_fcts: Vec<(String, Address)>, // natives registered by an embedding program
 }
 
 impl<'ast> VM<'ast> {
@@ -129,11 +142,13 @@ impl<'ast> VM<'ast> {
         let empty_module_id: ModuleId = 0.into();
         let empty_trait_id: TraitId = 0.into();
         let empty_fct_id: FctId = 0.into();
+
         let gc = Gc::new(&args);
 
         let vm = Box::new(VM {
             args,
             files: Vec::new(),
+            stdlib_files: 0,
             consts: GrowableVec::new(),
             structs: GrowableVec::new(),
             struct_defs: GrowableVec::new(),
@@ -168,6 +183,8 @@ impl<'ast> VM<'ast> {
 
                 cls: KnownClasses {
                     string_buffer: empty_class_id,
+                    mutex: empty_class_id,
+                    atomic_ref: empty_class_id,
                 },
 
                 mods: KnownModules {
@@ -178,6 +195,14 @@ impl<'ast> VM<'ast> {
                     string_buffer_empty: empty_fct_id,
                     string_buffer_append: empty_fct_id,
                     string_buffer_to_string: empty_fct_id,
+                    mutex_lock: empty_fct_id,
+                    mutex_unlock: empty_fct_id,
+                    format_uint8: empty_fct_id,
+                    format_int32: empty_fct_id,
+                    format_int64: empty_fct_id,
+                    format_float32: empty_fct_id,
+                    format_float64: empty_fct_id,
+                    format_string: empty_fct_id,
                 },
 
                 testing_class: empty_class_id,
@@ -188,6 +213,7 @@ impl<'ast> VM<'ast> {
                 comparable_trait: empty_trait_id,
                 stringable_trait: empty_trait_id,
                 iterator_trait: Mutex::new(None),
+                iterable_trait: Mutex::new(None),
                 zero_trait: empty_trait_id,
 
                 byte_array_def: Mutex::new(None),
@@ -207,6 +233,7 @@ impl<'ast> VM<'ast> {
             sym: Mutex::new(SymTable::new()),
             fcts: GrowableVec::new(),
             jit_fcts: GrowableVec::new(),
+            free_jit_fcts: Mutex::new(Vec::new()),
             fct_defs: GrowableVec::new(),
             code_map: Mutex::new(CodeMap::new()),
             lists: Mutex::new(TypeLists::new()),
@@ -217,6 +244,9 @@ impl<'ast> VM<'ast> {
             trap_stub: Mutex::new(Address::null()),
             guard_check_stub: Mutex::new(Address::null()),
             threads: Threads::new(),
+            monitors: Monitors::new(),
+            active_compilations: AtomicUsize::new(0),
+            host_fcts: Vec::new(),
         });
 
         set_vm(&vm);
@@ -224,6 +254,21 @@ impl<'ast> VM<'ast> {
         vm
     }
 
+    /// compressed references are decided when the heap is reserved
+    pub fn compressed_refs(&self) -> bool {
+        self.gc.compressed_refs()
+    }
+
+    /// return width of references stored in the heap: 4
+    /// with compressed references, otherwise pointer width
+    pub fn ref_width(&self) -> i32 {
+        if self.compressed_refs() {
+            4
+        } else {
+            crate::mem::ptr_width()
+        }
+    }
+
     pub fn run(&self, fct_id: FctId) -> i32 {
         let stack_top = stack_pointer();
         let stack_limit = stack_top.sub(STACK_SIZE);
@@ -421,11 +466,21 @@ impl<'ast> VM<'ast> {
         }
     }
 
+    // classes of the stdlib take precedence over program classes of the same name
+    fn cls_id_by_name(&self, name: &'static str) -> ClassId {
+        let name = self.interner.intern(name);
+        let sym = self.sym.lock();
+
+        sym.lookup_type(name, true)
+            .or_else(|| sym.get_type(name))
+            .and_then(|sym| sym.to_class())
+            .expect("class not found")
+    }
+
     pub fn cls_def_by_name(&self, name: &'static str) -> ClassDefId {
         use crate::semck::specialize::specialize_class_id;
 
-        let name = self.interner.intern(name);
-        let cls_id = self.sym.lock().get_class(name).expect("class not found");
+        let cls_id = self.cls_id_by_name(name);
 
         specialize_class_id(self, cls_id)
     }
@@ -437,8 +492,7 @@ impl<'ast> VM<'ast> {
     ) -> ClassDefId {
         use crate::semck::specialize::specialize_class_id_params;
 
-        let name = self.interner.intern(name);
-        let cls_id = self.sym.lock().get_class(name).expect("class not found");
+        let cls_id = self.cls_id_by_name(name);
 
         specialize_class_id_params(self, cls_id, &cls_type_params)
     }
@@ -643,6 +697,11 @@ impl<'ast> VM<'ast> {
     pub fn file(&self, idx: FileId) -> &File {
         &self.files[idx.0 as usize]
     }
+
+    // the stdlib is always parsed before all other files
+    pub fn is_stdlib_file(&self, idx: FileId) -> bool {
+        (idx.0 as usize) < self.stdlib_files
+    }
 }
 
 unsafe impl<'ast> Sync for VM<'ast> {}
@@ -328,7 +328,7 @@ pub fn find_methods_in_class(
         for &impl_id in &cls.impls {
             let ximpl = vm.impls[impl_id].read();
 
-            if ximpl.class_ty.type_params(vm) != class_type.type_params(vm) {
+            if !ximpl.applies_to(vm, class_type) {
                 continue;
             }
 
@@ -353,6 +353,37 @@ pub fn find_methods_in_class(
     candidates
 }
 
+// finds the impl of the trait for the class or one of its super classes,
+// returns the class type the impl applies to
+pub fn find_impl_in_class(
+    vm: &VM,
+    object_type: BuiltinType,
+    trait_id: TraitId,
+) -> Option<(BuiltinType, ImplId)> {
+    let mut class_type = object_type;
+
+    loop {
+        let cls_id = class_type.cls_id(vm).expect("no class");
+        let cls = vm.classes.idx(cls_id);
+        let cls = cls.read();
+
+        for &impl_id in &cls.impls {
+            let ximpl = vm.impls[impl_id].read();
+
+            if ximpl.trait_id == Some(trait_id) && ximpl.applies_to(vm, class_type) {
+                return Some((class_type, impl_id));
+            }
+        }
+
+        if let Some(parent_class) = cls.parent_class {
+            let type_list = class_type.type_params(vm);
+            class_type = replace_type_param(vm, parent_class, &type_list, &TypeList::empty(), None);
+        } else {
+            return None;
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct TypeParam {
     pub name: Name,
@@ -398,6 +429,9 @@ pub struct ClassDef {
     pub fields: Vec<FieldDef>,
     pub size: InstanceSize,
     pub ref_fields: Vec<i32>,
+    // references inside tuples always use full pointer width,
+    // even with compressed references
+    pub tuple_ref_fields: Vec<i32>,
     pub vtable: Option<VTableBox>,
 }
 
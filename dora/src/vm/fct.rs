@@ -205,6 +205,10 @@ impl<'ast> Fct<'ast> {
         &self.param_types
     }
 
+    pub fn is_extern(&self) -> bool {
+        self.ast.external.is_some()
+    }
+
     pub fn params_without_self(&self) -> &[BuiltinType] {
         if self.has_self() {
             &self.param_types[1..]
@@ -410,6 +414,10 @@ pub enum Intrinsic {
     Float32Neg,
     Float32IsNan,
     Float32Sqrt,
+    Float32RoundToZero,
+    Float32RoundUp,
+    Float32RoundDown,
+    Float32RoundHalfEven,
 
     Float64ToInt32,
     Float64ToInt64,
@@ -428,9 +436,59 @@ pub enum Intrinsic {
     Float64Neg,
     Float64IsNan,
     Float64Sqrt,
+    Float64RoundToZero,
+    Float64RoundUp,
+    Float64RoundDown,
+    Float64RoundHalfEven,
+
+    AtomicInt32Load,
+    AtomicInt32Store,
+    AtomicInt32Exchange,
+    AtomicInt32CompareExchange,
+    AtomicInt32FetchAdd,
+
+    AtomicInt64Load,
+    AtomicInt64Store,
+    AtomicInt64Exchange,
+    AtomicInt64CompareExchange,
+    AtomicInt64FetchAdd,
+
+    AtomicBoolLoad,
+    AtomicBoolStore,
+    AtomicBoolExchange,
+    AtomicBoolCompareExchange,
+
+    AtomicRefLoad,
+    AtomicRefStore,
+    AtomicRefExchange,
+    AtomicRefCompareExchange,
 }
 
 impl Intrinsic {
+    pub fn is_atomic(self) -> bool {
+        match self {
+            Intrinsic::AtomicInt32Load
+            | Intrinsic::AtomicInt32Store
+            | Intrinsic::AtomicInt32Exchange
+            | Intrinsic::AtomicInt32CompareExchange
+            | Intrinsic::AtomicInt32FetchAdd
+            | Intrinsic::AtomicInt64Load
+            | Intrinsic::AtomicInt64Store
+            | Intrinsic::AtomicInt64Exchange
+            | Intrinsic::AtomicInt64CompareExchange
+            | Intrinsic::AtomicInt64FetchAdd
+            | Intrinsic::AtomicBoolLoad
+            | Intrinsic::AtomicBoolStore
+            | Intrinsic::AtomicBoolExchange
+            | Intrinsic::AtomicBoolCompareExchange
+            | Intrinsic::AtomicRefLoad
+            | Intrinsic::AtomicRefStore
+            | Intrinsic::AtomicRefExchange
+            | Intrinsic::AtomicRefCompareExchange => true,
+            _ => false,
+        }
+    }
+
     pub fn result_type(self) -> BytecodeType {
         match self {
             Intrinsic::Int32Add
@@ -509,6 +567,10 @@ impl Intrinsic {
             | Intrinsic::Int32ToFloat32
             | Intrinsic::Int64ToFloat32
             | Intrinsic::Float32Sqrt
+            | Intrinsic::Float32RoundToZero
+            | Intrinsic::Float32RoundUp
+            | Intrinsic::Float32RoundDown
+            | Intrinsic::Float32RoundHalfEven
             | Intrinsic::DemoteFloat64ToFloat32 => BytecodeType::Float32,
             Intrinsic::Float64Add
             | Intrinsic::Float64Sub
@@ -520,6 +582,10 @@ impl Intrinsic {
             | Intrinsic::Int64ToFloat64
             | Intrinsic::ReinterpretInt64AsFloat64
             | Intrinsic::Float64Sqrt
+            | Intrinsic::Float64RoundToZero
+            | Intrinsic::Float64RoundUp
+            | Intrinsic::Float64RoundDown
+            | Intrinsic::Float64RoundHalfEven
             | Intrinsic::PromoteFloat32ToFloat64 => BytecodeType::Float64,
             Intrinsic::BoolEq
             | Intrinsic::ByteEq
@@ -34,10 +34,21 @@ pub struct GlobalData {
     pub initializer: Option<FctId>,
     pub address_init: Address,
     pub address_value: Address,
+
+    // @threadlocal globals have a copy in each thread's table of
+    // thread-locals, offsets are relative to the start of that table
+    pub thread_local: bool,
+    pub thread_local_init: i32,
+    pub thread_local_value: i32,
 }
 
 impl GlobalData {
     pub fn needs_initialization(&self) -> bool {
+        if self.thread_local {
+            // other threads might not have initialized their copy yet
+            return self.initializer.is_some();
+        }
+
         self.initializer.is_some() && !self.is_initialized()
     }
 
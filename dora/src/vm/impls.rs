@@ -4,7 +4,8 @@ use std::ops::Index;
 
 use dora_parser::lexer::position::Position;
 
-use crate::ty::BuiltinType;
+use crate::semck::specialize::specialize_type;
+use crate::ty::{BuiltinType, TypeList};
 use crate::vm::{ClassId, FctId, FileId, TraitId, VM};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -22,6 +23,7 @@ pub struct ImplData {
     pub file: FileId,
     pub pos: Position,
     pub trait_id: Option<TraitId>,
+    pub trait_ty: BuiltinType,
     pub class_ty: BuiltinType,
     pub methods: Vec<FctId>,
 }
@@ -37,6 +39,22 @@ impl ImplData {
             .expect("class_ty not initialized yet.")
     }
 
+    // impl[T] Foo[T] for Bar[T] applies to all instances of Bar,
+    // other impls only to the given type params
+    pub fn is_generic(&self, vm: &VM) -> bool {
+        self.class_ty.contains_type_param(vm)
+    }
+
+    pub fn applies_to(&self, vm: &VM, class_ty: BuiltinType) -> bool {
+        self.is_generic(vm) || self.class_ty.type_params(vm) == class_ty.type_params(vm)
+    }
+
+    // the implemented trait with the type params of the class replaced
+    pub fn trait_ty_for(&self, vm: &VM, class_ty: BuiltinType) -> BuiltinType {
+        let type_params = class_ty.type_params(vm);
+        specialize_type(vm, self.trait_ty, &type_params, &TypeList::empty())
+    }
+
     pub fn find_implements(&self, vm: &VM, fct_id: FctId) -> Option<FctId> {
         for &mtd_id in &self.methods {
             let mtd = vm.fcts.idx(mtd_id);
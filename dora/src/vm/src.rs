@@ -246,7 +246,9 @@ impl IdentType {
 
 #[derive(Debug, Clone)]
 pub struct ForTypeInfo {
-    pub make_iterator: FctId,
+    // makeIterator() and the Iterable type it is called on,
+    // not set when iterating an Iterator directly
+    pub make_iterator: Option<(FctId, BuiltinType)>,
     pub next: FctId,
     pub has_next: FctId,
     pub iterator_type: BuiltinType,
@@ -4,8 +4,9 @@ use std::ops::Index;
 use dora_parser::interner::Name;
 use dora_parser::lexer::position::Position;
 
-use crate::ty::BuiltinType;
-use crate::vm::{FctId, FileId, VM};
+use crate::semck::specialize::replace_trait_type_param;
+use crate::ty::{BuiltinType, TypeList};
+use crate::vm::{FctId, FileId, TypeParam, VM};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct TraitId(u32);
@@ -22,6 +23,7 @@ pub struct TraitData {
     pub file: FileId,
     pub pos: Position,
     pub name: Name,
+    pub type_params: Vec<TypeParam>,
     pub methods: Vec<FctId>,
 }
 
@@ -45,6 +47,7 @@ impl TraitData {
         is_static: bool,
         name: Name,
         replace: Option<BuiltinType>,
+        trait_type_params: &TypeList,
         args: &[BuiltinType],
     ) -> Option<FctId> {
         for &method in &self.methods {
@@ -53,7 +56,13 @@ impl TraitData {
 
             if method.name == name
                 && method.is_static == is_static
-                && params_match(replace, method.params_without_self(), args)
+                && params_match(
+                    vm,
+                    replace,
+                    trait_type_params,
+                    method.params_without_self(),
+                    args,
+                )
             {
                 return Some(method.id);
             }
@@ -64,7 +73,9 @@ impl TraitData {
 }
 
 fn params_match(
+    vm: &VM,
     replace: Option<BuiltinType>,
+    trait_type_params: &TypeList,
     trait_args: &[BuiltinType],
     args: &[BuiltinType],
 ) -> bool {
@@ -77,6 +88,9 @@ fn params_match(
 
         let found = if ty == BuiltinType::This {
             replace.is_none() || replace.unwrap() == other
+        } else if trait_type_params.len() > 0 {
+            let self_ty = replace.unwrap_or(BuiltinType::This);
+            replace_trait_type_param(vm, ty, trait_type_params, self_ty) == other
         } else {
             ty == other
         };
@@ -33,6 +33,7 @@ pub struct KnownElements {
     pub comparable_trait: TraitId,
     pub stringable_trait: TraitId,
     pub iterator_trait: Mutex<Option<TraitId>>,
+    pub iterable_trait: Mutex<Option<TraitId>>,
     pub zero_trait: TraitId,
 
     pub byte_array_def: Mutex<Option<ClassDefId>>,
@@ -49,6 +50,8 @@ pub struct KnownElements {
 #[derive(Debug)]
 pub struct KnownClasses {
     pub string_buffer: ClassId,
+    pub mutex: ClassId,
+    pub atomic_ref: ClassId,
 }
 
 #[derive(Debug)]
@@ -61,6 +64,14 @@ pub struct KnownFunctions {
     pub string_buffer_empty: FctId,
     pub string_buffer_append: FctId,
     pub string_buffer_to_string: FctId,
+    pub mutex_lock: FctId,
+    pub mutex_unlock: FctId,
+    pub format_uint8: FctId,
+    pub format_int32: FctId,
+    pub format_int64: FctId,
+    pub format_float32: FctId,
+    pub format_float64: FctId,
+    pub format_string: FctId,
 }
 
 impl KnownElements {
@@ -68,6 +79,10 @@ impl KnownElements {
         self.iterator_trait.lock().expect("iterator trait not set")
     }
 
+    pub fn iterable(&self) -> TraitId {
+        self.iterable_trait.lock().expect("iterable trait not set")
+    }
+
     pub fn array_ty(&self, vm: &VM, element: BuiltinType) -> BuiltinType {
         let list = TypeList::single(element);
         let list_id = vm.lists.lock().insert(list);
@@ -0,0 +1,215 @@
+use dora::embed::{Args, Builder, Handle, Obj, Str};
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, MutexGuard};
+
+lazy_static! {
+    static ref SERIAL: Mutex<()> = Mutex::new(());
+}
+
+// only one VM can be alive at a time, tests run one after another
+fn serial() -> MutexGuard<'static, ()> {
+    SERIAL.lock()
+}
+
+extern "C" fn host_add(lhs: i32, rhs: i32) -> i32 {
+    lhs + rhs
+}
+
+extern "C" fn host_length(value: Handle<Str>) -> i64 {
+    value.content().len() as i64
+}
+
+#[test]
+fn call_with_primitives() {
+    let _serial = serial();
+    let vm = Builder::new()
+        .source(
+            "foo.dora",
+            "fun add(a: Int32, b: Int64, c: Float64, d: Float32) -> Float64 {
+                a.toFloat64() + b.toFloat64() + c + d.toFloat64()
+            }
+            fun not(a: Bool) -> Bool { !a }
+            fun five() -> Float32 { 5.0F }
+            fun nothing() {}",
+        )
+        .build()
+        .unwrap();
+
+    let add = vm.function("add").unwrap();
+    let result: f64 = vm.call(add, (1, 2i64, 3.5, 0.5f32)).unwrap();
+    assert_eq!(7.0, result);
+
+    let not = vm.function("not").unwrap();
+    assert_eq!(false, vm.call::<_, bool>(not, (true,)).unwrap());
+    assert_eq!(true, vm.call::<_, bool>(not, (false,)).unwrap());
+
+    let five = vm.function("five").unwrap();
+    assert_eq!(5.0, vm.call::<_, f32>(five, ()).unwrap());
+
+    let nothing = vm.function("nothing").unwrap();
+    vm.call::<_, ()>(nothing, ()).unwrap();
+
+    assert!(vm.function("unknown").is_none());
+}
+
+#[test]
+fn call_with_wrong_types() {
+    let _serial = serial();
+    let vm = Builder::new()
+        .source("foo.dora", "fun id(a: Int32) -> Int32 { a }")
+        .build()
+        .unwrap();
+
+    let id = vm.function("id").unwrap();
+    assert!(vm.call::<_, i32>(id, (1i64,)).is_err());
+    assert!(vm.call::<_, i32>(id, (1, 2)).is_err());
+    assert!(vm.call::<_, i64>(id, (1,)).is_err());
+    assert_eq!(1, vm.call::<_, i32>(id, (1,)).unwrap());
+}
+
+#[test]
+fn call_host_natives() {
+    let _serial = serial();
+    let vm = Builder::new()
+        .source(
+            "foo.dora",
+            "@internal fun hostAdd(a: Int32, b: Int32) -> Int32;
+            @internal fun hostLength(value: String) -> Int64;
+            fun compute(value: String) -> Int64 {
+                hostLength(value) + hostAdd(1, 2).toInt64()
+            }",
+        )
+        .native("hostAdd", host_add as *const u8)
+        .native("hostLength", host_length as *const u8)
+        .build()
+        .unwrap();
+
+    let compute = vm.function("compute").unwrap();
+    let value = vm.string("hello");
+    assert_eq!(8, vm.call::<_, i64>(compute, (value,)).unwrap());
+}
+
+#[test]
+fn unresolved_host_native() {
+    let _serial = serial();
+    let result = Builder::new()
+        .source(
+            "foo.dora",
+            "@internal fun hostAdd(a: Int32, b: Int32) -> Int32;",
+        )
+        .build();
+    assert!(result.is_err());
+}
+
+#[test]
+fn errors() {
+    let _serial = serial();
+    let result = Builder::new().source("foo.dora", "fun f( {").build();
+    assert!(result
+        .err()
+        .unwrap()
+        .starts_with("error in foo.dora at 1:8"));
+
+    let result = Builder::new()
+        .source("foo.dora", "fun f() -> Int32 { false }")
+        .build();
+    assert!(result
+        .err()
+        .unwrap()
+        .starts_with("error in foo.dora at 1:18"));
+}
+
+#[test]
+fn returned_objects_survive_gc() {
+    let _serial = serial();
+    let mut args: Args = Default::default();
+    args.flag_gc_verify = true;
+
+    let vm = Builder::new()
+        .args(args)
+        .source(
+            "foo.dora",
+            "fun greet(name: String) -> String {
+                let result = \"hello \" + name;
+                forceCollect();
+                result
+            }
+            fun collect() { forceCollect(); }",
+        )
+        .build()
+        .unwrap();
+
+    let greet = vm.function("greet").unwrap();
+    let collect = vm.function("collect").unwrap();
+
+    let greeting: Handle<Str> = vm.call(greet, (vm.string("world"),)).unwrap();
+    vm.call::<_, ()>(collect, ()).unwrap();
+    assert_eq!(b"hello world", greeting.content());
+
+    let length = vm.scope(|| {
+        let other: Handle<Str> = vm.call(greet, (vm.string("again"),)).unwrap();
+        vm.call::<_, ()>(collect, ()).unwrap();
+        other.content().len()
+    });
+    assert_eq!(11, length);
+    assert_eq!(b"hello world", greeting.content());
+}
+
+#[test]
+fn multiple_vms() {
+    let _serial = serial();
+    for value in 0..3 {
+        let vm = Builder::new()
+            .source("foo.dora", "fun twice(a: Int32) -> Int32 { 2 * a }")
+            .build()
+            .unwrap();
+
+        let twice = vm.function("twice").unwrap();
+        assert_eq!(2 * value, vm.call::<_, i32>(twice, (value,)).unwrap());
+    }
+}
+
+#[test]
+fn call_with_wrong_object_types() {
+    let _serial = serial();
+    let vm = Builder::new()
+        .source(
+            "foo.dora",
+            "fun size(a: Array[Int32]) -> Int64 { a.size() }
+            fun id(a: Object) -> Object { a }
+            fun name() -> String { \"dora\" }",
+        )
+        .build()
+        .unwrap();
+
+    let size = vm.function("size").unwrap();
+    assert!(vm.call::<_, i64>(size, (vm.string("abc"),)).is_err());
+
+    let id = vm.function("id").unwrap();
+    assert!(vm.call::<_, Handle<Str>>(id, (vm.string("abc"),)).is_err());
+    let object: Handle<Obj> = vm.call(id, (vm.string("abc"),)).unwrap();
+    assert!(vm.call::<_, Handle<Obj>>(id, (object,)).is_ok());
+
+    let name = vm.function("name").unwrap();
+    let string: Handle<Str> = vm.call(name, ()).unwrap();
+    assert_eq!(b"dora", string.content());
+    assert!(vm.call::<_, Handle<Obj>>(name, ()).is_ok());
+}
+
+#[test]
+fn one_vm_at_a_time() {
+    let _serial = serial();
+    let vm = Builder::new()
+        .source("foo.dora", "fun f() {}")
+        .build()
+        .unwrap();
+
+    let result = Builder::new().source("bar.dora", "fun g() {}").build();
+    assert!(result.is_err());
+
+    drop(vm);
+    assert!(Builder::new()
+        .source("bar.dora", "fun g() {}")
+        .build()
+        .is_ok());
+}
@@ -0,0 +1,69 @@
+// Shared library used by the tests in this directory, tools/test
+// builds it as target/libffitest.so before running the tests.
+
+#include <stdbool.h>
+#include <stdint.h>
+#include <string.h>
+#include <time.h>
+
+int32_t ffi_add(int32_t a, int32_t b) {
+    return a + b;
+}
+
+// more arguments than argument registers
+int64_t ffi_sum(int64_t a, int64_t b, int64_t c, int64_t d, int64_t e,
+                int64_t f, int64_t g, int64_t h, int64_t i) {
+    return a + b * 2 + c * 3 + d * 4 + e * 5 + f * 6 + g * 7 + h * 8 + i * 9;
+}
+
+double ffi_mixed(int32_t a, double b, float c, int64_t d, uint8_t e) {
+    return a + b + c + d + e;
+}
+
+float ffi_half(float value) {
+    return value / 2.0f;
+}
+
+bool ffi_negate(bool value) {
+    return !value;
+}
+
+int64_t ffi_length(const char *value) {
+    if (value == NULL) {
+        return -1;
+    }
+
+    return strlen(value);
+}
+
+// strings after the argument registers are passed on the stack
+int32_t ffi_compare(int64_t a, int64_t b, int64_t c, int64_t d, int64_t e,
+                    int64_t f, const char *lhs, const char *rhs) {
+    return strcmp(lhs, rhs) + (int32_t)(a + b + c + d + e + f);
+}
+
+void ffi_fill(uint8_t *data, int64_t length, uint8_t value) {
+    memset(data, value, length);
+}
+
+int64_t ffi_checksum(const uint8_t *data, int64_t length) {
+    if (data == NULL) {
+        return -1;
+    }
+
+    int64_t sum = 0;
+
+    for (int64_t i = 0; i < length; i++) {
+        sum = sum * 31 + data[i];
+    }
+
+    return sum;
+}
+
+// blocks for a while before filling the data, other threads
+// collect garbage in the meantime
+void ffi_sleep_fill(uint8_t *data, int64_t length, uint8_t value, int64_t millis) {
+    struct timespec duration = { millis / 1000, (millis % 1000) * 1000000 };
+    nanosleep(&duration, NULL);
+    memset(data, value, length);
+}
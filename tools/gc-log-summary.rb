@@ -0,0 +1,75 @@
+#!/usr/bin/ruby
+
+# Summarizes GC logs written with `--gc-log=<path>`.
+# Usage: tools/gc-log-summary.rb <file>...
+
+require 'json'
+
+PERCENTILES = [50, 90, 99]
+
+def percentile(sorted, p)
+  return 0.0 if sorted.empty?
+  rank = ((p / 100.0) * sorted.size).ceil - 1
+  sorted[[rank, 0].max]
+end
+
+def format_row(name, values)
+  sorted = values.sort
+  columns = PERCENTILES.map { |p| format("p%d=%-9.3f", p, percentile(sorted, p)) }
+  columns.push(format("max=%-9.3f", sorted.last || 0.0))
+  columns.push(format("sum=%.3f", values.sum))
+  format("  %-16s %s", name, columns.join(" "))
+end
+
+def format_size(size)
+  return "#{size}B" if size < 1024
+  return format("%.1fK", size / 1024.0) if size < 1024 * 1024
+  format("%.1fM", size / 1024.0 / 1024.0)
+end
+
+if ARGV.empty?
+  puts "usage: #{$0} <file>..."
+  exit 1
+end
+
+records = []
+
+ARGV.each do |file|
+  File.foreach(file).with_index do |line, idx|
+    next if line.strip.empty?
+
+    begin
+      records.push(JSON.parse(line))
+    rescue JSON::ParserError => e
+      puts "#{file}:#{idx + 1}: invalid record (#{e.message})"
+      exit 1
+    end
+  end
+end
+
+puts "#{records.size} collections"
+
+records.group_by { |r| r["kind"] }.sort.each do |kind, list|
+  puts
+  puts "#{kind}: #{list.size} collections"
+
+  reasons = list.group_by { |r| r["reason"] }.map { |reason, l| "#{reason}=#{l.size}" }
+  puts "  reasons: #{reasons.join(", ")}"
+
+  puts format_row("pause", list.map { |r| r["pause"] })
+
+  phases = list.flat_map { |r| (r["phases"] || {}).keys }.uniq
+  phases.each do |phase|
+    puts format_row(phase, list.map { |r| (r["phases"] || {})[phase] || 0.0 })
+  end
+
+  if list.first.key?("promoted")
+    promoted = list.map { |r| r["promoted"] }.sum
+    puts "  promoted: #{format_size(promoted)} total"
+  end
+
+  # collectors without generations only log the memory in use
+  key = list.first["before"].key?("object") ? "object" : "memory"
+  freed = list.map { |r| r["before"][key] - r["after"][key] }.sum
+  puts "  freed: #{format_size([freed, 0].max)} total"
+end
@@ -8,11 +8,13 @@ require 'timeout'
 
 $config = {
   cannon: '--compiler=cannon',
+  region_stress: '--compiler=cannon --gc=region --gc-stress',
 }
 
 $ARGS = ARGV.clone
 $release = $ARGS.delete("--release") != nil
 $no_capture = $ARGS.delete("--no-capture") != nil
+$gc_region_stress = $ARGS.delete("--gc-region-stress") != nil
 $processors = 0
 
 $ARGS.delete_if do |arg|
@@ -483,6 +485,11 @@ def parse_test_file(file)
     end
   end
 
+  # tests that configure the GC themselves are skipped in stress mode
+  if $gc_region_stress
+    test_case.configs = test_case.vm_args.include?("--gc") ? [] : [:region_stress]
+  end
+
   test_case
 end
 